@@ -0,0 +1,105 @@
+// Compares hit-testing through the canvas spatial index with a linear scan over every
+// segment, on a layer of 100k segments
+use criterion::{Criterion, criterion_group, criterion_main};
+use eframe_template::draw::canvas::{Action, Canvas, SingleStroke};
+use eframe_template::draw::eraser::{self, EraserMode};
+use eframe_template::draw::selection;
+use egui::{Color32, Pos2, Rect, Stroke, Vec2};
+use std::hint::black_box;
+
+const CANVAS_SIZE: f32 = 4000.0;
+const STROKES: usize = 1000;
+const SEGMENTS_PER_STROKE: usize = 100;
+const ERASER_RADIUS: f32 = 10.0;
+const CENTER: Pos2 = Pos2::new(CANVAS_SIZE / 2.0, CANVAS_SIZE / 2.0);
+
+// Small deterministic generator so every run benchmarks the same drawing
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self) -> f32 {
+        self.0 = self
+            .0
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (self.0 >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+// Random walks scattered over the canvas
+fn canvas() -> Canvas {
+    let mut canvas = Canvas::new(Vec2::splat(CANVAS_SIZE));
+    let mut random = Lcg(7);
+
+    for index in 0..STROKES {
+        let mut point = Pos2::new(random.next() * CANVAS_SIZE, random.next() * CANVAS_SIZE);
+        let mut points = vec![point];
+        for _ in 0..SEGMENTS_PER_STROKE {
+            point += Vec2::new(random.next() - 0.5, random.next() - 0.5) * 20.0;
+            points.push(point);
+        }
+
+        Action::AddStroke {
+            stroke: SingleStroke::new(Stroke::new(2.0, Color32::BLACK), points),
+            index,
+            layer: 0,
+        }
+        .execute(&mut canvas);
+    }
+
+    canvas
+}
+
+fn eraser(c: &mut Criterion) {
+    let canvas = canvas();
+    let strokes = canvas.strokes(0).cloned().unwrap_or_default();
+    let pos = CENTER;
+
+    let mut group = c.benchmark_group("eraser");
+    group.bench_function("linear", |b| {
+        b.iter(|| {
+            eraser::erase_actions(
+                EraserMode::Split,
+                &strokes,
+                0,
+                black_box(pos),
+                ERASER_RADIUS,
+            )
+        });
+    });
+    group.bench_function("indexed", |b| {
+        b.iter(|| {
+            let reach = Rect::from_center_size(black_box(pos), Vec2::splat(ERASER_RADIUS * 2.0));
+            eraser::erase_actions_among(
+                EraserMode::Split,
+                &strokes,
+                &canvas.strokes_in(0, reach),
+                0,
+                pos,
+                ERASER_RADIUS,
+            )
+        });
+    });
+    group.finish();
+}
+
+fn marquee(c: &mut Criterion) {
+    let canvas = canvas();
+    let strokes = canvas.strokes(0).cloned().unwrap_or_default();
+    let rect = Rect::from_center_size(CENTER, Vec2::splat(200.0));
+
+    let mut group = c.benchmark_group("marquee");
+    group.bench_function("linear", |b| {
+        b.iter(|| selection::pick_in_rect(&strokes, black_box(rect)));
+    });
+    group.bench_function("indexed", |b| {
+        b.iter(|| {
+            let rect = black_box(rect);
+            selection::pick_in_rect_among(&strokes, canvas.strokes_in(0, rect), rect)
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, eraser, marquee);
+criterion_main!(benches);
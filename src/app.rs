@@ -1,11 +1,28 @@
-use crate::draw::canvas::{self, Canvas, SingleStroke};
+use crate::document::file::{self, DocumentError};
+use crate::document::palette::{PaletteFormat, Palettes};
+use crate::draw::brush::{self, BrushKind, BrushPreset, StrokeCap};
+use crate::draw::canvas;
+use crate::draw::eraser::{self, EraserMode};
+use crate::draw::eyedropper;
+use crate::draw::fill;
+use crate::draw::highlighter;
+use crate::draw::history::History;
+use crate::draw::mesh_cache::StrokeTessellator;
+use crate::draw::pressure::{self, PressureMode};
+use crate::draw::selection::{Gesture, SelectMode, Selection};
+use crate::draw::shapes::{self, Shape, ShapeKind};
+use crate::draw::simplify::{self, Reduction};
+use crate::draw::smoothing::{self, Smoothing};
+use crate::draw::text::{self as text_box, TextAlign};
+use crate::export::png;
+use crate::keymap::{Command, Keymap};
 use crate::modals;
-use crate::toolbar::main::{Tool, toolbar};
-use crate::utils;
-use egui::{Response, Stroke};
+use crate::panels::layers::layers_panel;
+use crate::panels::palette::palette_panel;
+use crate::toolbar::main::{self as toolbar_main, Tool, toolbar};
+use crate::tools::{select, text};
+use egui::{Rect, Response, Stroke, Vec2};
 use egui_file::FileDialog;
-use std::fs::File;
-use std::io::{BufReader, BufWriter};
 
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)] // if we add new fields, give them default values when deserializing old state
@@ -14,11 +31,55 @@ pub struct SimplePaintApp {
     pub canvas: canvas::Canvas,
     pub stroke_type: Stroke,
     pub tool: Tool,
+    pub eraser_mode: EraserMode,
+    pub pressure_mode: PressureMode,
+    pub smoothing: Smoothing,
+    pub stroke_cap: StrokeCap,
+    pub brush_kind: BrushKind,
+    pub brush_presets: Vec<BrushPreset>,
+    // Widest gap the fill tool bridges, in canvas units
+    pub fill_gap: f32,
+    pub text_size: f32,
+    pub text_align: TextAlign,
+    pub select_mode: SelectMode,
     pub history: History,
+    pub keymap: Keymap,
+    pub palettes: Palettes,
+    pub session: modals::SessionSettings,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub preferences: modals::Preferences,
     #[serde(skip_serializing, skip_deserializing)]
     file_dialog: Option<(FileDialog, Dialog)>,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub selection: Selection,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub select_gesture: Option<Gesture>,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub text_edit: Option<text_box::Editing>,
+    // Outcome of the last document command, shown in the status bar
+    #[serde(skip_serializing, skip_deserializing)]
+    pub status: Option<String>,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub canvas_pointer: Option<egui::Pos2>,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub layer_rename: Option<(usize, String)>,
+    // Layer whose opacity slider is being dragged, and its settings before the drag
+    #[serde(skip_serializing, skip_deserializing)]
+    pub layer_opacity_drag: Option<(usize, canvas::LayerSettings)>,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub error: Option<DocumentError>,
+    // Unreadable saved state, written back under `CORRUPT_SESSION_KEY` on the next save
+    #[serde(skip_serializing, skip_deserializing)]
+    corrupt_session: Option<String>,
 }
 
+// Eyedropper swatch, in screen points
+const SWATCH_RADIUS: f32 = 8.0;
+const SWATCH_OFFSET: f32 = 16.0;
+
+// Storage key a corrupt session is moved to, so it can be recovered by hand
+const CORRUPT_SESSION_KEY: &str = "simple_paint_corrupt_session";
+
 impl Default for SimplePaintApp {
     fn default() -> Self {
         Self {
@@ -26,42 +87,146 @@ impl Default for SimplePaintApp {
             canvas: canvas::Canvas::new(egui::Vec2::new(1920.0, 1080.0)),
             stroke_type: egui::Stroke::new(8.0, egui::Color32::BLACK),
             tool: Tool::Pen,
+            eraser_mode: EraserMode::Partial,
+            pressure_mode: PressureMode::Force,
+            smoothing: Smoothing::default(),
+            stroke_cap: StrokeCap::default(),
+            brush_kind: BrushKind::default(),
+            brush_presets: brush::default_presets(),
+            fill_gap: fill::DEFAULT_GAP,
+            text_size: text_box::DEFAULT_SIZE,
+            text_align: TextAlign::default(),
+            select_mode: SelectMode::Rectangle,
             history: History::default(),
+            keymap: Keymap::default(),
+            palettes: Palettes::default(),
+            session: modals::SessionSettings::default(),
+            preferences: modals::Preferences::default(),
             file_dialog: None,
+            selection: Selection::default(),
+            select_gesture: None,
+            text_edit: None,
+            status: None,
+            canvas_pointer: None,
+            layer_rename: None,
+            layer_opacity_drag: None,
+            error: None,
+            corrupt_session: None,
         }
     }
 }
 
 impl SimplePaintApp {
     /// Called once before the first frame.
-    pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
+    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
         // This is also where you can customize the look and feel of egui using
         // `cc.egui_ctx.set_visuals` and `cc.egui_ctx.set_fonts`.
 
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
-        // if let Some(storage) = cc.storage {
-        // eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default()
-        // } else {
+        cc.storage.map(Self::restore).unwrap_or_default()
+    }
+
+    pub fn restore(storage: &dyn eframe::Storage) -> Self {
+        let Some(saved) = storage.get_string(eframe::APP_KEY) else {
+            return Self::default();
+        };
+
+        let Some(mut app) = eframe::get_value::<Self>(storage, eframe::APP_KEY) else {
+            log::warn!("Saved session could not be read, starting with defaults");
+            return Self {
+                corrupt_session: Some(saved),
+                ..Self::default()
+            };
+        };
+
+        if app.session.restore_session {
+            app.canvas.reset_input();
+            app.canvas.rebuild_caches();
+        } else {
+            app.start_fresh();
+        }
+
+        app
+    }
+
+    // Drops the drawing and its history but keeps tools, shortcuts and preferences
+    pub fn start_fresh(&mut self) {
+        self.replace_canvas(Self::default().canvas);
+        self.initial_modal = modals::InitialModal::default();
+    }
+
+    // Swaps in another drawing. Selections, edits and history refer to the old one by index,
+    // so none of them carry over.
+    pub fn replace_canvas(&mut self, canvas: canvas::Canvas) {
+        self.canvas = canvas;
+        self.history.clear();
+        self.selection = Selection::default();
+        self.select_gesture = None;
+        self.text_edit = None;
+        self.layer_rename = None;
+        self.layer_opacity_drag = None;
+    }
 
-        Default::default()
+    pub fn run(&mut self, action: canvas::Action) {
+        self.history.execute(action, &mut self.canvas);
     }
 
-    fn run(&mut self, action: canvas::Action) {
-        action.execute(&mut self.canvas);
-        self.history.undo.push(action);
-        self.history.redo.clear();
+    // A selection drag previews on the canvas outside the history, so undo and redo wait
+    // until it has been recorded
+    pub fn can_undo(&self) -> bool {
+        self.select_gesture.is_none() && self.history.can_undo()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.select_gesture.is_none() && self.history.can_redo()
+    }
+
+    // Undo and redo can shuffle stroke indices, so the selection is dropped.
+    // A text being typed is recorded first, so undo takes it back.
+    pub fn undo(&mut self) {
+        if self.select_gesture.is_some() {
+            return;
+        }
+        text::finish_editing(self);
+        self.history.undo(&mut self.canvas);
+        self.selection.strokes.clear();
+    }
+
+    pub fn redo(&mut self) {
+        if self.select_gesture.is_some() {
+            return;
+        }
+        text::finish_editing(self);
+        self.history.redo(&mut self.canvas);
+        self.selection.strokes.clear();
     }
 
     fn draw(&mut self, response: &Response, painter: &egui::Painter) {
-        if let Some(pen_position) = response.interact_pointer_pos() {
+        if !self.canvas.active_layer_editable() {
+            return;
+        }
+
+        if let Some(cursor) = response.interact_pointer_pos() {
+            // The stabilizer trails the pen behind the cursor; `last_cursor_pos` is the pen
+            let pen_position = self.canvas.last_cursor_pos.map_or(cursor, |pen| {
+                smoothing::lazy_follow(pen, cursor, self.smoothing.stabilizer)
+            });
+
             if response.dragged() {
                 if let Some(prev) = self.canvas.last_cursor_pos {
-                    // Modify this to change resolution. Less tiny segments = lower resolution
                     if prev.distance(pen_position) > 0.0 {
-                        self.canvas
-                            .segments
-                            .push(canvas::Segment::new(prev, pen_position));
+                        let width = self.sample_width(&response.ctx);
+                        let stroke_type = self.stroke_type;
+                        let (cap, kind) = (self.stroke_cap, self.brush_kind);
+                        let stroke = self.canvas.current_stroke.get_or_insert_with(|| {
+                            let mut stroke = canvas::SingleStroke::new(stroke_type, Vec::new());
+                            stroke.cap = cap;
+                            stroke.kind = kind;
+                            stroke.push(prev, width);
+                            stroke
+                        });
+                        stroke.push(pen_position, width);
                     }
                 }
 
@@ -69,64 +234,453 @@ impl SimplePaintApp {
             }
 
             // draw strokes in realtime
-            for seg in &self.canvas.segments {
-                painter.line_segment(seg.segment, self.stroke_type);
+            if let Some(stroke) = &self.canvas.current_stroke {
+                paint_stroke(painter, stroke, stroke.ink());
+            }
+            if response.dragged() && self.smoothing.stabilizer > 0.0 {
+                painter.line_segment(
+                    [pen_position, cursor],
+                    Stroke::new(1.0, egui::Color32::GRAY),
+                );
             }
 
             if response.drag_stopped() {
-                if !self.canvas.segments.is_empty() {
-                    let stroke = canvas::SingleStroke {
-                        stroke: self.stroke_type,
-                        points: std::mem::take(&mut self.canvas.segments),
-                    };
-                    self.run(canvas::Action::AddStroke { stroke });
+                if let Some(stroke) = self.canvas.current_stroke.take() {
+                    let stroke = smoothing::smooth(&stroke, self.smoothing.passes);
+                    let (stroke, reduction) =
+                        simplify::simplify(&stroke, simplify::tolerance(self.canvas.zoom));
+                    self.status = Some(format!("Last stroke: {reduction}"));
+                    let layer = self.canvas.active_layer;
+                    let index = self.canvas.strokes(layer).map_or(0, Vec::len);
+                    self.palettes.use_color(stroke.stroke.color);
+                    self.run(canvas::Action::AddStroke {
+                        stroke,
+                        index,
+                        layer,
+                    });
                 }
                 self.canvas.last_cursor_pos = None;
             }
         }
     }
 
-    fn erase(&mut self, response: &Response) {
-        let mut erase_actions: Vec<canvas::Action> = Vec::new();
+    // Width of the next pen sample, or `None` for a constant-width stroke
+    fn sample_width(&self, ctx: &egui::Context) -> Option<f32> {
+        let width = self.stroke_type.width;
+        let last_width = self
+            .canvas
+            .current_stroke
+            .as_ref()
+            .and_then(|stroke| stroke.widths.last().copied());
+
+        match self.pressure_mode {
+            PressureMode::Constant => None,
+            PressureMode::Force => ctx
+                .input(|i| {
+                    i.events.iter().rev().find_map(|event| match event {
+                        egui::Event::Touch {
+                            force: Some(force), ..
+                        } => Some(*force),
+                        _ => None,
+                    })
+                })
+                .map(|force| pressure::width_from_force(width, force))
+                // Keep the last reading for frames without touch events
+                .or(last_width),
+            PressureMode::Velocity => Some(pressure::width_from_velocity(
+                width,
+                ctx.input(|i| i.pointer.velocity().length()),
+                last_width,
+            )),
+        }
+    }
+
+    // Fills the closed region around `pos` on the active layer with the current color
+    pub fn fill_at(&mut self, pos: egui::Pos2) {
+        if !self.canvas.active_layer_editable() {
+            return;
+        }
+        let layer = self.canvas.active_layer;
+        let Some(source) = self.canvas.layers.get(layer) else {
+            return;
+        };
+
+        let region = fill::find_region(
+            self.canvas.canvas_area,
+            &fill::walls(source),
+            pos,
+            self.fill_gap,
+        );
+        let Some(contours) = region else {
+            self.status = Some("Nothing to fill there".to_owned());
+            return;
+        };
+
+        self.palettes.use_color(self.stroke_type.color);
+        self.run(canvas::Action::AddFill {
+            fill: fill::Fill::new(self.stroke_type.color, contours),
+            index: source.fills.len(),
+            layer,
+        });
+    }
+
+    // Sets the pen color to what is drawn at `pos`
+    pub fn pick_color(&mut self, pos: egui::Pos2, painter: &egui::Painter) {
+        self.stroke_type.color =
+            eyedropper::color_at(&self.canvas, pos, |text| text.painted_bounds(painter));
+    }
 
-        if response.dragged() {
-            if let Some(eraser_pos) = response.interact_pointer_pos() {
-                // let mut retained_segments: Vec<canvas::Segment> = Vec::new();
-                for (idx, stroke) in self.canvas.strokes.iter().enumerate() {
-                    let mut erased = false;
-
-                    let retained_segments: Vec<canvas::Segment> = stroke
-                        .points
-                        .iter()
-                        .filter_map(|segment| {
-                            let segment_eraser_distance =
-                                utils::cursor_to_segment_distance(eraser_pos, segment);
-
-                            if segment_eraser_distance <= self.stroke_type.width {
-                                erased = true;
-                                None
-                            } else {
-                                Some(*segment)
-                            }
-                        })
-                        .collect();
-
-                    if !erased {
-                        continue;
+    // Previews the color under the cursor and picks it while the canvas is pressed
+    fn eyedropper(&mut self, ui: &egui::Ui, response: &Response, painter: &egui::Painter) {
+        ui.output_mut(|o| o.cursor_icon = egui::CursorIcon::Crosshair);
+        let Some(pos) = response.interact_pointer_pos().or(response.hover_pos()) else {
+            return;
+        };
+
+        if response.drag_started() || response.dragged() {
+            self.pick_color(pos, painter);
+        }
+
+        // A swatch next to the cursor, the same size on screen at any zoom
+        let scale = pixels_per_unit(painter) / painter.ctx().pixels_per_point();
+        painter.circle(
+            pos + Vec2::new(SWATCH_OFFSET, -SWATCH_OFFSET) / scale,
+            SWATCH_RADIUS / scale,
+            eyedropper::color_at(&self.canvas, pos, |text| text.painted_bounds(painter)),
+            Stroke::new(1.0 / scale, egui::Color32::GRAY),
+        );
+    }
+
+    fn draw_shape(&mut self, kind: ShapeKind, response: &Response, painter: &egui::Painter) {
+        if !self.canvas.active_layer_editable() {
+            return;
+        }
+
+        if response.drag_started() {
+            self.canvas.shape_anchor = response.interact_pointer_pos();
+        }
+
+        let (Some(anchor), Some(cursor)) =
+            (self.canvas.shape_anchor, response.interact_pointer_pos())
+        else {
+            return;
+        };
+
+        let modifiers = response.ctx.input(|i| i.modifiers);
+        let (from, to) = shapes::drag_points(kind, anchor, cursor, modifiers.shift, modifiers.alt);
+        let shape = Shape::new(kind, self.stroke_type, from, to);
+
+        // live preview while dragging
+        painter.add(shape.to_egui_shape(self.stroke_type));
+
+        if response.drag_stopped() {
+            self.canvas.shape_anchor = None;
+            if from != to {
+                let layer = self.canvas.active_layer;
+                let index = self.canvas.shapes(layer).map_or(0, Vec::len);
+                self.palettes.use_color(shape.stroke.color);
+                self.run(canvas::Action::AddShape {
+                    shape,
+                    index,
+                    layer,
+                });
+            }
+        }
+    }
+
+    fn file_menu(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
+        if ui.button("New").clicked() {
+            self.start_fresh();
+        }
+        if ui.button("Save").clicked() {
+            self.open_dialog(FileDialog::save_file(), Dialog::Save);
+        }
+        if ui.button("Open").clicked() {
+            self.open_dialog(FileDialog::open_file(), Dialog::Open);
+        }
+        ui.menu_button("Export", |ui| {
+            if ui.button("SVG").clicked() {
+                self.open_dialog(FileDialog::save_file(), Dialog::ExportSvg);
+            }
+            ui.menu_button("PNG", |ui| {
+                for scale in png::SCALES {
+                    if ui.button(format!("{scale}x")).clicked() {
+                        self.open_dialog(FileDialog::save_file(), Dialog::ExportPng { scale });
                     }
+                }
+            });
+        });
+        if ui.button("Simplify All Strokes").clicked() {
+            self.simplify_all();
+        }
+        if ui.button("Preferences").clicked() {
+            self.preferences.open = true;
+        }
 
-                    let modified_stroke = SingleStroke {
-                        stroke: stroke.stroke,
-                        points: retained_segments.clone(),
-                    };
+        if ui.button("Quit").clicked() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+    }
 
-                    erase_actions.push(canvas::Action::ModifyStroke {
-                        before: Some(stroke.clone()),
-                        after: Some(modified_stroke),
-                        index: idx,
-                    });
+    // Simplifies every stroke on unlocked layers as one undo step
+    pub fn simplify_all(&mut self) {
+        let tolerance = simplify::tolerance(self.canvas.zoom);
+        let mut total = Reduction::default();
+
+        self.history.begin();
+        for layer in 0..self.canvas.layers.len() {
+            let Some(strokes) = self
+                .canvas
+                .layers
+                .get(layer)
+                .filter(|layer| !layer.locked)
+                .map(|layer| &layer.strokes)
+            else {
+                continue;
+            };
+
+            let mut indices = Vec::new();
+            let mut before = Vec::new();
+            let mut after = Vec::new();
+            for (index, stroke) in strokes.iter().enumerate() {
+                let (simplified, reduction) = simplify::simplify(stroke, tolerance);
+                total += reduction;
+                if reduction.removed() > 0 {
+                    indices.push(index);
+                    before.push(stroke.clone());
+                    after.push(simplified);
                 }
             }
+
+            if !indices.is_empty() {
+                self.run(canvas::Action::ModifyStrokes {
+                    before,
+                    after,
+                    indices,
+                    layer,
+                });
+            }
+        }
+        self.history.commit();
+
+        log::info!("Simplified all strokes: {total}");
+        self.status = Some(format!("Simplified all strokes: {total}"));
+    }
+
+    // Switches the pen to a brush preset; out of range indices are ignored
+    pub fn apply_preset(&mut self, index: usize) {
+        if let Some(preset) = self.brush_presets.get(index) {
+            self.stroke_type = preset.stroke();
+            self.smoothing = preset.smoothing();
+            self.stroke_cap = preset.cap;
+            self.brush_kind = preset.kind;
+        }
+    }
+
+    pub fn import_presets_dialog(&mut self) {
+        self.open_dialog(FileDialog::open_file(), Dialog::ImportPresets);
+    }
+
+    pub fn export_presets_dialog(&mut self) {
+        self.open_dialog(FileDialog::save_file(), Dialog::ExportPresets);
+    }
+
+    pub fn import_palette_dialog(&mut self) {
+        self.open_dialog(FileDialog::open_file(), Dialog::ImportPalette);
+    }
+
+    pub fn export_palette_dialog(&mut self, format: PaletteFormat) {
+        self.open_dialog(FileDialog::save_file(), Dialog::ExportPalette { format });
+    }
+
+    fn open_dialog(&mut self, mut dialog: FileDialog, kind: Dialog) {
+        dialog.open();
+        self.file_dialog = Some((dialog, kind));
+    }
+
+    fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        // Plain letter shortcuts must not fire while typing into a text field
+        if self.initial_modal.active
+            || self.preferences.capturing.is_some()
+            || ctx.wants_keyboard_input()
+        {
+            return;
+        }
+
+        for command in self.keymap.triggered(ctx) {
+            self.run_command(command);
+        }
+
+        // The platform turns the clipboard shortcuts into events instead of key presses
+        let events = ctx.input(|i| i.events.clone());
+        for event in events {
+            match event {
+                egui::Event::Copy => select::copy_selection(self, ctx),
+                egui::Event::Cut => select::cut_selection(self, ctx),
+                egui::Event::Paste(text) => select::paste(self, &text),
+                _ => {}
+            }
+        }
+    }
+
+    pub fn run_command(&mut self, command: Command) {
+        let zoom_step = 1.25;
+
+        match command {
+            Command::Undo => self.undo(),
+            Command::Redo => self.redo(),
+            Command::SelectTool(tool) => self.tool = tool,
+            Command::ApplyPreset(index) => self.apply_preset(index),
+            Command::IncreaseWidth => {
+                self.stroke_type.width = (self.stroke_type.width + 1.0).clamp(
+                    *toolbar_main::WIDTH_RANGE.start(),
+                    *toolbar_main::WIDTH_RANGE.end(),
+                );
+            }
+            Command::DecreaseWidth => {
+                self.stroke_type.width = (self.stroke_type.width - 1.0).clamp(
+                    *toolbar_main::WIDTH_RANGE.start(),
+                    *toolbar_main::WIDTH_RANGE.end(),
+                );
+            }
+            Command::ZoomIn => self.canvas.set_zoom(self.canvas.zoom * zoom_step),
+            Command::ZoomOut => self.canvas.set_zoom(self.canvas.zoom / zoom_step),
+            Command::ZoomFit => self.canvas.set_zoom(canvas::FIT_ZOOM),
+            Command::DeleteSelection => select::delete_selection(self),
+            Command::DuplicateSelection => select::duplicate_selection(self),
+            Command::SimplifyAll => self.simplify_all(),
+            Command::Save => self.open_dialog(FileDialog::save_file(), Dialog::Save),
+            Command::Open => self.open_dialog(FileDialog::open_file(), Dialog::Open),
+        }
+    }
+
+    fn paint_layers(&mut self, painter: &egui::Painter) {
+        let ctx = painter.ctx();
+        let mut tessellator = StrokeTessellator::new(
+            pixels_per_unit(painter),
+            ctx.tessellation_options(|options| *options),
+            ctx.fonts(|fonts| fonts.font_image_size()),
+        );
+
+        for index in 0..self.canvas.layers.len() {
+            if !self
+                .canvas
+                .layers
+                .get(index)
+                .is_some_and(|layer| layer.visible)
+            {
+                continue;
+            }
+            // Fills go beneath the lines outlining them
+            if let Some(layer) = self.canvas.layers.get(index) {
+                for fill in &layer.fills {
+                    painter.add(fill.mesh(fill.color.gamma_multiply(layer.opacity)));
+                }
+            }
+            painter.extend(self.canvas.stroke_shapes(index, &mut tessellator));
+
+            let Some(layer) = self.canvas.layers.get(index) else {
+                continue;
+            };
+            for shape in &layer.shapes {
+                let mut stroke_type = shape.stroke;
+                stroke_type.color = stroke_type.color.gamma_multiply(layer.opacity);
+
+                painter.add(shape.to_egui_shape(stroke_type));
+            }
+
+            // The text being edited is previewed by the editor instead
+            let editing = self
+                .text_edit
+                .as_ref()
+                .and_then(|editing| Some((editing.layer, editing.index?)));
+            for (text_index, text) in layer.texts.iter().enumerate() {
+                if editing != Some((index, text_index)) {
+                    let color = text.color.gamma_multiply(layer.opacity);
+                    painter.galley(text.pos, painter.layout_job(text.layout_job(color)), color);
+                }
+            }
+        }
+    }
+
+    // Hands the pointer on the canvas to the current tool
+    fn use_tool(&mut self, ui: &egui::Ui, response: &Response, painter: &egui::Painter) {
+        if matches!(self.tool, Tool::Select | Tool::Text)
+            && text::edit_on_double_click(self, response, painter)
+        {
+            return;
+        }
+
+        match self.tool {
+            Tool::Select => select::select_tool(self, response),
+            // Holding Alt with the pen picks a color instead, unless a stroke is under way
+            Tool::Pen if ui.input(|i| i.modifiers.alt) && self.canvas.current_stroke.is_none() => {
+                self.eyedropper(ui, response, painter);
+            }
+            Tool::Pen => {
+                ui.output_mut(|o| o.cursor_icon = egui::CursorIcon::Crosshair);
+                self.draw(response, painter);
+            }
+            Tool::Eyedropper => self.eyedropper(ui, response, painter),
+            Tool::Erase => {
+                if let Some(pos) = response.hover_pos() {
+                    painter.circle_stroke(
+                        pos,
+                        self.stroke_type.width,
+                        egui::Stroke::new(1.0, egui::Color32::BLACK),
+                    );
+                }
+                self.erase(response);
+            }
+            Tool::Fill => {
+                ui.output_mut(|o| o.cursor_icon = egui::CursorIcon::Cell);
+                // The canvas only senses drags, which start as soon as it is pressed
+                if response.drag_started()
+                    && let Some(pos) = response.interact_pointer_pos()
+                {
+                    self.fill_at(pos);
+                }
+            }
+            Tool::Text => {
+                ui.output_mut(|o| o.cursor_icon = egui::CursorIcon::Text);
+                text::text_tool(self, response);
+            }
+            Tool::Rectangle | Tool::Ellipse | Tool::Line => {
+                ui.output_mut(|o| o.cursor_icon = egui::CursorIcon::Crosshair);
+                if let Some(kind) = self.tool.shape_kind() {
+                    self.draw_shape(kind, response, painter);
+                }
+            }
+        }
+    }
+
+    fn erase(&mut self, response: &Response) {
+        if !self.canvas.active_layer_editable() {
+            return;
+        }
+        let layer = self.canvas.active_layer;
+
+        // One swipe of the eraser is undone as a whole
+        if response.drag_started() {
+            self.history.begin();
+        }
+
+        let mut erase_actions: Vec<canvas::Action> = Vec::new();
+
+        if response.dragged()
+            && let Some(eraser_pos) = response.interact_pointer_pos()
+            && let Some(strokes) = self.canvas.strokes(layer)
+        {
+            let reach =
+                Rect::from_center_size(eraser_pos, Vec2::splat(self.stroke_type.width * 2.0));
+            erase_actions = eraser::erase_actions_among(
+                self.eraser_mode,
+                strokes,
+                &self.canvas.strokes_in(layer, reach),
+                layer,
+                eraser_pos,
+                self.stroke_type.width,
+            );
         }
         for action in erase_actions {
             self.run(action);
@@ -137,35 +691,37 @@ impl SimplePaintApp {
 impl eframe::App for SimplePaintApp {
     /// Called by the framework to save state before shutdown.
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
-        eframe::set_value(storage, eframe::APP_KEY, self);
+        if let Some(corrupt) = self.corrupt_session.take() {
+            storage.set_string(CORRUPT_SESSION_KEY, corrupt);
+        }
+
+        if self.session.restore_history {
+            eframe::set_value(storage, eframe::APP_KEY, self);
+        } else {
+            let history = std::mem::take(&mut self.history);
+            eframe::set_value(storage, eframe::APP_KEY, self);
+            self.history = history;
+        }
     }
 
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         if self.initial_modal.active {
             modals::initial_modal(ctx, self);
         }
+        if self.error.is_some() {
+            modals::error_modal(ctx, self);
+        }
+        if self.preferences.open {
+            modals::preferences_window(ctx, self);
+        }
+        self.handle_shortcuts(ctx);
 
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             egui::MenuBar::new().ui(ui, |ui| {
                 // NOTE: no File->Quit on web pages!
                 let is_web = cfg!(target_arch = "wasm32");
                 if !is_web {
-                    ui.menu_button("File", |ui| {
-                        if ui.button("Save").clicked() {
-                            let mut dialog = FileDialog::save_file();
-                            dialog.open();
-                            self.file_dialog = Some((dialog, Dialog::Save));
-                        }
-                        if ui.button("Open").clicked() {
-                            let mut dialog = FileDialog::open_file();
-                            dialog.open();
-                            self.file_dialog = Some((dialog, Dialog::Open));
-                        }
-
-                        if ui.button("Quit").clicked() {
-                            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
-                        }
-                    });
+                    ui.menu_button("File", |ui| self.file_menu(ctx, ui));
                     ui.add_space(16.0);
                 }
                 self.run_dialog(ctx);
@@ -181,105 +737,105 @@ impl eframe::App for SimplePaintApp {
                 ui.set_max_height(30.0);
 
                 ui.horizontal_centered(|ui| {
-                    let toolbar_width = 620.0;
+                    let toolbar_width = 820.0;
                     ui.add_space((ui.available_width() / 2.0) - toolbar_width / 2.0);
                     toolbar(self, ui);
                 });
             });
 
+        if let Some(status) = &self.status {
+            egui::TopBottomPanel::bottom("status bar").show(ctx, |ui| {
+                ui.label(status);
+            });
+        }
+
+        egui::SidePanel::left("palette panel")
+            .resizable(false)
+            .show(ctx, |ui| {
+                palette_panel(self, ui);
+            });
+
+        egui::SidePanel::right("layers panel")
+            .resizable(false)
+            .show(ctx, |ui| {
+                layers_panel(self, ui);
+            });
+
         egui::CentralPanel::default()
             .frame(egui::Frame::new().fill(egui::Color32::DARK_GRAY))
             .show(ctx, |ui| {
                 if self.initial_modal.active {
                     return;
                 }
-                let scene = egui::Scene::new().zoom_range(0.01..=10.0);
+                let scene = egui::Scene::new().zoom_range(canvas::ZOOM_RANGE);
                 let scene_response = scene.show(ui, &mut self.canvas.canvas_viewport, |ui| {
                     ui.allocate_painter(self.canvas.canvas_area.size(), egui::Sense::drag())
                 });
 
                 let (response, painter) = scene_response.inner;
                 painter.rect_filled(self.canvas.canvas_area, 0.0, egui::Color32::WHITE);
+                self.canvas_pointer = response.hover_pos();
 
                 if response.hovered() {
-                    match self.tool {
-                        Tool::Pen => {
-                            ui.output_mut(|o| o.cursor_icon = egui::CursorIcon::Crosshair);
-                            self.draw(&response, &painter);
-                        }
-                        Tool::Erase => {
-                            if let Some(pos) = response.hover_pos() {
-                                painter.circle_stroke(
-                                    pos,
-                                    self.stroke_type.width,
-                                    egui::Stroke::new(1.0, egui::Color32::BLACK),
-                                );
-                            }
-                            self.erase(&response);
-                        }
-                    }
+                    self.use_tool(ui, &response, &painter);
+                }
+
+                // Close gestures that ended off the canvas or after switching tools
+                if !response.dragged() && self.history.in_transaction() {
+                    self.history.commit();
+                }
+                if !response.dragged() && self.select_gesture.is_some() {
+                    select::finish_gesture(self);
+                }
+                if self.tool != Tool::Select || !select::selection_editable(self) {
+                    self.selection.strokes.clear();
+                }
+                if self.tool != Tool::Text {
+                    text::finish_editing(self);
                 }
 
                 self.canvas.update_zoom();
 
-                for stroke in &self.canvas.strokes {
-                    for segment in &stroke.points {
-                        painter.line_segment(segment.segment, stroke.stroke);
-                    }
+                self.paint_layers(&painter);
+                if self.tool == Tool::Select {
+                    select::paint_selection(self, &painter);
                 }
-
-                // ui.separator();
-                //
-                // ui.add(egui::github_link_file!(
-                //     "https://github.com/emilk/eframe_template/blob/main/",
-                //     "Source code."
-                // ));
-                // ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
-                //     powered_by_egui_and_eframe(ui);
-                //     egui::warn_if_debug_build(ui);
-                // });
+                text::text_editor(self, &painter);
             });
     }
 }
 
-fn powered_by_egui_and_eframe(ui: &mut egui::Ui) {
-    ui.horizontal(|ui| {
-        ui.spacing_mut().item_spacing.x = 0.0;
-        ui.label("Powered by ");
-        ui.hyperlink_to("egui", "https://github.com/emilk/egui");
-        ui.label(" and ");
-        ui.hyperlink_to(
-            "eframe",
-            "https://github.com/emilk/egui/tree/master/crates/eframe",
-        );
-        ui.label(".");
-    });
-}
-
-#[derive(serde::Deserialize, serde::Serialize, Default)]
-pub struct History {
-    undo: Vec<canvas::Action>,
-    redo: Vec<canvas::Action>,
+// Physical pixels per canvas unit at the current zoom
+fn pixels_per_unit(painter: &egui::Painter) -> f32 {
+    let ctx = painter.ctx();
+    let scaling = ctx
+        .layer_transform_to_global(painter.layer_id())
+        .map_or(1.0, |transform| transform.scaling);
+    scaling * ctx.pixels_per_point()
 }
 
-impl History {
-    pub fn undo(&mut self, canvas: &mut canvas::Canvas) {
-        if let Some(action) = self.undo.pop() {
-            action.undo(canvas);
-            self.redo.push(action);
-        }
-    }
-
-    pub fn redo(&mut self, canvas: &mut canvas::Canvas) {
-        if let Some(action) = self.redo.pop() {
-            action.execute(canvas);
-        }
+// Paints the stroke still being drawn; committed strokes go through the mesh cache instead
+fn paint_stroke(painter: &egui::Painter, stroke: &canvas::SingleStroke, color: egui::Color32) {
+    if stroke.kind == BrushKind::Highlighter {
+        painter.add(highlighter::mesh(stroke, color));
+    } else if stroke.is_tapered() {
+        // Fade the edge out over about one physical pixel, whatever the zoom
+        let feather = 1.0 / pixels_per_unit(painter);
+        painter.add(pressure::stroke_mesh(stroke, color, feather));
+    } else {
+        painter.extend(stroke.line_shapes(color));
     }
 }
 
 enum Dialog {
     Open,
     Save,
+    ExportSvg,
+    ExportPng { scale: f32 },
+    ImportPalette,
+    ExportPalette { format: PaletteFormat },
+    ImportPresets,
+    ExportPresets,
 }
 
 impl SimplePaintApp {
@@ -287,18 +843,38 @@ impl SimplePaintApp {
         if let Some((dialog, action)) = &mut self.file_dialog {
             if dialog.show(ctx).selected() {
                 if let Some(path) = dialog.path() {
-                    match action {
-                        Dialog::Open => {
-                            let file = File::open(path).unwrap();
-                            let reader = BufReader::new(file);
-                            let canvas: Canvas = serde_json::from_reader(reader).unwrap();
-                            self.canvas = canvas;
+                    let result = match action {
+                        // A failed open leaves the current canvas untouched
+                        Dialog::Open => file::open(path).map(|canvas| self.replace_canvas(canvas)),
+                        Dialog::Save => file::save(&path.with_extension("json"), &self.canvas),
+                        Dialog::ExportSvg => {
+                            file::export_svg(&path.with_extension("svg"), &self.canvas)
+                        }
+                        Dialog::ExportPng { scale } => {
+                            file::export_png(&path.with_extension("png"), &self.canvas, *scale)
+                        }
+                        Dialog::ImportPalette => {
+                            file::import_palette(path).map(|palette| self.palettes.insert(palette))
                         }
-                        Dialog::Save => {
-                            let save_file = File::create(path.with_extension("json")).unwrap();
-                            let mut writer = BufWriter::new(save_file);
-                            serde_json::to_writer_pretty(writer, &self.canvas).unwrap();
+                        Dialog::ExportPalette { format } => match self.palettes.active() {
+                            Some(palette) => file::export_palette(
+                                &path.with_extension(format.extension()),
+                                palette,
+                                *format,
+                            ),
+                            None => Ok(()),
+                        },
+                        // Imported presets are added after the current ones
+                        Dialog::ImportPresets => file::import_presets(path)
+                            .map(|presets| self.brush_presets.extend(presets)),
+                        Dialog::ExportPresets => {
+                            file::export_presets(&path.with_extension("json"), &self.brush_presets)
                         }
+                    };
+
+                    if let Err(err) = result {
+                        log::error!("{err}");
+                        self.error = Some(err);
                     }
                 }
 
@@ -0,0 +1,413 @@
+use super::format::FORMAT_VERSION;
+use crate::draw::brush::StrokeCap;
+use crate::draw::canvas::SingleStroke;
+use crate::export::svg;
+use egui::{Color32, Pos2, Rect, Stroke, Vec2};
+use std::fmt::Write as _;
+
+pub const CLIPBOARD_TYPE: &str = "simple_paint_strokes";
+// Id of the SVG <metadata> element carrying the app's own stroke data
+const METADATA_ID: &str = "simple_paint";
+// Line segments used to approximate each curve command of pasted SVG paths
+const CURVE_STEPS: usize = 8;
+
+#[derive(serde::Deserialize, serde::Serialize)]
+struct ClipboardStrokes {
+    #[serde(rename = "type")]
+    clipboard_type: String,
+    version: u64,
+    strokes: Vec<SingleStroke>,
+}
+
+// Copied strokes as an SVG document, with the exact strokes embedded as JSON metadata
+// so another window of the app can paste them back losslessly
+pub fn strokes_to_clipboard(strokes: &[SingleStroke]) -> String {
+    let bounds = strokes_bounds(strokes).unwrap_or(Rect::ZERO);
+    let data = ClipboardStrokes {
+        clipboard_type: CLIPBOARD_TYPE.to_owned(),
+        version: FORMAT_VERSION,
+        strokes: strokes.to_vec(),
+    };
+    let json = serde_json::to_string(&data).unwrap_or_default();
+
+    let mut text = String::new();
+    writeln!(
+        text,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}" width="{}" height="{}">"#,
+        bounds.min.x,
+        bounds.min.y,
+        bounds.width(),
+        bounds.height(),
+        bounds.width(),
+        bounds.height(),
+    )
+    .ok();
+    writeln!(
+        text,
+        r#"  <metadata id="{METADATA_ID}">{}</metadata>"#,
+        svg::escape(&json)
+    )
+    .ok();
+    for stroke in strokes {
+        if let Some(path) = svg::stroke_to_path(stroke) {
+            writeln!(text, "  {path}").ok();
+        }
+    }
+    text.push_str("</svg>\n");
+
+    text
+}
+
+// Reads strokes copied by the app, or falls back to the paths of any SVG markup or
+// bare path data. Strokes without their own style use `default_stroke`.
+pub fn strokes_from_clipboard(text: &str, default_stroke: Stroke) -> Option<Vec<SingleStroke>> {
+    if let Some(strokes) = embedded_strokes(text) {
+        return Some(strokes);
+    }
+
+    let trimmed = text.trim();
+    let strokes: Vec<SingleStroke> = if trimmed.starts_with('<') {
+        elements(trimmed, "path")
+            .flat_map(|element| {
+                let cap = attribute(element, "stroke-linecap")
+                    .and_then(|value| StrokeCap::from_svg_name(&value))
+                    .unwrap_or_default();
+                attribute(element, "d")
+                    .map(|data| {
+                        path_to_strokes(&data, element_stroke(element, default_stroke), cap)
+                    })
+                    .unwrap_or_default()
+            })
+            .collect()
+    } else if trimmed.starts_with(['M', 'm']) {
+        path_to_strokes(trimmed, default_stroke, StrokeCap::default())
+    } else {
+        Vec::new()
+    };
+
+    (!strokes.is_empty()).then_some(strokes)
+}
+
+fn embedded_strokes(text: &str) -> Option<Vec<SingleStroke>> {
+    let json = if text.trim_start().starts_with('{') {
+        text.to_owned()
+    } else {
+        let start = text.find(&format!(r#"<metadata id="{METADATA_ID}">"#))?;
+        let content = text.get(start..)?.split_once('>')?.1;
+        unescape(content.split_once("</metadata>")?.0)
+    };
+
+    let data: ClipboardStrokes = serde_json::from_str(&json).ok()?;
+    (data.clipboard_type == CLIPBOARD_TYPE).then_some(data.strokes)
+}
+
+// Moves `strokes` so they are centered on `cursor`, keeping them inside `area` where they fit
+pub fn place_at(strokes: &[SingleStroke], cursor: Pos2, area: Rect) -> Vec<SingleStroke> {
+    let Some(bounds) = strokes_bounds(strokes) else {
+        return strokes.to_vec();
+    };
+
+    let target = bounds.translate(area.clamp(cursor) - bounds.center());
+    let nudge = |min: f32, max: f32, area_min: f32, area_max: f32| {
+        if max - min > area_max - area_min {
+            0.0
+        } else if min < area_min {
+            area_min - min
+        } else if max > area_max {
+            area_max - max
+        } else {
+            0.0
+        }
+    };
+    let offset = target.min - bounds.min
+        + Vec2::new(
+            nudge(target.min.x, target.max.x, area.min.x, area.max.x),
+            nudge(target.min.y, target.max.y, area.min.y, area.max.y),
+        );
+
+    strokes
+        .iter()
+        .map(|stroke| {
+            let mut stroke = stroke.clone();
+            for point in &mut stroke.points {
+                *point += offset;
+            }
+            stroke
+        })
+        .collect()
+}
+
+fn strokes_bounds(strokes: &[SingleStroke]) -> Option<Rect> {
+    let mut bounds = Rect::NOTHING;
+    for point in strokes.iter().flat_map(|stroke| &stroke.points) {
+        bounds.extend_with(*point);
+    }
+
+    bounds.is_finite().then_some(bounds)
+}
+
+// Start tags of every `<name ...>` element in `text`
+fn elements<'a>(text: &'a str, name: &'a str) -> impl Iterator<Item = &'a str> {
+    text.split('<').filter_map(move |tag| {
+        let rest = tag.strip_prefix(name)?;
+        rest.starts_with(|c: char| c.is_whitespace() || c == '/' || c == '>')
+            .then(|| tag.split('>').next().unwrap_or(tag))
+    })
+}
+
+fn attribute(element: &str, name: &str) -> Option<String> {
+    let mut rest = element;
+    while let Some(position) = rest.find(name) {
+        let preceded_by_space = rest
+            .get(..position)
+            .and_then(|before| before.chars().last())
+            .is_some_and(char::is_whitespace);
+        let after = rest.get(position + name.len()..)?;
+        rest = after;
+
+        if let Some(value) = after.trim_start().strip_prefix('=')
+            && preceded_by_space
+        {
+            let value = value.trim_start();
+            let quote = value.chars().next()?;
+            let value = value.get(quote.len_utf8()..)?;
+            return Some(unescape(value.split(quote).next()?));
+        }
+    }
+    None
+}
+
+fn element_stroke(element: &str, default_stroke: Stroke) -> Stroke {
+    let mut stroke = default_stroke;
+
+    if let Some(color) = attribute(element, "stroke").and_then(|value| parse_color(&value)) {
+        let opacity = attribute(element, "stroke-opacity")
+            .and_then(|value| value.parse::<f32>().ok())
+            .unwrap_or(1.0);
+        let [r, g, b, _] = color.to_array();
+        stroke.color =
+            Color32::from_rgba_unmultiplied(r, g, b, (opacity.clamp(0.0, 1.0) * 255.0) as u8);
+    }
+    if let Some(width) = attribute(element, "stroke-width")
+        .and_then(|value| value.trim_end_matches("px").parse::<f32>().ok())
+    {
+        stroke.width = width;
+    }
+
+    stroke
+}
+
+fn parse_color(value: &str) -> Option<Color32> {
+    let hex = value.trim().strip_prefix('#')?;
+    match hex.len() {
+        6 => Color32::from_hex(&format!("#{hex}")).ok(),
+        3 => {
+            let long: String = hex.chars().flat_map(|c| [c, c]).collect();
+            Color32::from_hex(&format!("#{long}")).ok()
+        }
+        _ => None,
+    }
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&quot;", "\"")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+// One stroke per subpath, since strokes are single polylines
+fn path_to_strokes(data: &str, stroke: Stroke, cap: StrokeCap) -> Vec<SingleStroke> {
+    parse_path(data)
+        .into_iter()
+        .filter(|subpath| subpath.len() >= 2)
+        .map(|subpath| SingleStroke {
+            cap,
+            ..SingleStroke::new(stroke, subpath)
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Token {
+    Command(char),
+    Number(f32),
+}
+
+fn tokenize(data: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut number = String::new();
+    let flush = |number: &mut String, tokens: &mut Vec<Token>| {
+        if let Ok(value) = number.parse() {
+            tokens.push(Token::Number(value));
+        }
+        number.clear();
+    };
+
+    for c in data.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            '.' => {
+                // "1.5.5" is shorthand for "1.5 .5"
+                if number.contains('.') && !number.contains(['e', 'E']) {
+                    flush(&mut number, &mut tokens);
+                }
+                number.push(c);
+            }
+            '-' | '+' => {
+                if !number.ends_with(['e', 'E']) {
+                    flush(&mut number, &mut tokens);
+                }
+                number.push(c);
+            }
+            'e' | 'E' if !number.is_empty() => number.push(c),
+            c if c.is_ascii_alphabetic() => {
+                flush(&mut number, &mut tokens);
+                tokens.push(Token::Command(c));
+            }
+            _ => flush(&mut number, &mut tokens),
+        }
+    }
+    flush(&mut number, &mut tokens);
+
+    tokens
+}
+
+// Flattens SVG path data into polylines, one per subpath. Curves are sampled, smooth
+// curves reuse their explicit control point, and arcs become a line to their end point.
+fn parse_path(data: &str) -> Vec<Vec<Pos2>> {
+    let tokens = tokenize(data);
+    let mut subpaths: Vec<Vec<Pos2>> = Vec::new();
+    let mut current = Pos2::ZERO;
+    let mut start = Pos2::ZERO;
+    let mut command = 'M';
+    let mut index = 0;
+
+    while index < tokens.len() {
+        if let Some(Token::Command(c)) = tokens.get(index) {
+            command = *c;
+            index += 1;
+        }
+
+        let relative = command.is_ascii_lowercase();
+        let base = if relative {
+            current.to_vec2()
+        } else {
+            Vec2::ZERO
+        };
+        let point = |x: f32, y: f32| Pos2::new(x, y) + base;
+
+        match command.to_ascii_uppercase() {
+            'Z' => {
+                if let Some(subpath) = subpaths.last_mut() {
+                    subpath.push(start);
+                }
+                current = start;
+                // A repeated Z has no numbers to consume
+                if !matches!(tokens.get(index), Some(Token::Command(_))) {
+                    index += 1;
+                }
+                continue;
+            }
+            'M' => {
+                let Some(&[x, y]) = take_numbers(&tokens, &mut index, 2).as_deref() else {
+                    break;
+                };
+                current = point(x, y);
+                start = current;
+                subpaths.push(vec![current]);
+                // Further coordinate pairs after a moveto are implicit linetos
+                command = if relative { 'l' } else { 'L' };
+                continue;
+            }
+            'L' | 'T' => {
+                let Some(&[x, y]) = take_numbers(&tokens, &mut index, 2).as_deref() else {
+                    break;
+                };
+                current = point(x, y);
+            }
+            'H' => {
+                let Some(&[x]) = take_numbers(&tokens, &mut index, 1).as_deref() else {
+                    break;
+                };
+                current = Pos2::new(x + base.x, current.y);
+            }
+            'V' => {
+                let Some(&[y]) = take_numbers(&tokens, &mut index, 1).as_deref() else {
+                    break;
+                };
+                current = Pos2::new(current.x, y + base.y);
+            }
+            'C' => {
+                let Some(&[x1, y1, x2, y2, x, y]) = take_numbers(&tokens, &mut index, 6).as_deref()
+                else {
+                    break;
+                };
+                let (from, c1, c2, to) = (current, point(x1, y1), point(x2, y2), point(x, y));
+                push_curve(&mut subpaths, |t| cubic([from, c1, c2, to], t));
+                current = to;
+                continue;
+            }
+            'S' | 'Q' => {
+                let Some(&[x1, y1, x, y]) = take_numbers(&tokens, &mut index, 4).as_deref() else {
+                    break;
+                };
+                let (from, control, to) = (current, point(x1, y1), point(x, y));
+                push_curve(&mut subpaths, |t| quadratic([from, control, to], t));
+                current = to;
+                continue;
+            }
+            'A' => {
+                let Some(&[_, _, _, _, _, x, y]) = take_numbers(&tokens, &mut index, 7).as_deref()
+                else {
+                    break;
+                };
+                current = point(x, y);
+            }
+            _ => break,
+        }
+
+        match subpaths.last_mut() {
+            Some(subpath) => subpath.push(current),
+            None => subpaths.push(vec![current]),
+        }
+    }
+
+    subpaths
+}
+
+fn take_numbers(tokens: &[Token], index: &mut usize, count: usize) -> Option<Vec<f32>> {
+    let values: Option<Vec<f32>> = (0..count)
+        .map(|offset| match tokens.get(*index + offset) {
+            Some(Token::Number(value)) => Some(*value),
+            _ => None,
+        })
+        .collect();
+    if values.is_some() {
+        *index += count;
+    }
+    values
+}
+
+fn cubic([from, c1, c2, to]: [Pos2; 4], t: f32) -> Pos2 {
+    let u = 1.0 - t;
+    (from.to_vec2() * u * u * u
+        + c1.to_vec2() * 3.0 * u * u * t
+        + c2.to_vec2() * 3.0 * u * t * t
+        + to.to_vec2() * t * t * t)
+        .to_pos2()
+}
+
+fn quadratic([from, control, to]: [Pos2; 3], t: f32) -> Pos2 {
+    let u = 1.0 - t;
+    (from.to_vec2() * u * u + control.to_vec2() * 2.0 * u * t + to.to_vec2() * t * t).to_pos2()
+}
+
+fn push_curve(subpaths: &mut Vec<Vec<Pos2>>, curve: impl Fn(f32) -> Pos2) {
+    if subpaths.is_empty() {
+        subpaths.push(vec![curve(0.0)]);
+    }
+    if let Some(subpath) = subpaths.last_mut() {
+        subpath.extend((1..=CURVE_STEPS).map(|step| curve(step as f32 / CURVE_STEPS as f32)));
+    }
+}
@@ -0,0 +1,205 @@
+use super::format::Document;
+use super::migrations;
+use super::palette::{self, Palette, PaletteError, PaletteFormat};
+use crate::draw::brush::{self, BrushPreset, PresetPack};
+use crate::draw::canvas::Canvas;
+use crate::export::{png, svg};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum DocumentError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+    VersionMismatch { found: u64, supported: u64 },
+    UnknownType(String),
+    Encode(::png::EncodingError),
+    Palette(PaletteError),
+    Presets(serde_json::Error),
+}
+
+impl fmt::Display for DocumentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "Could not access the file: {err}"),
+            Self::Parse(err) => write!(f, "The file is not a valid drawing: {err}"),
+            Self::VersionMismatch { found, supported } => write!(
+                f,
+                "The file uses format version {found}, but only versions up to {supported} are supported"
+            ),
+            Self::UnknownType(found) => {
+                write!(
+                    f,
+                    "The file is not a Simple Paint drawing (type \"{found}\")"
+                )
+            }
+            Self::Encode(err) => write!(f, "Could not encode the image: {err}"),
+            Self::Palette(err) => write!(f, "{err}"),
+            Self::Presets(err) => write!(f, "The file is not a valid preset pack: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for DocumentError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Parse(err) | Self::Presets(err) => Some(err),
+            Self::Encode(err) => Some(err),
+            Self::Palette(err) => Some(err),
+            Self::VersionMismatch { .. } | Self::UnknownType(_) => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for DocumentError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for DocumentError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Parse(err)
+    }
+}
+
+impl From<::png::EncodingError> for DocumentError {
+    fn from(err: ::png::EncodingError) -> Self {
+        Self::Encode(err)
+    }
+}
+
+impl From<PaletteError> for DocumentError {
+    fn from(err: PaletteError) -> Self {
+        Self::Palette(err)
+    }
+}
+
+/// Reads a canvas from a JSON document, upgrading files written by older versions.
+///
+/// # Errors
+///
+/// Fails if the file can't be read, isn't a valid document, or was written by a newer version.
+pub fn open(path: &Path) -> Result<Canvas, DocumentError> {
+    let reader = BufReader::new(File::open(path)?);
+
+    from_value(serde_json::from_reader(reader)?)
+}
+
+/// Parses a document of any supported version.
+///
+/// # Errors
+///
+/// Fails if the value isn't a valid document or was written by a newer version.
+pub fn from_value(value: serde_json::Value) -> Result<Canvas, DocumentError> {
+    let document: Document = serde_json::from_value(migrations::migrate(value)?)?;
+
+    Ok(document.into_canvas())
+}
+
+/// Writes the canvas as a JSON document in the current format version.
+///
+/// # Errors
+///
+/// Fails if the file can't be created or written.
+pub fn save(path: &Path, canvas: &Canvas) -> Result<(), DocumentError> {
+    let writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(writer, &Document::from_canvas(canvas))?;
+
+    Ok(())
+}
+
+/// Writes the canvas as an SVG image.
+///
+/// # Errors
+///
+/// Fails if the file can't be written.
+pub fn export_svg(path: &Path, canvas: &Canvas) -> Result<(), DocumentError> {
+    std::fs::write(path, svg::canvas_to_svg(canvas))?;
+
+    Ok(())
+}
+
+/// Writes the canvas as a PNG image at `scale` pixels per canvas unit.
+///
+/// # Errors
+///
+/// Fails if the image can't be encoded or the file can't be written.
+pub fn export_png(path: &Path, canvas: &Canvas, scale: f32) -> Result<(), DocumentError> {
+    std::fs::write(path, png::canvas_to_png(canvas, scale)?)?;
+
+    Ok(())
+}
+
+/// Reads a GIMP or Adobe palette, picking the format from the file extension.
+///
+/// # Errors
+///
+/// Fails if the file can't be read, has another extension, or isn't a valid palette.
+pub fn import_palette(path: &Path) -> Result<Palette, DocumentError> {
+    let format = PaletteFormat::from_path(path).ok_or_else(|| {
+        let extension = path.extension().unwrap_or_default();
+        PaletteError::UnknownFormat(extension.to_string_lossy().into_owned())
+    })?;
+    let fallback_name = path.file_stem().map_or_else(
+        || "Imported".to_owned(),
+        |stem| stem.to_string_lossy().into_owned(),
+    );
+
+    let palette = match format {
+        PaletteFormat::Gpl => palette::from_gpl(&std::fs::read_to_string(path)?, &fallback_name)?,
+        PaletteFormat::Ase => palette::from_ase(&std::fs::read(path)?, &fallback_name)?,
+    };
+    Ok(palette)
+}
+
+/// Writes a palette as a GIMP or Adobe palette file.
+///
+/// # Errors
+///
+/// Fails if the file can't be written.
+pub fn export_palette(
+    path: &Path,
+    palette: &Palette,
+    format: PaletteFormat,
+) -> Result<(), DocumentError> {
+    match format {
+        PaletteFormat::Gpl => std::fs::write(path, palette::to_gpl(palette))?,
+        PaletteFormat::Ase => std::fs::write(path, palette::to_ase(palette))?,
+    }
+
+    Ok(())
+}
+
+/// Reads a preset pack written by `export_presets`.
+///
+/// # Errors
+///
+/// Fails if the file can't be read, isn't a valid pack, or was written by a newer version.
+pub fn import_presets(path: &Path) -> Result<Vec<BrushPreset>, DocumentError> {
+    let reader = BufReader::new(File::open(path)?);
+    let pack: PresetPack = serde_json::from_reader(reader).map_err(DocumentError::Presets)?;
+    if pack.version > brush::PACK_VERSION {
+        return Err(DocumentError::VersionMismatch {
+            found: pack.version,
+            supported: brush::PACK_VERSION,
+        });
+    }
+
+    Ok(pack.presets)
+}
+
+/// Writes brush presets as a JSON preset pack.
+///
+/// # Errors
+///
+/// Fails if the file can't be created or written.
+pub fn export_presets(path: &Path, presets: &[BrushPreset]) -> Result<(), DocumentError> {
+    let writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(writer, &PresetPack::new(presets.to_vec()))?;
+
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use crate::draw::canvas::{Canvas, Layer};
+use egui::Rect;
+
+// Written into every document so other JSON files are rejected instead of misread
+pub const DOCUMENT_TYPE: &str = "simple_paint";
+
+// Bump this and add a step to `migrations::MIGRATIONS` whenever `Document` changes shape
+pub const FORMAT_VERSION: u64 = 7;
+
+// On-disk representation of a drawing. Only persistent data lives here; view state such as
+// the viewport and zoom, and in-progress input, stay on `Canvas`.
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub struct Document {
+    #[serde(rename = "type")]
+    pub document_type: String,
+    pub version: u64,
+    pub canvas_area: Rect,
+    pub active_layer: usize,
+    pub layers: Vec<Layer>,
+}
+
+impl Document {
+    pub fn from_canvas(canvas: &Canvas) -> Self {
+        Self {
+            document_type: DOCUMENT_TYPE.to_owned(),
+            version: FORMAT_VERSION,
+            canvas_area: canvas.canvas_area,
+            active_layer: canvas.active_layer,
+            layers: canvas.layers.clone(),
+        }
+    }
+
+    pub fn into_canvas(self) -> Canvas {
+        let mut canvas = Canvas::new(self.canvas_area.size());
+        canvas.canvas_area = self.canvas_area;
+        canvas.layers = self.layers;
+        if canvas.layers.is_empty() {
+            canvas.layers.push(Layer::new("Layer 1"));
+        }
+        canvas.active_layer = self.active_layer.min(canvas.layers.len() - 1);
+        canvas.rebuild_caches();
+
+        canvas
+    }
+}
@@ -0,0 +1,250 @@
+use super::file::DocumentError;
+use super::format::{DOCUMENT_TYPE, FORMAT_VERSION};
+use serde_json::{Map, Value, json};
+
+type Migration = fn(Map<String, Value>) -> Result<Map<String, Value>, DocumentError>;
+
+// `MIGRATIONS[n]` upgrades a version `n` document to version `n + 1`
+const MIGRATIONS: [Migration; FORMAT_VERSION as usize] = [
+    v0_to_v1, v1_to_v2, v2_to_v3, v3_to_v4, v4_to_v5, v5_to_v6, v6_to_v7,
+];
+
+/// Upgrades a parsed document of any supported version to `FORMAT_VERSION`.
+///
+/// # Errors
+///
+/// Fails if the document is malformed, of another type, or newer than this build.
+pub fn migrate(value: Value) -> Result<Value, DocumentError> {
+    let Value::Object(mut document) = value else {
+        return Err(malformed("expected a JSON object"));
+    };
+
+    let version = document_version(&document)?;
+
+    for migration in MIGRATIONS.iter().skip(version as usize) {
+        document = migration(document)?;
+    }
+
+    Ok(Value::Object(document))
+}
+
+fn document_version(document: &Map<String, Value>) -> Result<u64, DocumentError> {
+    let Some(version) = document.get("version") else {
+        // Files written before versioning was introduced are raw `Canvas` dumps
+        return Ok(0);
+    };
+    let Some(version) = version.as_u64() else {
+        return Err(malformed("version must be a number"));
+    };
+
+    if version > FORMAT_VERSION {
+        return Err(DocumentError::VersionMismatch {
+            found: version,
+            supported: FORMAT_VERSION,
+        });
+    }
+
+    match document.get("type").and_then(Value::as_str) {
+        Some(DOCUMENT_TYPE) => Ok(version),
+        other => Err(DocumentError::UnknownType(
+            other.unwrap_or_default().to_owned(),
+        )),
+    }
+}
+
+// v0 was the serialized `Canvas`, first with a flat `strokes` list and later with `layers`,
+// along with transient fields that v1 drops
+fn v0_to_v1(mut canvas: Map<String, Value>) -> Result<Map<String, Value>, DocumentError> {
+    let canvas_area = canvas
+        .remove("canvas_area")
+        .ok_or_else(|| malformed("missing canvas_area"))?;
+
+    let layers = canvas.remove("layers").unwrap_or_else(|| {
+        let strokes = canvas.remove("strokes").unwrap_or_else(|| json!([]));
+        json!([{
+            "name": "Layer 1",
+            "visible": true,
+            "locked": false,
+            "opacity": 1.0,
+            "strokes": strokes,
+        }])
+    });
+    let active_layer = canvas.remove("active_layer").unwrap_or_else(|| json!(0));
+
+    let Value::Object(document) = json!({
+        "type": DOCUMENT_TYPE,
+        "version": 1,
+        "canvas_area": canvas_area,
+        "active_layer": active_layer,
+        "layers": layers,
+    }) else {
+        return Err(malformed("could not build version 1 document"));
+    };
+
+    Ok(document)
+}
+
+// v2 segments can carry their own `widths`. Older builds would drop them silently, so the
+// version goes up to make those builds refuse the file; v1 segments all use the stroke width,
+// which is what a segment without widths means.
+fn v1_to_v2(mut document: Map<String, Value>) -> Result<Map<String, Value>, DocumentError> {
+    strokes_mut(&mut document)?;
+
+    document.insert("version".to_owned(), json!(2));
+    Ok(document)
+}
+
+// v2 stored strokes as lists of two-point segments; v3 stores one polyline per stroke.
+// Segments that don't continue from the previous one start a new stroke.
+fn v2_to_v3(mut document: Map<String, Value>) -> Result<Map<String, Value>, DocumentError> {
+    let Some(Value::Array(layers)) = document.get_mut("layers") else {
+        return Err(malformed("missing layers"));
+    };
+
+    for layer in layers {
+        let Some(Value::Array(strokes)) = layer.get_mut("strokes") else {
+            return Err(malformed("layer without strokes"));
+        };
+        let mut polylines = Vec::new();
+        for stroke in strokes.iter() {
+            polylines.extend(segments_to_polylines(stroke)?);
+        }
+        *strokes = polylines;
+    }
+
+    document.insert("version".to_owned(), json!(3));
+    Ok(document)
+}
+
+// v4 layers can have `fills`; layers written before have none
+fn v3_to_v4(mut document: Map<String, Value>) -> Result<Map<String, Value>, DocumentError> {
+    for layer in layers_mut(&mut document)? {
+        layer.entry("fills").or_insert_with(|| json!([]));
+    }
+
+    document.insert("version".to_owned(), json!(4));
+    Ok(document)
+}
+
+// v5 layers can have `texts`, added the same way as fills in v4
+fn v4_to_v5(mut document: Map<String, Value>) -> Result<Map<String, Value>, DocumentError> {
+    for layer in layers_mut(&mut document)? {
+        layer.entry("texts").or_insert_with(|| json!([]));
+    }
+
+    document.insert("version".to_owned(), json!(5));
+    Ok(document)
+}
+
+// v6 strokes have a `cap`; everything before was drawn with round ends
+fn v5_to_v6(mut document: Map<String, Value>) -> Result<Map<String, Value>, DocumentError> {
+    for stroke in strokes_mut(&mut document)? {
+        stroke.entry("cap").or_insert_with(|| json!("Round"));
+    }
+
+    document.insert("version".to_owned(), json!(6));
+    Ok(document)
+}
+
+// v7 strokes have a brush `kind`; everything before was drawn with the pen
+fn v6_to_v7(mut document: Map<String, Value>) -> Result<Map<String, Value>, DocumentError> {
+    for stroke in strokes_mut(&mut document)? {
+        stroke.entry("kind").or_insert_with(|| json!("Pen"));
+    }
+
+    document.insert("version".to_owned(), json!(7));
+    Ok(document)
+}
+
+fn layers_mut(
+    document: &mut Map<String, Value>,
+) -> Result<Vec<&mut Map<String, Value>>, DocumentError> {
+    let Some(Value::Array(layers)) = document.get_mut("layers") else {
+        return Err(malformed("missing layers"));
+    };
+
+    layers
+        .iter_mut()
+        .map(|layer| {
+            layer
+                .as_object_mut()
+                .ok_or_else(|| malformed("layer is not an object"))
+        })
+        .collect()
+}
+
+// Stroke objects of every layer
+fn strokes_mut(
+    document: &mut Map<String, Value>,
+) -> Result<Vec<&mut Map<String, Value>>, DocumentError> {
+    let mut strokes = Vec::new();
+    for layer in layers_mut(document)? {
+        let Some(Value::Array(layer_strokes)) = layer.get_mut("strokes") else {
+            return Err(malformed("layer without strokes"));
+        };
+        for stroke in layer_strokes {
+            let stroke = stroke
+                .as_object_mut()
+                .ok_or_else(|| malformed("stroke is not an object"))?;
+            strokes.push(stroke);
+        }
+    }
+    Ok(strokes)
+}
+
+fn segments_to_polylines(stroke: &Value) -> Result<Vec<Value>, DocumentError> {
+    let style = stroke
+        .get("stroke")
+        .ok_or_else(|| malformed("stroke without style"))?;
+    let width = style.get("width").and_then(Value::as_f64).unwrap_or(1.0);
+    let Some(Value::Array(segments)) = stroke.get("points") else {
+        return Err(malformed("stroke without points"));
+    };
+
+    // (points, widths, any width recorded) for each connected run
+    let mut runs: Vec<(Vec<Value>, Vec<f64>, bool)> = Vec::new();
+    for segment in segments {
+        let Some([a, b]) = segment
+            .get("segment")
+            .and_then(Value::as_array)
+            .and_then(|points| points.get(..2))
+        else {
+            return Err(malformed("segment without two points"));
+        };
+        let widths = segment.get("widths").and_then(Value::as_array);
+        let width_at = |index: usize| {
+            widths
+                .and_then(|widths| widths.get(index))
+                .and_then(Value::as_f64)
+                .unwrap_or(width)
+        };
+
+        match runs.last_mut() {
+            Some((points, run_widths, tapered)) if points.last() == Some(a) => {
+                points.push(b.clone());
+                run_widths.push(width_at(1));
+                *tapered |= widths.is_some();
+            }
+            _ => runs.push((
+                vec![a.clone(), b.clone()],
+                vec![width_at(0), width_at(1)],
+                widths.is_some(),
+            )),
+        }
+    }
+
+    Ok(runs
+        .into_iter()
+        .map(|(points, widths, tapered)| {
+            let mut polyline = json!({ "stroke": style, "points": points });
+            if tapered && let Some(polyline) = polyline.as_object_mut() {
+                polyline.insert("widths".to_owned(), json!(widths));
+            }
+            polyline
+        })
+        .collect())
+}
+
+fn malformed(message: &str) -> DocumentError {
+    DocumentError::Parse(serde::de::Error::custom(message))
+}
@@ -0,0 +1,406 @@
+use crate::draw::canvas::Canvas;
+use egui::Color32;
+use std::fmt;
+use std::fmt::Write as _;
+use std::path::Path;
+
+// Most recently used colors kept, newest first
+pub const RECENT_LIMIT: usize = 16;
+
+const GPL_HEADER: &str = "GIMP Palette";
+const ASE_SIGNATURE: &[u8; 4] = b"ASEF";
+const ASE_GROUP_START: u16 = 0xC001;
+const ASE_GROUP_END: u16 = 0xC002;
+const ASE_COLOR: u16 = 0x0001;
+// Swatch kind written for every color; Adobe calls plain swatches "normal"
+const ASE_NORMAL: u16 = 2;
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct Swatch {
+    pub color: Color32,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+}
+
+impl Swatch {
+    pub fn new(color: Color32) -> Self {
+        Self {
+            color,
+            name: String::new(),
+        }
+    }
+
+    // The name, or the color as hex when it has none
+    pub fn label(&self) -> String {
+        if self.name.is_empty() {
+            self.color.to_hex()
+        } else {
+            self.name.clone()
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct Palette {
+    pub name: String,
+    pub swatches: Vec<Swatch>,
+}
+
+impl Palette {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            swatches: Vec::new(),
+        }
+    }
+
+    pub fn colors(&self) -> impl Iterator<Item = Color32> + '_ {
+        self.swatches.iter().map(|swatch| swatch.color)
+    }
+
+    // Adds `color` unless the palette already has it
+    pub fn add(&mut self, color: Color32) {
+        if !self.colors().any(|existing| existing == color) {
+            self.swatches.push(Swatch::new(color));
+        }
+    }
+}
+
+// The user's named palettes and the colors they used last
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Palettes {
+    pub palettes: Vec<Palette>,
+    pub active: usize,
+    pub recent: Vec<Color32>,
+}
+
+impl Default for Palettes {
+    fn default() -> Self {
+        let mut basic = Palette::new("Basic");
+        for color in [
+            Color32::BLACK,
+            Color32::DARK_GRAY,
+            Color32::GRAY,
+            Color32::WHITE,
+            Color32::RED,
+            Color32::ORANGE,
+            Color32::YELLOW,
+            Color32::GREEN,
+            Color32::DARK_GREEN,
+            Color32::LIGHT_BLUE,
+            Color32::BLUE,
+            Color32::PURPLE,
+            Color32::BROWN,
+        ] {
+            basic.add(color);
+        }
+
+        Self {
+            palettes: vec![basic],
+            active: 0,
+            recent: Vec::new(),
+        }
+    }
+}
+
+impl Palettes {
+    pub fn active(&self) -> Option<&Palette> {
+        self.palettes.get(self.active)
+    }
+
+    pub fn active_mut(&mut self) -> Option<&mut Palette> {
+        self.palettes.get_mut(self.active)
+    }
+
+    // Adds `palette` and switches to it
+    pub fn insert(&mut self, palette: Palette) {
+        self.palettes.push(palette);
+        self.active = self.palettes.len() - 1;
+    }
+
+    pub fn remove_active(&mut self) {
+        if self.active < self.palettes.len() {
+            self.palettes.remove(self.active);
+        }
+        self.active = self.active.min(self.palettes.len().saturating_sub(1));
+    }
+
+    // Moves `color` to the front of the recent colors
+    pub fn use_color(&mut self, color: Color32) {
+        self.recent.retain(|recent| *recent != color);
+        self.recent.insert(0, color);
+        self.recent.truncate(RECENT_LIMIT);
+    }
+}
+
+// Every color drawn with in `canvas`, in the order first used
+pub fn document_colors(canvas: &Canvas) -> Palette {
+    let mut palette = Palette::new("Document");
+    for layer in &canvas.layers {
+        let strokes = layer.strokes.iter().map(|stroke| stroke.stroke.color);
+        let shapes = layer.shapes.iter().map(|shape| shape.stroke.color);
+        let fills = layer.fills.iter().map(|fill| fill.color);
+        let texts = layer.texts.iter().map(|text| text.color);
+        for color in strokes.chain(shapes).chain(fills).chain(texts) {
+            palette.add(color);
+        }
+    }
+    palette
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PaletteFormat {
+    Gpl,
+    Ase,
+}
+
+impl PaletteFormat {
+    pub const ALL: [Self; 2] = [Self::Gpl, Self::Ase];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Gpl => "GIMP (.gpl)",
+            Self::Ase => "Adobe (.ase)",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Gpl => "gpl",
+            Self::Ase => "ase",
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|format| format.extension() == extension)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PaletteError {
+    UnknownFormat(String),
+    MissingGplHeader,
+    InvalidGplLine { line: usize, text: String },
+    MissingAseSignature,
+    TruncatedAse,
+}
+
+impl fmt::Display for PaletteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownFormat(extension) => write!(
+                f,
+                "Palettes can be read from .gpl and .ase files, not \"{extension}\""
+            ),
+            Self::MissingGplHeader => write!(f, "The file is not a GIMP palette"),
+            Self::InvalidGplLine { line, text } => {
+                write!(f, "Line {line} of the GIMP palette is not a color: {text}")
+            }
+            Self::MissingAseSignature => write!(f, "The file is not an Adobe swatch exchange file"),
+            Self::TruncatedAse => write!(f, "The Adobe swatch exchange file is cut short"),
+        }
+    }
+}
+
+impl std::error::Error for PaletteError {}
+
+// GIMP palettes are text: a header, then one "red green blue name" line per color
+pub fn to_gpl(palette: &Palette) -> String {
+    let mut gpl = format!("{GPL_HEADER}\nName: {}\n#\n", palette.name);
+    for swatch in &palette.swatches {
+        let [r, g, b, _] = swatch.color.to_srgba_unmultiplied();
+        writeln!(gpl, "{r:3} {g:3} {b:3}\t{}", swatch.label()).ok();
+    }
+    gpl
+}
+
+/// Reads a GIMP palette. Colors are opaque, since the format has no alpha.
+///
+/// # Errors
+///
+/// Fails if the header is missing or a line is neither a comment, a header field nor a color.
+pub fn from_gpl(text: &str, fallback_name: &str) -> Result<Palette, PaletteError> {
+    let mut lines = text.lines().enumerate();
+    if lines
+        .next()
+        .is_none_or(|(_, header)| header.trim() != GPL_HEADER)
+    {
+        return Err(PaletteError::MissingGplHeader);
+    }
+
+    let mut palette = Palette::new(fallback_name);
+    for (index, line) in lines {
+        let line = line.trim();
+        if let Some(name) = line.strip_prefix("Name:") {
+            name.trim().clone_into(&mut palette.name);
+            continue;
+        }
+        if line.is_empty() || line.starts_with('#') || line.starts_with("Columns:") {
+            continue;
+        }
+
+        let mut fields = line.split_whitespace();
+        let mut channel = || fields.next().and_then(|field| field.parse::<u8>().ok());
+        let (Some(r), Some(g), Some(b)) = (channel(), channel(), channel()) else {
+            return Err(PaletteError::InvalidGplLine {
+                line: index + 1,
+                text: line.to_owned(),
+            });
+        };
+        palette.swatches.push(Swatch {
+            color: Color32::from_rgb(r, g, b),
+            name: fields.collect::<Vec<_>>().join(" "),
+        });
+    }
+    Ok(palette)
+}
+
+// Adobe swatch exchange: big-endian blocks, here one group named after the palette
+pub fn to_ase(palette: &Palette) -> Vec<u8> {
+    let mut blocks = Vec::new();
+    ase_block(&mut blocks, ASE_GROUP_START, &ase_name(&palette.name));
+    for swatch in &palette.swatches {
+        let [r, g, b, _] = swatch.color.to_srgba_unmultiplied();
+        let mut data = ase_name(&swatch.label());
+        data.extend_from_slice(b"RGB ");
+        for channel in [r, g, b] {
+            data.extend_from_slice(&(f32::from(channel) / 255.0).to_be_bytes());
+        }
+        data.extend_from_slice(&ASE_NORMAL.to_be_bytes());
+        ase_block(&mut blocks, ASE_COLOR, &data);
+    }
+    ase_block(&mut blocks, ASE_GROUP_END, &[]);
+
+    let mut ase = ASE_SIGNATURE.to_vec();
+    ase.extend_from_slice(&1_u16.to_be_bytes());
+    ase.extend_from_slice(&0_u16.to_be_bytes());
+    let block_count = u32::try_from(palette.swatches.len() + 2).unwrap_or(u32::MAX);
+    ase.extend_from_slice(&block_count.to_be_bytes());
+    ase.extend(blocks);
+    ase
+}
+
+fn ase_block(out: &mut Vec<u8>, kind: u16, data: &[u8]) {
+    out.extend_from_slice(&kind.to_be_bytes());
+    out.extend_from_slice(&u32::try_from(data.len()).unwrap_or(u32::MAX).to_be_bytes());
+    out.extend_from_slice(data);
+}
+
+// Length in UTF-16 units including the terminating zero, then the name itself
+fn ase_name(name: &str) -> Vec<u8> {
+    let units: Vec<u16> = name.encode_utf16().chain([0]).collect();
+    let mut data = u16::try_from(units.len())
+        .unwrap_or(u16::MAX)
+        .to_be_bytes()
+        .to_vec();
+    for unit in units {
+        data.extend_from_slice(&unit.to_be_bytes());
+    }
+    data
+}
+
+/// Reads an Adobe swatch exchange file. The first group names the palette; RGB, CMYK and
+/// gray swatches are kept, Lab swatches are skipped.
+///
+/// # Errors
+///
+/// Fails if the signature is missing or a block runs past the end of the data.
+pub fn from_ase(data: &[u8], fallback_name: &str) -> Result<Palette, PaletteError> {
+    let mut reader = AseReader { data, position: 0 };
+    if reader.take(4)? != ASE_SIGNATURE {
+        return Err(PaletteError::MissingAseSignature);
+    }
+    reader.take(4)?; // version
+    let block_count = reader.u32()?;
+
+    let mut palette = Palette::new(fallback_name);
+    let mut named = false;
+    for _ in 0..block_count {
+        let kind = reader.u16()?;
+        let length = reader.u32()? as usize;
+        let mut block = AseReader {
+            data: reader.take(length)?,
+            position: 0,
+        };
+
+        match kind {
+            ASE_GROUP_START if !named => {
+                palette.name = block.name()?;
+                named = true;
+            }
+            ASE_COLOR => {
+                let name = block.name()?;
+                if let Some(color) = block.color()? {
+                    palette.swatches.push(Swatch { color, name });
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(palette)
+}
+
+struct AseReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> AseReader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], PaletteError> {
+        let bytes = self
+            .data
+            .get(self.position..self.position + count)
+            .ok_or(PaletteError::TruncatedAse)?;
+        self.position += count;
+        Ok(bytes)
+    }
+
+    fn bytes<const N: usize>(&mut self) -> Result<[u8; N], PaletteError> {
+        let mut bytes = [0; N];
+        bytes.copy_from_slice(self.take(N)?);
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> Result<u16, PaletteError> {
+        Ok(u16::from_be_bytes(self.bytes()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, PaletteError> {
+        Ok(u32::from_be_bytes(self.bytes()?))
+    }
+
+    fn f32(&mut self) -> Result<f32, PaletteError> {
+        Ok(f32::from_be_bytes(self.bytes()?))
+    }
+
+    fn name(&mut self) -> Result<String, PaletteError> {
+        let length = self.u16()?;
+        let units = (0..length)
+            .map(|_| self.u16())
+            .collect::<Result<Vec<u16>, _>>()?;
+        let name = String::from_utf16_lossy(&units);
+        Ok(name.trim_end_matches('\0').to_owned())
+    }
+
+    fn color(&mut self) -> Result<Option<Color32>, PaletteError> {
+        let to_byte = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+        let color = match self.take(4)? {
+            b"RGB " => {
+                let (r, g, b) = (self.f32()?, self.f32()?, self.f32()?);
+                Some(Color32::from_rgb(to_byte(r), to_byte(g), to_byte(b)))
+            }
+            b"CMYK" => {
+                let (c, m, y, k) = (self.f32()?, self.f32()?, self.f32()?, self.f32()?);
+                let channel = |ink: f32| to_byte((1.0 - ink) * (1.0 - k));
+                Some(Color32::from_rgb(channel(c), channel(m), channel(y)))
+            }
+            b"Gray" => Some(Color32::from_gray(to_byte(self.f32()?))),
+            _ => None,
+        };
+        Ok(color)
+    }
+}
@@ -0,0 +1,207 @@
+use super::highlighter;
+use super::smoothing::{self, Smoothing};
+use crate::toolbar::main::WIDTH_RANGE;
+use egui::{Color32, Stroke};
+
+// Presets 1 to 9 are reachable with the number keys
+pub const SHORTCUT_SLOTS: usize = 9;
+// Bumped when preset packs change in a way older versions can't read
+pub const PACK_VERSION: u64 = 1;
+
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize,
+)]
+pub enum StrokeCap {
+    #[default]
+    Round,
+    // Ends exactly at the first and last point
+    Butt,
+    // Like `Butt`, extended by half the width
+    Square,
+}
+
+impl StrokeCap {
+    pub const ALL: [Self; 3] = [Self::Round, Self::Butt, Self::Square];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Round => "Round",
+            Self::Butt => "Flat",
+            Self::Square => "Square",
+        }
+    }
+
+    // Value of the SVG `stroke-linecap` attribute
+    pub fn svg_name(&self) -> &'static str {
+        match self {
+            Self::Round => "round",
+            Self::Butt => "butt",
+            Self::Square => "square",
+        }
+    }
+
+    pub fn from_svg_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|cap| cap.svg_name() == name.trim())
+    }
+
+    pub fn is_round(&self) -> bool {
+        *self == Self::Round
+    }
+}
+
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize,
+)]
+pub enum BrushKind {
+    #[default]
+    Pen,
+    // Translucent ink beneath the other strokes of its layer that doesn't darken where
+    // the stroke overlaps itself
+    Highlighter,
+}
+
+impl BrushKind {
+    pub const ALL: [Self; 2] = [Self::Pen, Self::Highlighter];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Pen => "Pen",
+            Self::Highlighter => "Highlighter",
+        }
+    }
+
+    pub fn is_pen(&self) -> bool {
+        *self == Self::Pen
+    }
+
+    // Color the stroke is painted with
+    pub fn ink(&self, color: Color32) -> Color32 {
+        match self {
+            Self::Pen => color,
+            Self::Highlighter => color.gamma_multiply(highlighter::OPACITY),
+        }
+    }
+}
+
+// Named brush settings the pen switches between in one step
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct BrushPreset {
+    pub name: String,
+    pub width: f32,
+    // Always opaque; the alpha is kept in `opacity`
+    pub color: Color32,
+    pub opacity: f32,
+    pub smoothing: Smoothing,
+    #[serde(default)]
+    pub cap: StrokeCap,
+    #[serde(default)]
+    pub kind: BrushKind,
+}
+
+impl BrushPreset {
+    // Captures the current pen settings
+    pub fn from_brush(
+        name: impl Into<String>,
+        stroke: Stroke,
+        smoothing: Smoothing,
+        cap: StrokeCap,
+        kind: BrushKind,
+    ) -> Self {
+        let [r, g, b, a] = stroke.color.to_srgba_unmultiplied();
+        Self {
+            name: name.into(),
+            width: stroke.width,
+            color: Color32::from_rgb(r, g, b),
+            opacity: f32::from(a) / 255.0,
+            smoothing,
+            cap,
+            kind,
+        }
+    }
+
+    // Values from shared packs may be out of range, so they are clamped when used
+    pub fn stroke(&self) -> Stroke {
+        let [r, g, b, _] = self.color.to_srgba_unmultiplied();
+        let alpha = (self.opacity.clamp(0.0, 1.0) * 255.0).round() as u8;
+        Stroke::new(
+            self.width.clamp(*WIDTH_RANGE.start(), *WIDTH_RANGE.end()),
+            Color32::from_rgba_unmultiplied(r, g, b, alpha),
+        )
+    }
+
+    pub fn smoothing(&self) -> Smoothing {
+        Smoothing {
+            stabilizer: self.smoothing.stabilizer.clamp(
+                *smoothing::STABILIZER_RANGE.start(),
+                *smoothing::STABILIZER_RANGE.end(),
+            ),
+            passes: self.smoothing.passes.clamp(
+                *smoothing::PASSES_RANGE.start(),
+                *smoothing::PASSES_RANGE.end(),
+            ),
+        }
+    }
+
+    // Whether the pen is currently set up exactly like this preset
+    pub fn matches(
+        &self,
+        stroke: Stroke,
+        smoothing: Smoothing,
+        cap: StrokeCap,
+        kind: BrushKind,
+    ) -> bool {
+        self.stroke() == stroke
+            && self.smoothing() == smoothing
+            && self.cap == cap
+            && self.kind == kind
+    }
+}
+
+pub fn default_presets() -> Vec<BrushPreset> {
+    let pen = |name: &str, width: f32, passes: u32| BrushPreset {
+        name: name.to_owned(),
+        width,
+        color: Color32::BLACK,
+        opacity: 1.0,
+        smoothing: Smoothing {
+            stabilizer: 0.0,
+            passes,
+        },
+        cap: StrokeCap::Round,
+        kind: BrushKind::Pen,
+    };
+
+    vec![
+        pen("Fine Liner", 2.0, 2),
+        pen("Pen", 8.0, 1),
+        BrushPreset {
+            cap: StrokeCap::Square,
+            opacity: 0.8,
+            ..pen("Marker", 20.0, 1)
+        },
+        BrushPreset {
+            color: Color32::YELLOW,
+            cap: StrokeCap::Butt,
+            kind: BrushKind::Highlighter,
+            ..pen("Highlighter", 24.0, 1)
+        },
+    ]
+}
+
+// Presets shared as one JSON file
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct PresetPack {
+    pub version: u64,
+    pub presets: Vec<BrushPreset>,
+}
+
+impl PresetPack {
+    pub fn new(presets: Vec<BrushPreset>) -> Self {
+        Self {
+            version: PACK_VERSION,
+            presets,
+        }
+    }
+}
@@ -1,28 +1,85 @@
+use super::brush::{BrushKind, StrokeCap};
+use super::fill::Fill;
+use super::mesh_cache::{MeshCache, StrokeTessellator};
+use super::shapes::Shape;
+use super::spatial::SpatialIndex;
+use super::text::TextBox;
 use egui::{Pos2, Rect};
+use std::ops::RangeInclusive;
+
+// Zoom at which the whole canvas fits the view with a small margin
+pub const FIT_ZOOM: f32 = 0.85;
+pub const ZOOM_RANGE: RangeInclusive<f32> = 0.01..=10.0;
 
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
 pub struct Canvas {
     pub canvas_viewport: Rect,
     pub canvas_area: Rect,
-    pub strokes: Vec<SingleStroke>,
-    pub segments: Vec<Segment>,
+    pub layers: Vec<Layer>,
+    pub active_layer: usize,
+    // Stroke being drawn with the pen, committed when the drag ends
+    #[serde(default)]
+    pub current_stroke: Option<SingleStroke>,
     pub last_cursor_pos: Option<Pos2>,
+    #[serde(default)]
+    pub shape_anchor: Option<Pos2>,
     pub zoom: f32,
+    // Rebuilt from the layers whenever they are replaced wholesale, see `rebuild_caches`
+    #[serde(skip)]
+    index: SpatialIndex,
+    #[serde(skip)]
+    meshes: MeshCache,
 }
 
 impl Canvas {
     pub fn new(canvas_size: egui::Vec2) -> Self {
         let canvas_dimensions = canvas_size.to_pos2();
-        let initial_zoom = 0.85;
+        let initial_zoom = FIT_ZOOM;
         let canvas_viewport = build_viewport(canvas_size, initial_zoom);
 
-        Self {
+        let mut canvas = Self {
             canvas_viewport,
             canvas_area: Rect::from_min_max(Pos2::default(), canvas_dimensions),
-            strokes: Vec::default(),
-            segments: Vec::default(),
+            layers: vec![Layer::new("Layer 1")],
+            active_layer: 0,
+            current_stroke: None,
             last_cursor_pos: None,
+            shape_anchor: None,
             zoom: initial_zoom,
+            index: SpatialIndex::default(),
+            meshes: MeshCache::default(),
+        };
+        canvas.rebuild_caches();
+        canvas
+    }
+
+    // Needed after assigning `layers` directly, e.g. when loading a document or session
+    pub fn rebuild_caches(&mut self) {
+        self.index = SpatialIndex::build(&self.layers);
+        self.meshes.reset(&self.layers);
+    }
+
+    // Cached meshes of the strokes on `layer`; only new or changed strokes are tessellated
+    pub fn stroke_shapes(
+        &mut self,
+        layer: usize,
+        tessellator: &mut StrokeTessellator,
+    ) -> Vec<egui::Shape> {
+        self.meshes.shapes(&self.layers, layer, tessellator)
+    }
+
+    pub fn is_mesh_cached(&self, layer: usize, index: usize) -> bool {
+        self.meshes.is_cached(layer, index)
+    }
+
+    // Indices of strokes on `layer` that may come within `area`, in ascending order.
+    // Falls back to every stroke if the index has gone out of sync with the layer.
+    pub fn strokes_in(&self, layer: usize, area: Rect) -> Vec<usize> {
+        let count = self.strokes(layer).map_or(0, Vec::len);
+        if self.index.len(layer) == Some(count) {
+            self.index.query(layer, area)
+        } else {
+            (0..count).collect()
         }
     }
 
@@ -33,80 +90,727 @@ impl Canvas {
 
         self.zoom = canvas_size.x / viewport_size.x;
     }
+
+    // Forgets any half-finished stroke or shape, e.g. after restoring a saved session
+    pub fn reset_input(&mut self) {
+        self.current_stroke = None;
+        self.last_cursor_pos = None;
+        self.shape_anchor = None;
+    }
+
+    pub fn set_zoom(&mut self, zoom: f32) {
+        self.zoom = zoom.clamp(*ZOOM_RANGE.start(), *ZOOM_RANGE.end());
+        self.canvas_viewport = build_viewport(self.canvas_area.size(), self.zoom);
+    }
+
+    pub fn active_layer(&self) -> Option<&Layer> {
+        self.layers.get(self.active_layer)
+    }
+
+    pub fn active_layer_mut(&mut self) -> Option<&mut Layer> {
+        self.layers.get_mut(self.active_layer)
+    }
+
+    // Tools only touch the active layer, and only while it is visible and unlocked
+    pub fn active_layer_editable(&self) -> bool {
+        self.active_layer().is_some_and(Layer::is_editable)
+    }
+
+    pub fn strokes(&self, layer: usize) -> Option<&Vec<SingleStroke>> {
+        self.layers.get(layer).map(|layer| &layer.strokes)
+    }
+
+    pub fn shapes(&self, layer: usize) -> Option<&Vec<Shape>> {
+        self.layers.get(layer).map(|layer| &layer.shapes)
+    }
+
+    // Overwrites the strokes at `indices` with `strokes`, pairwise
+    pub fn replace_strokes(&mut self, layer: usize, indices: &[usize], strokes: &[SingleStroke]) {
+        let Some(layer_strokes) = self.layers.get_mut(layer).map(|layer| &mut layer.strokes) else {
+            return;
+        };
+
+        for (index, stroke) in indices.iter().zip(strokes) {
+            if let Some(target) = layer_strokes.get_mut(*index) {
+                target.clone_from(stroke);
+                self.index.replace(layer, *index, stroke);
+                self.meshes.invalidate(layer, *index);
+            }
+        }
+    }
+
+    fn insert_stroke(&mut self, layer: usize, index: usize, stroke: &SingleStroke) {
+        if let Some(strokes) = self.layers.get_mut(layer).map(|layer| &mut layer.strokes)
+            && index <= strokes.len()
+        {
+            strokes.insert(index, stroke.clone());
+            self.index.insert(layer, index, stroke);
+            self.meshes.insert(layer, index);
+        }
+    }
+
+    fn remove_stroke(&mut self, layer: usize, index: usize) {
+        if let Some(strokes) = self.layers.get_mut(layer).map(|layer| &mut layer.strokes)
+            && index < strokes.len()
+        {
+            strokes.remove(index);
+            self.index.remove(layer, index);
+            self.meshes.remove(layer, index);
+        }
+    }
+
+    fn insert_shape(&mut self, layer: usize, index: usize, shape: &Shape) {
+        if let Some(layer) = self.layers.get_mut(layer)
+            && index <= layer.shapes.len()
+        {
+            layer.shapes.insert(index, shape.clone());
+        }
+    }
+
+    fn remove_shape(&mut self, layer: usize, index: usize) {
+        if let Some(layer) = self.layers.get_mut(layer)
+            && index < layer.shapes.len()
+        {
+            layer.shapes.remove(index);
+        }
+    }
+
+    pub fn fills(&self, layer: usize) -> Option<&Vec<Fill>> {
+        self.layers.get(layer).map(|layer| &layer.fills)
+    }
+
+    fn insert_fill(&mut self, layer: usize, index: usize, fill: &Fill) {
+        if let Some(layer) = self.layers.get_mut(layer)
+            && index <= layer.fills.len()
+        {
+            layer.fills.insert(index, fill.clone());
+        }
+    }
+
+    fn remove_fill(&mut self, layer: usize, index: usize) {
+        if let Some(layer) = self.layers.get_mut(layer)
+            && index < layer.fills.len()
+        {
+            layer.fills.remove(index);
+        }
+    }
+
+    pub fn texts(&self, layer: usize) -> Option<&Vec<TextBox>> {
+        self.layers.get(layer).map(|layer| &layer.texts)
+    }
+
+    fn insert_text(&mut self, layer: usize, index: usize, text: &TextBox) {
+        if let Some(layer) = self.layers.get_mut(layer)
+            && index <= layer.texts.len()
+        {
+            layer.texts.insert(index, text.clone());
+        }
+    }
+
+    fn remove_text(&mut self, layer: usize, index: usize) {
+        if let Some(layer) = self.layers.get_mut(layer)
+            && index < layer.texts.len()
+        {
+            layer.texts.remove(index);
+        }
+    }
+
+    fn replace_text(&mut self, layer: usize, index: usize, text: &TextBox) {
+        if let Some(target) = self
+            .layers
+            .get_mut(layer)
+            .and_then(|layer| layer.texts.get_mut(index))
+        {
+            target.clone_from(text);
+        }
+    }
+
+    fn merge_down(&mut self, upper: &Layer, index: usize) {
+        let Some(below) = index.checked_sub(1) else {
+            return;
+        };
+        if index >= self.layers.len() {
+            return;
+        }
+
+        self.layers.remove(index);
+        if let Some(lower) = self.layers.get_mut(below) {
+            // Bake the opacities into the colors so everything looks the same once merged.
+            // The upper colors are scaled relative to the lower layer while that still fits
+            // in a color; otherwise both are baked and the merged layer is fully opaque.
+            let relative = upper.opacity / lower.opacity;
+            let factor = if lower.opacity > 0.0 && f32::from(upper.max_alpha()) * relative <= 255.0
+            {
+                relative
+            } else {
+                lower.multiply_colors(lower.opacity);
+                lower.opacity = 1.0;
+                upper.opacity
+            };
+
+            let mut upper = upper.clone();
+            upper.multiply_colors(factor);
+            lower.strokes.append(&mut upper.strokes);
+            lower.shapes.append(&mut upper.shapes);
+            lower.fills.append(&mut upper.fills);
+            lower.texts.append(&mut upper.texts);
+        }
+        self.active_layer = below;
+    }
+
+    // Undoes `merge_down`, putting back both layers as they were
+    fn split_down(&mut self, upper: &Layer, lower: &Layer, index: usize) {
+        let Some(below) = index.checked_sub(1) else {
+            return;
+        };
+        if index > self.layers.len() {
+            return;
+        }
+
+        if let Some(target) = self.layers.get_mut(below) {
+            target.clone_from(lower);
+        }
+        self.layers.insert(index, upper.clone());
+        self.active_layer = index;
+    }
+
+    fn insert_layer(&mut self, index: usize, layer: &Layer) {
+        if index <= self.layers.len() {
+            self.layers.insert(index, layer.clone());
+            self.active_layer = index;
+        }
+    }
+
+    fn remove_layer(&mut self, index: usize) {
+        if index < self.layers.len() {
+            self.layers.remove(index);
+            self.clamp_active_layer();
+        }
+    }
+
+    fn move_layer(&mut self, from: usize, to: usize) {
+        if from < self.layers.len() && to < self.layers.len() {
+            let layer = self.layers.remove(from);
+            self.layers.insert(to, layer);
+            self.active_layer = to;
+        }
+    }
+
+    fn rename_layer(&mut self, index: usize, name: &str) {
+        if let Some(layer) = self.layers.get_mut(index) {
+            name.clone_into(&mut layer.name);
+        }
+    }
+
+    fn set_layer_settings(&mut self, index: usize, settings: LayerSettings) {
+        if let Some(layer) = self.layers.get_mut(index) {
+            layer.visible = settings.visible;
+            layer.locked = settings.locked;
+            layer.opacity = settings.opacity;
+        }
+    }
+
+    fn clamp_active_layer(&mut self) {
+        self.active_layer = self.active_layer.min(self.layers.len().saturating_sub(1));
+    }
+}
+
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct Layer {
+    pub name: String,
+    pub visible: bool,
+    pub locked: bool,
+    pub opacity: f32,
+    pub strokes: Vec<SingleStroke>,
+    #[serde(default)]
+    pub shapes: Vec<Shape>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fills: Vec<Fill>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub texts: Vec<TextBox>,
+}
+
+// The parts of a layer the layers panel toggles and slides
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct LayerSettings {
+    pub visible: bool,
+    pub locked: bool,
+    pub opacity: f32,
+}
+
+impl Layer {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            visible: true,
+            locked: false,
+            opacity: 1.0,
+            strokes: Vec::default(),
+            shapes: Vec::default(),
+            fills: Vec::default(),
+            texts: Vec::default(),
+        }
+    }
+
+    pub fn is_editable(&self) -> bool {
+        self.visible && !self.locked
+    }
+
+    pub fn settings(&self) -> LayerSettings {
+        LayerSettings {
+            visible: self.visible,
+            locked: self.locked,
+            opacity: self.opacity,
+        }
+    }
+
+    // Highest alpha of any color on the layer
+    fn max_alpha(&self) -> u8 {
+        let strokes = self.strokes.iter().map(|stroke| stroke.stroke.color);
+        let shapes = self.shapes.iter().map(|shape| shape.stroke.color);
+        let fills = self.fills.iter().map(|fill| fill.color);
+        let texts = self.texts.iter().map(|text| text.color);
+        strokes
+            .chain(shapes)
+            .chain(fills)
+            .chain(texts)
+            .map(|color| color.a())
+            .max()
+            .unwrap_or(0)
+    }
+
+    // Scales the color and alpha of everything on the layer, e.g. to bake in its opacity
+    pub fn multiply_colors(&mut self, factor: f32) {
+        for stroke in &mut self.strokes {
+            stroke.stroke.color = stroke.stroke.color.gamma_multiply(factor);
+        }
+        for shape in &mut self.shapes {
+            shape.stroke.color = shape.stroke.color.gamma_multiply(factor);
+        }
+        for fill in &mut self.fills {
+            fill.color = fill.color.gamma_multiply(factor);
+        }
+        for text in &mut self.texts {
+            text.color = text.color.gamma_multiply(factor);
+        }
+    }
+
+    // Indices of the strokes in the order they are painted: highlighter ink first, so it
+    // sits beneath the pen strokes of the layer
+    pub fn stroke_paint_order(&self) -> Vec<usize> {
+        let (highlights, pens): (Vec<usize>, Vec<usize>) =
+            (0..self.strokes.len()).partition(|index| {
+                self.strokes
+                    .get(*index)
+                    .is_some_and(|stroke| stroke.kind == BrushKind::Highlighter)
+            });
+        highlights.into_iter().chain(pens).collect()
+    }
 }
 
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
 pub enum Action {
     AddStroke {
         stroke: SingleStroke,
+        index: usize,
+        layer: usize,
     },
     RemoveStroke {
         stroke: SingleStroke,
         index: usize,
+        layer: usize,
     },
     ModifyStroke {
         before: Option<SingleStroke>,
         after: Option<SingleStroke>,
         index: usize,
+        layer: usize,
+    },
+    // Replaces several strokes at once, e.g. when transforming a selection
+    ModifyStrokes {
+        before: Vec<SingleStroke>,
+        after: Vec<SingleStroke>,
+        indices: Vec<usize>,
+        layer: usize,
+    },
+    AddShape {
+        shape: Shape,
+        index: usize,
+        layer: usize,
+    },
+    RemoveShape {
+        shape: Shape,
+        index: usize,
+        layer: usize,
+    },
+    AddFill {
+        fill: Fill,
+        index: usize,
+        layer: usize,
+    },
+    RemoveFill {
+        fill: Fill,
+        index: usize,
+        layer: usize,
+    },
+    AddText {
+        text: TextBox,
+        index: usize,
+        layer: usize,
+    },
+    RemoveText {
+        text: TextBox,
+        index: usize,
+        layer: usize,
+    },
+    // Edited content or formatting of an existing text
+    ModifyText {
+        before: TextBox,
+        after: TextBox,
+        index: usize,
+        layer: usize,
+    },
+    AddLayer {
+        layer: Layer,
+        index: usize,
+    },
+    DeleteLayer {
+        layer: Layer,
+        index: usize,
+    },
+    MoveLayer {
+        from: usize,
+        to: usize,
+    },
+    RenameLayer {
+        before: String,
+        after: String,
+        index: usize,
+    },
+    // Changed visibility, lock or opacity
+    ModifyLayer {
+        before: LayerSettings,
+        after: LayerSettings,
+        index: usize,
+    },
+    // Several actions recorded as one undo step, see `History::begin`
+    Group {
+        actions: Vec<Action>,
+    },
+    // Moves the strokes of layer `index` onto the layer below it
+    MergeDown {
+        upper: Layer,
+        // The layer below as it was; merging may change its colors and opacity
+        lower: Layer,
+        index: usize,
     },
 }
 
 impl Action {
     pub fn execute(&self, canvas: &mut Canvas) {
         match self {
-            Self::AddStroke { stroke } => {
-                canvas.strokes.push(stroke.clone());
-            }
-            Self::RemoveStroke { stroke: _, index } => {
-                canvas.strokes.remove(*index);
+            Self::Group { actions } => {
+                for action in actions {
+                    action.execute(canvas);
+                }
             }
+            Self::AddStroke {
+                stroke,
+                index,
+                layer,
+            } => canvas.insert_stroke(*layer, *index, stroke),
+            Self::RemoveStroke {
+                stroke: _,
+                index,
+                layer,
+            } => canvas.remove_stroke(*layer, *index),
             Self::ModifyStroke {
                 before: _,
                 after,
                 index,
+                layer,
             } => {
                 if let Some(after) = after {
-                    canvas.strokes[*index] = after.clone();
+                    canvas.replace_strokes(*layer, &[*index], std::slice::from_ref(after));
                 }
             }
+            Self::ModifyStrokes {
+                before: _,
+                after,
+                indices,
+                layer,
+            } => canvas.replace_strokes(*layer, indices, after),
+            Self::AddShape {
+                shape,
+                index,
+                layer,
+            } => canvas.insert_shape(*layer, *index, shape),
+            Self::RemoveShape {
+                shape: _,
+                index,
+                layer,
+            } => canvas.remove_shape(*layer, *index),
+            Self::AddFill { fill, index, layer } => canvas.insert_fill(*layer, *index, fill),
+            Self::RemoveFill {
+                fill: _,
+                index,
+                layer,
+            } => canvas.remove_fill(*layer, *index),
+            Self::AddText { text, index, layer } => canvas.insert_text(*layer, *index, text),
+            Self::RemoveText {
+                text: _,
+                index,
+                layer,
+            } => canvas.remove_text(*layer, *index),
+            Self::ModifyText {
+                before: _,
+                after,
+                index,
+                layer,
+            } => canvas.replace_text(*layer, *index, after),
+            Self::AddLayer { layer, index } => {
+                canvas.insert_layer(*index, layer);
+                canvas.rebuild_caches();
+            }
+            Self::DeleteLayer { layer: _, index } => {
+                canvas.remove_layer(*index);
+                canvas.rebuild_caches();
+            }
+            Self::MoveLayer { from, to } => {
+                canvas.move_layer(*from, *to);
+                canvas.rebuild_caches();
+            }
+            Self::RenameLayer {
+                before: _,
+                after,
+                index,
+            } => canvas.rename_layer(*index, after),
+            Self::ModifyLayer {
+                before: _,
+                after,
+                index,
+            } => canvas.set_layer_settings(*index, *after),
+            Self::MergeDown {
+                upper,
+                lower: _,
+                index,
+            } => {
+                canvas.merge_down(upper, *index);
+                canvas.rebuild_caches();
+            }
         }
     }
 
     pub fn undo(&self, canvas: &mut Canvas) {
         match self {
-            Self::AddStroke { stroke } => {
-                canvas.strokes.pop();
-            }
-            Self::RemoveStroke { stroke, index } => {
-                canvas.strokes.insert(*index, stroke.clone());
+            Self::Group { actions } => {
+                for action in actions.iter().rev() {
+                    action.undo(canvas);
+                }
             }
+            Self::AddStroke {
+                stroke: _,
+                index,
+                layer,
+            } => canvas.remove_stroke(*layer, *index),
+            Self::RemoveStroke {
+                stroke,
+                index,
+                layer,
+            } => canvas.insert_stroke(*layer, *index, stroke),
             Self::ModifyStroke {
                 before,
                 after: _,
                 index,
+                layer,
             } => {
                 if let Some(before) = before {
-                    canvas.strokes[*index] = before.clone();
+                    canvas.replace_strokes(*layer, &[*index], std::slice::from_ref(before));
                 }
             }
+            Self::ModifyStrokes {
+                before,
+                after: _,
+                indices,
+                layer,
+            } => canvas.replace_strokes(*layer, indices, before),
+            Self::AddShape {
+                shape: _,
+                index,
+                layer,
+            } => canvas.remove_shape(*layer, *index),
+            Self::RemoveShape {
+                shape,
+                index,
+                layer,
+            } => canvas.insert_shape(*layer, *index, shape),
+            Self::AddFill {
+                fill: _,
+                index,
+                layer,
+            } => canvas.remove_fill(*layer, *index),
+            Self::RemoveFill { fill, index, layer } => canvas.insert_fill(*layer, *index, fill),
+            Self::AddText {
+                text: _,
+                index,
+                layer,
+            } => canvas.remove_text(*layer, *index),
+            Self::RemoveText { text, index, layer } => canvas.insert_text(*layer, *index, text),
+            Self::ModifyText {
+                before,
+                after: _,
+                index,
+                layer,
+            } => canvas.replace_text(*layer, *index, before),
+            Self::AddLayer { layer: _, index } => {
+                canvas.remove_layer(*index);
+                canvas.rebuild_caches();
+            }
+            Self::DeleteLayer { layer, index } => {
+                canvas.insert_layer(*index, layer);
+                canvas.rebuild_caches();
+            }
+            Self::MoveLayer { from, to } => {
+                canvas.move_layer(*to, *from);
+                canvas.rebuild_caches();
+            }
+            Self::RenameLayer {
+                before,
+                after: _,
+                index,
+            } => canvas.rename_layer(*index, before),
+            Self::ModifyLayer {
+                before,
+                after: _,
+                index,
+            } => canvas.set_layer_settings(*index, *before),
+            Self::MergeDown {
+                upper,
+                lower,
+                index,
+            } => {
+                canvas.split_down(upper, lower, *index);
+                canvas.rebuild_caches();
+            }
         }
     }
 }
 
-#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+// A freehand stroke as one connected polyline
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
 pub struct SingleStroke {
     pub stroke: egui::Stroke,
-    pub points: Vec<Segment>,
+    pub points: Vec<Pos2>,
+    // Width at each point for pressure strokes; empty uses the width of the stroke
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub widths: Vec<f32>,
+    #[serde(default, skip_serializing_if = "StrokeCap::is_round")]
+    pub cap: StrokeCap,
+    #[serde(default, skip_serializing_if = "BrushKind::is_pen")]
+    pub kind: BrushKind,
 }
 
-#[derive(Debug, serde::Deserialize, serde::Serialize, Copy, Clone)]
-pub struct Segment {
-    pub segment: [Pos2; 2],
-}
+impl SingleStroke {
+    pub fn new(stroke: egui::Stroke, points: Vec<Pos2>) -> Self {
+        Self {
+            stroke,
+            points,
+            widths: Vec::new(),
+            cap: StrokeCap::default(),
+            kind: BrushKind::default(),
+        }
+    }
+
+    // Color the stroke is painted with
+    pub fn ink(&self) -> egui::Color32 {
+        self.kind.ink(self.stroke.color)
+    }
+
+    // Strokes drawn with pressure carry their own width at every point
+    pub fn is_tapered(&self) -> bool {
+        !self.widths.is_empty()
+    }
+
+    pub fn width_at(&self, index: usize) -> f32 {
+        self.widths.get(index).copied().unwrap_or(self.stroke.width)
+    }
+
+    pub fn max_width(&self) -> f32 {
+        self.widths
+            .iter()
+            .copied()
+            .fold(self.stroke.width, f32::max)
+    }
+
+    // Appends a point, keeping `widths` either empty or one entry per point
+    pub fn push(&mut self, point: Pos2, width: Option<f32>) {
+        match width {
+            Some(width) => {
+                self.widths.resize(self.points.len(), width);
+                self.widths.push(width);
+            }
+            None if self.is_tapered() => {
+                let last = self.widths.last().copied().unwrap_or(self.stroke.width);
+                self.widths.push(last);
+            }
+            None => {}
+        }
+        self.points.push(point);
+    }
+
+    // Pressure strokes always end round, whatever cap they were drawn with
+    pub fn end_cap(&self) -> StrokeCap {
+        if self.is_tapered() {
+            StrokeCap::Round
+        } else {
+            self.cap
+        }
+    }
+
+    // The points with square caps pushed out by half the width at both ends
+    pub fn capped_points(&self) -> Vec<Pos2> {
+        let mut points = self.points.clone();
+        if self.end_cap() != StrokeCap::Square {
+            return points;
+        }
+
+        let reach = self.stroke.width / 2.0;
+        if let (Some(&first), Some(&last)) = (points.first(), points.last()) {
+            let next = points.iter().find(|point| **point != first).copied();
+            let before = points.iter().rev().find(|point| **point != last).copied();
+            if let (Some(next), Some(first_point)) = (next, points.first_mut()) {
+                *first_point = first - (next - first).normalized() * reach;
+            }
+            if let (Some(before), Some(last_point)) = (before, points.last_mut()) {
+                *last_point = last + (last - before).normalized() * reach;
+            }
+        }
+        points
+    }
+
+    // A constant-width stroke as egui shapes: one path, so the tessellator joins the segments
+    // instead of overlapping them, and a disc on each end for round caps
+    pub fn line_shapes(&self, color: egui::Color32) -> Vec<egui::Shape> {
+        let mut shapes = vec![egui::Shape::line(
+            self.capped_points(),
+            egui::Stroke::new(self.stroke.width, color),
+        )];
+        if self.end_cap() == StrokeCap::Round
+            && let (Some(&first), Some(&last)) = (self.points.first(), self.points.last())
+        {
+            let radius = self.stroke.width / 2.0;
+            shapes.push(egui::Shape::circle_filled(first, radius, color));
+            shapes.push(egui::Shape::circle_filled(last, radius, color));
+        }
+        shapes
+    }
 
-impl Segment {
-    pub fn new(a: Pos2, b: Pos2) -> Self {
-        Self { segment: [a, b] }
+    // Consecutive point pairs
+    pub fn segments(&self) -> impl Iterator<Item = [Pos2; 2]> + '_ {
+        self.points.windows(2).filter_map(|pair| match pair {
+            [a, b] => Some([*a, *b]),
+            _ => None,
+        })
     }
 }
 
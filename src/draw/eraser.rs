@@ -0,0 +1,198 @@
+use super::canvas::{Action, SingleStroke};
+use crate::utils;
+use egui::Pos2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum EraserMode {
+    // Removes whole strokes
+    Stroke,
+    // Removes the touched segments, breaking the stroke into the pieces between them
+    Partial,
+    // Cuts strokes exactly along the edge of the eraser
+    Split,
+}
+
+impl EraserMode {
+    pub const ALL: [Self; 3] = [Self::Stroke, Self::Partial, Self::Split];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Stroke => "Stroke",
+            Self::Partial => "Partial",
+            Self::Split => "Split",
+        }
+    }
+}
+
+// Builds the actions erasing everything within `radius` of `eraser_pos` from `strokes`.
+// Actions are ordered from the highest stroke index down, so running them in order keeps
+// the remaining indices valid.
+pub fn erase_actions(
+    mode: EraserMode,
+    strokes: &[SingleStroke],
+    layer: usize,
+    eraser_pos: Pos2,
+    radius: f32,
+) -> Vec<Action> {
+    let all: Vec<usize> = (0..strokes.len()).collect();
+    erase_actions_among(mode, strokes, &all, layer, eraser_pos, radius)
+}
+
+// Same as `erase_actions`, but only tests the strokes at `candidates`, which must be in
+// ascending order. Used with `Canvas::strokes_in` to skip strokes far from the eraser.
+pub fn erase_actions_among(
+    mode: EraserMode,
+    strokes: &[SingleStroke],
+    candidates: &[usize],
+    layer: usize,
+    eraser_pos: Pos2,
+    radius: f32,
+) -> Vec<Action> {
+    let mut actions = Vec::new();
+
+    for (index, stroke) in candidates
+        .iter()
+        .rev()
+        .filter_map(|index| Some((*index, strokes.get(*index)?)))
+    {
+        let touched =
+            |segment: [Pos2; 2]| utils::cursor_to_segment_distance(eraser_pos, segment) <= radius;
+        let single_point_touched = stroke.points.len() == 1
+            && stroke
+                .points
+                .first()
+                .is_some_and(|point| point.distance(eraser_pos) <= radius);
+
+        if !single_point_touched && !stroke.segments().any(touched) {
+            continue;
+        }
+
+        let pieces = match mode {
+            EraserMode::Stroke => Vec::new(),
+            EraserMode::Partial => {
+                split_stroke(stroke, |segment| touched(segment).then_some((0.0, 1.0)))
+            }
+            EraserMode::Split => split_stroke(stroke, |segment| {
+                circle_overlap(segment, eraser_pos, radius)
+            }),
+        };
+        actions.extend(replace_stroke(stroke, pieces, index, layer));
+    }
+
+    actions
+}
+
+// Splits a stroke wherever `cut` returns the erased part of a segment, as the range of
+// its parameter from 0 at the start to 1 at the end. Pieces shorter than two points are
+// dropped.
+pub fn split_stroke(
+    stroke: &SingleStroke,
+    cut: impl Fn([Pos2; 2]) -> Option<(f32, f32)>,
+) -> Vec<SingleStroke> {
+    // Pieces are drawn with the same brush as the stroke they come from
+    let empty = || SingleStroke {
+        cap: stroke.cap,
+        kind: stroke.kind,
+        ..SingleStroke::new(stroke.stroke, Vec::new())
+    };
+    let mut pieces: Vec<SingleStroke> = Vec::new();
+    let mut current = empty();
+    let tapered = stroke.is_tapered();
+
+    let mut finish = |current: &mut SingleStroke| {
+        let piece = std::mem::replace(current, empty());
+        if piece.points.len() >= 2 {
+            pieces.push(piece);
+        }
+    };
+
+    for (index, segment) in stroke.segments().enumerate() {
+        let [a, b] = segment;
+        let width = |t: f32| {
+            tapered.then(|| egui::lerp(stroke.width_at(index)..=stroke.width_at(index + 1), t))
+        };
+        let push = |current: &mut SingleStroke, t: f32| {
+            let point = a.lerp(b, t);
+            if current.points.last() != Some(&point) {
+                current.push(point, width(t));
+            }
+        };
+
+        match cut(segment) {
+            None => {
+                push(&mut current, 0.0);
+                push(&mut current, 1.0);
+            }
+            Some((from, to)) => {
+                if from > 0.0 {
+                    push(&mut current, 0.0);
+                    push(&mut current, from);
+                }
+                finish(&mut current);
+                if to < 1.0 {
+                    push(&mut current, to);
+                    push(&mut current, 1.0);
+                }
+            }
+        }
+    }
+    finish(&mut current);
+
+    pieces
+}
+
+// Range of the segment's parameter lying inside the circle, if any
+fn circle_overlap([a, b]: [Pos2; 2], center: Pos2, radius: f32) -> Option<(f32, f32)> {
+    let direction = b - a;
+    let offset = a - center;
+    let qa = direction.length_sq();
+    if qa <= f32::EPSILON {
+        return (offset.length() <= radius).then_some((0.0, 1.0));
+    }
+
+    // Solve |a + t * direction - center| = radius for t
+    let qb = 2.0 * offset.dot(direction);
+    let qc = offset.length_sq() - radius * radius;
+    let discriminant = qb * qb - 4.0 * qa * qc;
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    let root = discriminant.sqrt();
+    let from = ((-qb - root) / (2.0 * qa)).max(0.0);
+    let to = ((-qb + root) / (2.0 * qa)).min(1.0);
+
+    (from < to).then_some((from, to))
+}
+
+// Swaps the stroke at `index` for `pieces`, which end up in its place in order
+fn replace_stroke(
+    stroke: &SingleStroke,
+    pieces: Vec<SingleStroke>,
+    index: usize,
+    layer: usize,
+) -> Vec<Action> {
+    let mut pieces = pieces.into_iter();
+
+    let Some(first) = pieces.next() else {
+        return vec![Action::RemoveStroke {
+            stroke: stroke.clone(),
+            index,
+            layer,
+        }];
+    };
+
+    let mut actions = vec![Action::ModifyStroke {
+        before: Some(stroke.clone()),
+        after: Some(first),
+        index,
+        layer,
+    }];
+    actions.extend(pieces.enumerate().map(|(offset, piece)| Action::AddStroke {
+        stroke: piece,
+        index: index + offset + 1,
+        layer,
+    }));
+
+    actions
+}
@@ -0,0 +1,102 @@
+use super::brush::BrushKind;
+use super::canvas::{Canvas, Layer, SingleStroke};
+use super::shapes::Shape;
+use super::text::TextBox;
+use crate::utils::cursor_to_segment_distance;
+use egui::{Color32, Pos2, Rect, Vec2};
+
+// Color of the canvas wherever nothing is drawn
+pub const BACKGROUND: Color32 = Color32::WHITE;
+
+// Color of the topmost object under `pos` on a visible layer, or the background.
+// Hit-tests the vector model, so the result is the same at any zoom. Texts need laying out
+// to know what they cover, which `text_bounds` does.
+pub fn color_at(canvas: &Canvas, pos: Pos2, text_bounds: impl Fn(&TextBox) -> Rect) -> Color32 {
+    canvas
+        .layers
+        .iter()
+        .enumerate()
+        .rev()
+        .filter(|(_, layer)| layer.visible)
+        .find_map(|(index, layer)| layer_color_at(canvas, index, layer, pos, &text_bounds))
+        .unwrap_or(BACKGROUND)
+}
+
+// Checks objects in the reverse of the order they are painted in
+fn layer_color_at(
+    canvas: &Canvas,
+    index: usize,
+    layer: &Layer,
+    pos: Pos2,
+    text_bounds: &impl Fn(&TextBox) -> Rect,
+) -> Option<Color32> {
+    if let Some(text) = layer
+        .texts
+        .iter()
+        .rev()
+        .find(|text| text_bounds(text).contains(pos))
+    {
+        return Some(text.color);
+    }
+
+    if let Some(shape) = layer
+        .shapes
+        .iter()
+        .rev()
+        .find(|shape| shape_hit(shape, pos))
+    {
+        return Some(shape.stroke.color);
+    }
+
+    // Segment bounds are indexed without their width, so look as far as the widest stroke on
+    // the layer reaches; scaling a selection can make strokes wider than the pen goes
+    let widest = layer
+        .strokes
+        .iter()
+        .map(SingleStroke::max_width)
+        .fold(0.0, f32::max);
+    let reach = Rect::from_center_size(pos, Vec2::splat(widest));
+    let stroke = canvas
+        .strokes_in(index, reach)
+        .into_iter()
+        .rev()
+        .filter_map(|stroke| layer.strokes.get(stroke))
+        .filter(|stroke| stroke_hit(stroke, pos))
+        // Pen strokes are painted over highlighter ink
+        .min_by_key(|stroke| stroke.kind == BrushKind::Highlighter);
+    if let Some(stroke) = stroke {
+        return Some(stroke.stroke.color);
+    }
+
+    layer
+        .fills
+        .iter()
+        .rev()
+        .find(|fill| fill.contains(pos))
+        .map(|fill| fill.color)
+}
+
+// Pressure strokes change width linearly along each segment, as they are painted
+fn stroke_hit(stroke: &SingleStroke, pos: Pos2) -> bool {
+    if let [point] = stroke.points.as_slice() {
+        return point.distance(pos) <= stroke.width_at(0) / 2.0;
+    }
+
+    stroke.segments().enumerate().any(|(index, [a, b])| {
+        let ab = b - a;
+        let t = if ab.length_sq() <= f32::EPSILON {
+            0.0
+        } else {
+            ((pos - a).dot(ab) / ab.length_sq()).clamp(0.0, 1.0)
+        };
+        let width = egui::lerp(stroke.width_at(index)..=stroke.width_at(index + 1), t);
+        pos.distance(a + ab * t) <= width / 2.0
+    })
+}
+
+fn shape_hit(shape: &Shape, pos: Pos2) -> bool {
+    shape.outline().windows(2).any(|pair| match pair {
+        [a, b] => cursor_to_segment_distance(pos, [*a, *b]) <= shape.stroke.width / 2.0,
+        _ => false,
+    })
+}
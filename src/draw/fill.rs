@@ -0,0 +1,407 @@
+use super::canvas::{Layer, SingleStroke};
+use super::selection;
+use super::simplify;
+use crate::utils;
+use egui::{Color32, Mesh, Pos2, Rect, Vec2};
+use std::collections::{BTreeMap, VecDeque};
+use std::ops::RangeInclusive;
+use std::sync::OnceLock;
+
+// Widest gap in an outline, in canvas units, that still counts as closed
+pub const GAP_RANGE: RangeInclusive<f32> = 0.0..=30.0;
+pub const DEFAULT_GAP: f32 = 4.0;
+// Grid cells along the longer side of the canvas when looking for a region
+const RESOLUTION: f32 = 1024.0;
+
+// Column and row of a grid corner
+type Corner = (isize, isize);
+
+// A region colored in with the bucket tool, painted beneath the strokes of its layer
+#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct Fill {
+    pub color: Color32,
+    // Closed outlines; whatever lies inside an odd number of them is filled
+    pub contours: Vec<Vec<Pos2>>,
+    #[serde(skip)]
+    triangles: OnceLock<Vec<Pos2>>,
+}
+
+impl Fill {
+    pub fn new(color: Color32, contours: Vec<Vec<Pos2>>) -> Self {
+        Self {
+            color,
+            contours,
+            triangles: OnceLock::new(),
+        }
+    }
+
+    pub fn bounds(&self) -> Rect {
+        Rect::from_points(&self.contours.concat())
+    }
+
+    pub fn contains(&self, point: Pos2) -> bool {
+        self.contours
+            .iter()
+            .filter(|contour| selection::point_in_polygon(point, contour))
+            .count()
+            % 2
+            == 1
+    }
+
+    // The region split into triangles, three points each, worked out on first use
+    pub fn triangles(&self) -> &[Pos2] {
+        self.triangles.get_or_init(|| triangulate(&self.contours))
+    }
+
+    pub fn mesh(&self, color: Color32) -> Mesh {
+        let mut mesh = Mesh::default();
+        for triangle in self.triangles().chunks_exact(3) {
+            let first = mesh.vertices.len() as u32;
+            for point in triangle {
+                mesh.colored_vertex(*point, color);
+            }
+            mesh.add_triangle(first, first + 1, first + 2);
+        }
+        mesh
+    }
+}
+
+// A centerline the fill can't cross, with half the width of the line drawn along it
+pub struct Wall {
+    pub points: Vec<Pos2>,
+    pub half_width: f32,
+}
+
+// Everything drawn on `layer` that bounds a region
+pub fn walls(layer: &Layer) -> Vec<Wall> {
+    let strokes = layer.strokes.iter().map(|stroke| Wall {
+        points: stroke.points.clone(),
+        half_width: (0..stroke.points.len())
+            .map(|index| stroke.width_at(index))
+            .fold(0.0, f32::max)
+            / 2.0,
+    });
+    let shapes = layer.shapes.iter().map(|shape| Wall {
+        points: shape.outline(),
+        half_width: shape.stroke.width / 2.0,
+    });
+
+    strokes.chain(shapes).collect()
+}
+
+// Finds the region around `seed` enclosed by `walls` and the edges of `area`, and returns
+// its outlines. Gaps in the walls up to `gap` wide are bridged. The region reaches to the
+// middle of the surrounding lines, so no sliver shows between the fill and its outline.
+pub fn find_region(area: Rect, walls: &[Wall], seed: Pos2, gap: f32) -> Option<Vec<Vec<Pos2>>> {
+    if !area.contains(seed) {
+        return None;
+    }
+
+    let grid = Grid::new(area);
+    let depth = grid.walls(walls, gap / 2.0);
+    let start = grid.cell_of(seed)?;
+    if depth.get(start).is_some_and(Option::is_some) {
+        return None;
+    }
+
+    let mut filled = grid.flood(start, |cell| depth.get(cell).is_some_and(Option::is_none));
+    grid.grow_into_walls(&mut filled, &depth);
+
+    let contours: Vec<Vec<Pos2>> = grid
+        .trace(&filled)
+        .into_iter()
+        .map(|contour| simplify_contour(&contour, grid.cell_size / 2.0))
+        .filter(|contour| contour.len() >= 3)
+        .collect();
+
+    (!contours.is_empty()).then_some(contours)
+}
+
+struct Grid {
+    origin: Pos2,
+    cell_size: f32,
+    columns: usize,
+    rows: usize,
+}
+
+impl Grid {
+    fn new(area: Rect) -> Self {
+        let cell_size = (area.width().max(area.height()) / RESOLUTION).max(0.5);
+        Self {
+            origin: area.min,
+            cell_size,
+            columns: (area.width() / cell_size).ceil().max(1.0) as usize,
+            rows: (area.height() / cell_size).ceil().max(1.0) as usize,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.columns * self.rows
+    }
+
+    fn cell_of(&self, pos: Pos2) -> Option<usize> {
+        let local = (pos - self.origin) / self.cell_size;
+        let (column, row) = (local.x.floor(), local.y.floor());
+        if column < 0.0 || row < 0.0 {
+            return None;
+        }
+        let (column, row) = (column as usize, row as usize);
+        (column < self.columns && row < self.rows).then_some(row * self.columns + column)
+    }
+
+    fn center(&self, column: usize, row: usize) -> Pos2 {
+        self.origin + Vec2::new(column as f32 + 0.5, row as f32 + 0.5) * self.cell_size
+    }
+
+    // Neighbors sharing a side with `cell`
+    fn neighbors(&self, cell: usize) -> impl Iterator<Item = usize> {
+        let (column, row) = (cell % self.columns, cell / self.columns);
+        let columns = self.columns;
+        [
+            (column > 0).then(|| cell - 1),
+            (column + 1 < columns).then_some(cell + 1),
+            (row > 0).then(|| cell - columns),
+            (row + 1 < self.rows).then_some(cell + columns),
+        ]
+        .into_iter()
+        .flatten()
+    }
+
+    // For every cell covered by a wall thickened by `extra` on each side, how far it may
+    // be grown into from outside before crossing the middle of the wall
+    fn walls(&self, walls: &[Wall], extra: f32) -> Vec<Option<f32>> {
+        let mut depth: Vec<Option<f32>> = vec![None; self.len()];
+        // Thin lines still need to cover every cell they pass through to keep the fill in
+        let min_radius = self.cell_size * std::f32::consts::FRAC_1_SQRT_2 + 1e-3;
+
+        for wall in walls {
+            let radius = (wall.half_width + extra).max(min_radius);
+            let segments: Vec<[Pos2; 2]> = match wall.points.as_slice() {
+                [point] => vec![[*point, *point]],
+                points => points
+                    .windows(2)
+                    .filter_map(|pair| match pair {
+                        [a, b] => Some([*a, *b]),
+                        _ => None,
+                    })
+                    .collect(),
+            };
+
+            for [a, b] in segments {
+                let bounds = Rect::from_two_pos(a, b).expand(radius);
+                let (Some(min), Some(max)) =
+                    (self.clamped_cell(bounds.min), self.clamped_cell(bounds.max))
+                else {
+                    continue;
+                };
+
+                for row in min.1..=max.1 {
+                    for column in min.0..=max.0 {
+                        let distance =
+                            utils::cursor_to_segment_distance(self.center(column, row), [a, b]);
+                        if distance > radius {
+                            continue;
+                        }
+                        if let Some(cell) = depth.get_mut(row * self.columns + column) {
+                            let reach = radius - distance;
+                            *cell = Some(cell.map_or(reach, |old| old.max(reach)));
+                        }
+                    }
+                }
+            }
+        }
+
+        depth
+    }
+
+    // Column and row of `pos`, pulled into the grid; `None` if the grid is empty
+    fn clamped_cell(&self, pos: Pos2) -> Option<(usize, usize)> {
+        let local = (pos - self.origin) / self.cell_size;
+        let column = local
+            .x
+            .floor()
+            .clamp(0.0, self.columns.checked_sub(1)? as f32);
+        let row = local.y.floor().clamp(0.0, self.rows.checked_sub(1)? as f32);
+        Some((column as usize, row as usize))
+    }
+
+    fn flood(&self, start: usize, open: impl Fn(usize) -> bool) -> Vec<bool> {
+        let mut filled = vec![false; self.len()];
+        let mut queue = VecDeque::from([start]);
+        if let Some(cell) = filled.get_mut(start) {
+            *cell = true;
+        }
+
+        while let Some(cell) = queue.pop_front() {
+            for next in self.neighbors(cell) {
+                if let Some(visited) = filled.get_mut(next)
+                    && !*visited
+                    && open(next)
+                {
+                    *visited = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        filled
+    }
+
+    // Pushes the region back over the thickened walls, up to their middle
+    fn grow_into_walls(&self, filled: &mut [bool], depth: &[Option<f32>]) {
+        let mut steps: Vec<Option<u32>> = filled.iter().map(|f| f.then_some(0)).collect();
+        let mut queue: VecDeque<usize> = (0..filled.len())
+            .filter(|cell| filled.get(*cell).copied().unwrap_or(false))
+            .collect();
+
+        while let Some(cell) = queue.pop_front() {
+            let step = steps.get(cell).copied().flatten().unwrap_or(0) + 1;
+            for next in self.neighbors(cell) {
+                let Some(Some(reach)) = depth.get(next).copied() else {
+                    continue;
+                };
+                if steps.get(next).copied().flatten().is_some()
+                    || step as f32 * self.cell_size > reach + self.cell_size / 2.0
+                {
+                    continue;
+                }
+                if let (Some(slot), Some(cell_filled)) = (steps.get_mut(next), filled.get_mut(next))
+                {
+                    *slot = Some(step);
+                    *cell_filled = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+
+    // Outlines of the filled cells, following cell edges with the region on the right
+    fn trace(&self, filled: &[bool]) -> Vec<Vec<Pos2>> {
+        let is_filled = |column: isize, row: isize| {
+            column >= 0
+                && row >= 0
+                && (column as usize) < self.columns
+                && (row as usize) < self.rows
+                && filled
+                    .get(row as usize * self.columns + column as usize)
+                    .copied()
+                    .unwrap_or(false)
+        };
+
+        let mut edges: BTreeMap<Corner, Vec<Corner>> = BTreeMap::new();
+        for row in 0..self.rows as isize {
+            for column in 0..self.columns as isize {
+                if !is_filled(column, row) {
+                    continue;
+                }
+                let (x, y) = (column, row);
+                let sides = [
+                    (!is_filled(x, y - 1), (x, y), (x + 1, y)),
+                    (!is_filled(x + 1, y), (x + 1, y), (x + 1, y + 1)),
+                    (!is_filled(x, y + 1), (x + 1, y + 1), (x, y + 1)),
+                    (!is_filled(x - 1, y), (x, y + 1), (x, y)),
+                ];
+                for (open, from, to) in sides {
+                    if open {
+                        edges.entry(from).or_default().push(to);
+                    }
+                }
+            }
+        }
+
+        let mut contours = Vec::new();
+        while let Some((&start, _)) = edges.first_key_value() {
+            let mut contour = vec![start];
+            let mut current = start;
+
+            while let Some(next) = take_edge(&mut edges, current) {
+                if next == start {
+                    break;
+                }
+                contour.push(next);
+                current = next;
+            }
+
+            contours.push(
+                contour
+                    .into_iter()
+                    .map(|(x, y)| self.origin + Vec2::new(x as f32, y as f32) * self.cell_size)
+                    .collect(),
+            );
+        }
+
+        contours
+    }
+}
+
+fn take_edge(edges: &mut BTreeMap<Corner, Vec<Corner>>, from: Corner) -> Option<Corner> {
+    let targets = edges.get_mut(&from)?;
+    let next = targets.pop();
+    if targets.is_empty() {
+        edges.remove(&from);
+    }
+    next
+}
+
+// Straightens the staircase left by the grid
+fn simplify_contour(contour: &[Pos2], tolerance: f32) -> Vec<Pos2> {
+    let mut points = contour.to_vec();
+    points.extend(contour.first());
+
+    let (simplified, _) =
+        simplify::simplify(&SingleStroke::new(egui::Stroke::NONE, points), tolerance);
+    let mut points = simplified.points;
+    points.pop();
+    points
+}
+
+// Cuts the region into horizontal slabs between vertex heights. Within a slab no edges
+// cross, so pairing them up left to right gives the filled trapezoids.
+fn triangulate(contours: &[Vec<Pos2>]) -> Vec<Pos2> {
+    let edges: Vec<[Pos2; 2]> = contours
+        .iter()
+        .flat_map(|contour| {
+            contour
+                .iter()
+                .zip(contour.iter().cycle().skip(1))
+                .map(|(a, b)| [*a, *b])
+        })
+        .filter(|[a, b]| a.y != b.y)
+        .collect();
+
+    let mut heights: Vec<f32> = contours.iter().flatten().map(|point| point.y).collect();
+    heights.sort_by(f32::total_cmp);
+    heights.dedup();
+
+    let x_at = |[a, b]: [Pos2; 2], y: f32| a.x + (b.x - a.x) * (y - a.y) / (b.y - a.y);
+    let mut triangles = Vec::new();
+
+    for slab in heights.windows(2) {
+        let [top, bottom] = slab else {
+            continue;
+        };
+        let middle = (top + bottom) / 2.0;
+        let mut crossings: Vec<(f32, f32, f32)> = edges
+            .iter()
+            .filter(|[a, b]| a.y.min(b.y) <= middle && a.y.max(b.y) > middle)
+            .map(|edge| (x_at(*edge, middle), x_at(*edge, *top), x_at(*edge, *bottom)))
+            .collect();
+        crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        for pair in crossings.chunks_exact(2) {
+            let [(_, left_top, left_bottom), (_, right_top, right_bottom)] = pair else {
+                continue;
+            };
+            let corners = [
+                Pos2::new(*left_top, *top),
+                Pos2::new(*right_top, *top),
+                Pos2::new(*right_bottom, *bottom),
+                Pos2::new(*left_bottom, *bottom),
+            ];
+            let [a, b, c, d] = corners;
+            triangles.extend([a, b, c, a, c, d]);
+        }
+    }
+
+    triangles
+}
@@ -0,0 +1,297 @@
+use super::brush::StrokeCap;
+use super::canvas::SingleStroke;
+use egui::{Color32, Mesh, Pos2, Rect, Vec2};
+
+// Highlighter ink is painted at this fraction of the alpha of its color
+pub const OPACITY: f32 = 0.4;
+// Corners of the polygons standing in for round ends
+const ROUND_STEPS: usize = 16;
+
+// An edge of the pieces a stroke is made of, pointing down, with the direction it had
+struct Edge {
+    top: Pos2,
+    bottom: Pos2,
+    winding: i32,
+}
+
+impl Edge {
+    fn x_at(&self, y: f32) -> f32 {
+        let t = (y - self.top.y) / (self.bottom.y - self.top.y);
+        self.top.x + (self.bottom.x - self.top.x) * t
+    }
+}
+
+// The area a highlighter stroke covers, as triangles of three points each. The triangles
+// don't overlap, so ink laid over itself within one stroke is only blended once.
+pub fn triangles(stroke: &SingleStroke) -> Vec<Pos2> {
+    let pieces = pieces(stroke);
+    let Some(widest) = pieces
+        .iter()
+        .map(|piece| Rect::from_points(piece).width())
+        .max_by(f32::total_cmp)
+    else {
+        return Vec::new();
+    };
+    let bounds = Rect::from_points(&pieces.concat());
+
+    // Pieces only overlap their neighbours, unless the stroke crosses itself, so the
+    // union is worked out strip by strip instead of across the whole stroke at once
+    let strip = (widest * 2.0).max(1.0);
+    let strips = (bounds.width() / strip).ceil().max(1.0) as usize;
+    (0..strips)
+        .flat_map(|index| {
+            let left = bounds.min.x + index as f32 * strip;
+            let right = if index + 1 == strips {
+                bounds.max.x
+            } else {
+                left + strip
+            };
+            let clipped: Vec<Vec<Pos2>> = pieces
+                .iter()
+                .filter_map(|piece| clip(piece, left, right))
+                .collect();
+            union(&clipped)
+        })
+        .collect()
+}
+
+pub fn mesh(stroke: &SingleStroke, color: Color32) -> Mesh {
+    let mut mesh = Mesh::default();
+    for triangle in triangles(stroke).chunks_exact(3) {
+        let first = mesh.vertices.len() as u32;
+        for point in triangle {
+            mesh.colored_vertex(*point, color);
+        }
+        mesh.add_triangle(first, first + 1, first + 2);
+    }
+    mesh
+}
+
+// Convex polygons that together cover the stroke: a quad per segment, wedges closing the
+// gaps at the joins and discs at round ends. All of them wind the same way.
+fn pieces(stroke: &SingleStroke) -> Vec<Vec<Pos2>> {
+    let mut samples: Vec<(Pos2, f32)> = stroke
+        .capped_points()
+        .into_iter()
+        .enumerate()
+        .map(|(index, point)| (point, stroke.width_at(index) / 2.0))
+        .collect();
+    samples.dedup_by(|a, b| a.0 == b.0);
+
+    let normals: Vec<Vec2> = samples
+        .windows(2)
+        .filter_map(|pair| match pair {
+            [(a, _), (b, _)] => Some((*b - *a).normalized().rot90()),
+            _ => None,
+        })
+        .collect();
+    let mut pieces = Vec::new();
+
+    for (pair, normal) in samples.windows(2).zip(&normals) {
+        if let [(a, half_a), (b, half_b)] = pair {
+            pieces.push(vec![
+                *a + *normal * *half_a,
+                *b + *normal * *half_b,
+                *b - *normal * *half_b,
+                *a - *normal * *half_a,
+            ]);
+        }
+    }
+
+    // The quads leave a gap on the outside of every turn; fill both sides, the inner wedge
+    // just overlaps
+    for ((point, half_width), pair) in samples.iter().skip(1).zip(normals.windows(2)) {
+        if let [before, after] = pair {
+            pieces.push(wedge(*point, *before * *half_width, *after * *half_width));
+            pieces.push(wedge(*point, -*before * *half_width, -*after * *half_width));
+        }
+    }
+
+    // Round ends, or a dot for a stroke that never moved
+    if stroke.end_cap() == StrokeCap::Round || samples.len() == 1 {
+        let last = samples.last().filter(|_| samples.len() > 1);
+        for (point, half_width) in samples.first().into_iter().chain(last) {
+            pieces.push(disc(*point, *half_width));
+        }
+    }
+
+    for piece in &mut pieces {
+        if signed_area(piece) < 0.0 {
+            piece.reverse();
+        }
+    }
+    pieces.retain(|piece| piece.len() >= 3);
+    pieces
+}
+
+// Circle sector around `center` from `from` to `to`, the shorter way round
+fn wedge(center: Pos2, from: Vec2, to: Vec2) -> Vec<Pos2> {
+    let angle = from.angle() - to.angle();
+    let turn =
+        (angle + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU) - std::f32::consts::PI;
+    let steps = (turn.abs() / (std::f32::consts::TAU / ROUND_STEPS as f32)).ceil() as usize;
+    // Straight on, there is no gap to fill
+    if steps == 0 {
+        return Vec::new();
+    }
+
+    std::iter::once(center)
+        .chain((0..=steps).map(|step| {
+            let t = step as f32 / steps as f32;
+            center + rotate(from, -turn * t)
+        }))
+        .collect()
+}
+
+fn disc(center: Pos2, radius: f32) -> Vec<Pos2> {
+    (0..ROUND_STEPS)
+        .map(|step| {
+            let angle = std::f32::consts::TAU * step as f32 / ROUND_STEPS as f32;
+            center + Vec2::angled(angle) * radius
+        })
+        .collect()
+}
+
+fn rotate(v: Vec2, angle: f32) -> Vec2 {
+    let (sin, cos) = angle.sin_cos();
+    Vec2::new(v.x * cos - v.y * sin, v.x * sin + v.y * cos)
+}
+
+fn signed_area(polygon: &[Pos2]) -> f32 {
+    polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .map(|(a, b)| a.x * b.y - b.x * a.y)
+        .sum::<f32>()
+        / 2.0
+}
+
+// The part of a convex polygon between two vertical lines
+fn clip(polygon: &[Pos2], left: f32, right: f32) -> Option<Vec<Pos2>> {
+    let bounds = Rect::from_points(polygon);
+    if bounds.max.x <= left || bounds.min.x >= right {
+        return None;
+    }
+    if bounds.min.x >= left && bounds.max.x <= right {
+        return Some(polygon.to_vec());
+    }
+
+    let clipped = clip_side(polygon, |x| x >= left, left);
+    let clipped = clip_side(&clipped, |x| x <= right, right);
+    (clipped.len() >= 3).then_some(clipped)
+}
+
+fn clip_side(polygon: &[Pos2], inside: impl Fn(f32) -> bool, x: f32) -> Vec<Pos2> {
+    let cut = |a: Pos2, b: Pos2| Pos2::new(x, a.y + (b.y - a.y) * (x - a.x) / (b.x - a.x));
+    let mut clipped = Vec::new();
+
+    for (a, b) in polygon.iter().zip(polygon.iter().cycle().skip(1)) {
+        match (inside(a.x), inside(b.x)) {
+            (true, true) => clipped.push(*b),
+            (true, false) => clipped.push(cut(*a, *b)),
+            (false, true) => clipped.extend([cut(*a, *b), *b]),
+            (false, false) => {}
+        }
+    }
+    clipped
+}
+
+// Cuts the pieces into horizontal slabs between vertex heights and the heights where
+// edges cross. Within a slab no edges cross, so walking the edges left to right and
+// keeping what lies inside any piece gives the covered trapezoids.
+fn union(pieces: &[Vec<Pos2>]) -> Vec<Pos2> {
+    let mut edges: Vec<Edge> = pieces
+        .iter()
+        .flat_map(|piece| piece.iter().zip(piece.iter().cycle().skip(1)))
+        .filter(|(a, b)| a.y != b.y)
+        .map(|(a, b)| {
+            let (top, bottom, winding) = if a.y < b.y { (a, b, 1) } else { (b, a, -1) };
+            Edge {
+                top: *top,
+                bottom: *bottom,
+                winding,
+            }
+        })
+        .collect();
+    edges.sort_by(|a, b| a.top.y.total_cmp(&b.top.y));
+
+    let mut heights: Vec<f32> = edges
+        .iter()
+        .flat_map(|edge| [edge.top.y, edge.bottom.y])
+        .collect();
+    for (index, edge) in edges.iter().enumerate() {
+        // Sorted by their tops, so the edges after the first one starting below can't cross
+        let overlapping = edges
+            .iter()
+            .skip(index + 1)
+            .take_while(|other| other.top.y < edge.bottom.y);
+        heights.extend(overlapping.filter_map(|other| crossing_height(edge, other)));
+    }
+    heights.sort_by(f32::total_cmp);
+    heights.dedup();
+
+    let mut triangles = Vec::new();
+    let mut next_edge = 0;
+    let mut active: Vec<&Edge> = Vec::new();
+    for slab in heights.windows(2) {
+        let [top, bottom] = slab else {
+            continue;
+        };
+        let middle = (top + bottom) / 2.0;
+        while let Some(edge) = edges.get(next_edge)
+            && edge.top.y <= middle
+        {
+            active.push(edge);
+            next_edge += 1;
+        }
+        active.retain(|edge| edge.bottom.y > middle);
+
+        let mut crossings: Vec<(f32, &Edge)> = active
+            .iter()
+            .map(|edge| (edge.x_at(middle), *edge))
+            .collect();
+        crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        // Nonzero winding: inside as long as any piece is
+        let mut winding = 0;
+        let mut left: Option<&Edge> = None;
+        for (_, edge) in crossings {
+            let outside = winding == 0;
+            winding += edge.winding;
+            if outside && winding != 0 {
+                left = Some(edge);
+            } else if !outside
+                && winding == 0
+                && let Some(left) = left.take()
+            {
+                let a = Pos2::new(left.x_at(*top), *top);
+                let b = Pos2::new(edge.x_at(*top), *top);
+                let c = Pos2::new(edge.x_at(*bottom), *bottom);
+                let d = Pos2::new(left.x_at(*bottom), *bottom);
+                triangles.extend([a, b, c, a, c, d]);
+            }
+        }
+    }
+
+    triangles
+}
+
+// Height at which two edges cross, if they do
+fn crossing_height(a: &Edge, b: &Edge) -> Option<f32> {
+    let (a_left, a_right) = (a.top.x.min(a.bottom.x), a.top.x.max(a.bottom.x));
+    if b.top.x.max(b.bottom.x) < a_left || b.top.x.min(b.bottom.x) > a_right {
+        return None;
+    }
+
+    let r = a.bottom - a.top;
+    let s = b.bottom - b.top;
+    let denominator = r.x * s.y - r.y * s.x;
+    if denominator.abs() <= f32::EPSILON {
+        return None;
+    }
+    let offset = b.top - a.top;
+    let t = (offset.x * s.y - offset.y * s.x) / denominator;
+    let u = (offset.x * r.y - offset.y * r.x) / denominator;
+
+    ((0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u)).then_some(a.top.y + r.y * t)
+}
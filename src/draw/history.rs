@@ -0,0 +1,90 @@
+use super::canvas::{Action, Canvas};
+
+// Command stack for canvas edits. Every action on `undo` has been applied to the canvas and
+// every action on `redo` has been reverted, so the two stacks always agree with the canvas.
+#[derive(serde::Deserialize, serde::Serialize, Default)]
+pub struct History {
+    undo: Vec<Action>,
+    redo: Vec<Action>,
+    // Actions applied since `begin`, waiting to become a single undo step
+    #[serde(skip)]
+    transaction: Option<Vec<Action>>,
+    #[serde(skip)]
+    depth: usize,
+}
+
+impl History {
+    // Applies a new action. Anything that was undone can no longer be redone.
+    pub fn execute(&mut self, action: Action, canvas: &mut Canvas) {
+        action.execute(canvas);
+        self.redo.clear();
+
+        match &mut self.transaction {
+            Some(actions) => actions.push(action),
+            None => self.undo.push(action),
+        }
+    }
+
+    // Groups every action executed until the matching `commit` into one undo step.
+    // Transactions nest; only the outermost commit records the group.
+    pub fn begin(&mut self) {
+        self.depth += 1;
+        self.transaction.get_or_insert_with(Vec::new);
+    }
+
+    pub fn commit(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+        if self.depth > 0 {
+            return;
+        }
+
+        match self.transaction.take() {
+            Some(mut actions) if actions.len() == 1 => self.undo.extend(actions.pop()),
+            Some(actions) if !actions.is_empty() => self.undo.push(Action::Group { actions }),
+            _ => {}
+        }
+    }
+
+    pub fn in_transaction(&self) -> bool {
+        self.transaction.is_some()
+    }
+
+    pub fn undo(&mut self, canvas: &mut Canvas) {
+        self.commit_all();
+
+        if let Some(action) = self.undo.pop() {
+            action.undo(canvas);
+            self.redo.push(action);
+        }
+    }
+
+    pub fn redo(&mut self, canvas: &mut Canvas) {
+        self.commit_all();
+
+        if let Some(action) = self.redo.pop() {
+            action.execute(canvas);
+            self.undo.push(action);
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty() || self.transaction.as_ref().is_some_and(|a| !a.is_empty())
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.transaction = None;
+        self.depth = 0;
+    }
+
+    // Closes any open transaction so its actions can be undone as one step
+    fn commit_all(&mut self) {
+        self.depth = 1;
+        self.commit();
+    }
+}
@@ -0,0 +1,171 @@
+use super::brush::BrushKind;
+use super::canvas::{Layer, SingleStroke};
+use super::highlighter;
+use super::pressure;
+use egui::epaint::{TessellationOptions, Tessellator};
+use egui::{Color32, Mesh, Shape};
+use std::sync::Arc;
+
+// Turns strokes into meshes in canvas units, for a given number of physical pixels per unit
+pub struct StrokeTessellator {
+    tessellator: Tessellator,
+    pixels_per_unit: f32,
+}
+
+impl StrokeTessellator {
+    pub fn new(
+        pixels_per_unit: f32,
+        mut options: TessellationOptions,
+        font_tex_size: [usize; 2],
+    ) -> Self {
+        // The view pans by fractions of a pixel, so snapping in canvas units would only wobble
+        options.round_line_segments_to_pixels = false;
+        options.round_rects_to_pixels = false;
+
+        // Zooming changes the scale every frame; rounding it to a power of two keeps the
+        // cached meshes until the feathering would be off by more than a factor of √2
+        let pixels_per_unit = pixels_per_unit.log2().round().exp2();
+
+        Self {
+            tessellator: Tessellator::new(pixels_per_unit, options, font_tex_size, Vec::new()),
+            pixels_per_unit,
+        }
+    }
+
+    pub fn pixels_per_unit(&self) -> f32 {
+        self.pixels_per_unit
+    }
+
+    pub fn tessellate(&mut self, stroke: &SingleStroke, color: Color32) -> Mesh {
+        if stroke.kind == BrushKind::Highlighter {
+            return highlighter::mesh(stroke, color);
+        }
+
+        // egui lines keep one width, so pressure strokes come from the outline mesh
+        if stroke.is_tapered() {
+            // Fade the edge out over about one physical pixel
+            return pressure::stroke_mesh(stroke, color, 1.0 / self.pixels_per_unit);
+        }
+
+        let mut mesh = Mesh::default();
+        for shape in stroke.line_shapes(color) {
+            self.tessellator.tessellate_shape(shape, &mut mesh);
+        }
+        mesh
+    }
+}
+
+// Meshes of committed strokes, kept per layer in stroke order. Entries are dropped when
+// their stroke changes and rebuilt the next time they are painted.
+#[derive(Debug, Default)]
+pub struct MeshCache {
+    layers: Vec<Vec<Option<CachedMesh>>>,
+    pixels_per_unit: f32,
+}
+
+#[derive(Debug)]
+struct CachedMesh {
+    color: Color32,
+    mesh: Arc<Mesh>,
+}
+
+impl MeshCache {
+    // Forgets every mesh, e.g. after layers were added, removed or reordered
+    pub fn reset(&mut self, layers: &[Layer]) {
+        self.layers = layers
+            .iter()
+            .map(|layer| layer.strokes.iter().map(|_| None).collect())
+            .collect();
+    }
+
+    pub fn insert(&mut self, layer: usize, index: usize) {
+        if let Some(meshes) = self.layers.get_mut(layer)
+            && index <= meshes.len()
+        {
+            meshes.insert(index, None);
+        }
+    }
+
+    pub fn remove(&mut self, layer: usize, index: usize) {
+        if let Some(meshes) = self.layers.get_mut(layer)
+            && index < meshes.len()
+        {
+            meshes.remove(index);
+        }
+    }
+
+    pub fn invalidate(&mut self, layer: usize, index: usize) {
+        if let Some(mesh) = self
+            .layers
+            .get_mut(layer)
+            .and_then(|meshes| meshes.get_mut(index))
+        {
+            *mesh = None;
+        }
+    }
+
+    pub fn is_cached(&self, layer: usize, index: usize) -> bool {
+        self.layers
+            .get(layer)
+            .and_then(|meshes| meshes.get(index))
+            .is_some_and(Option::is_some)
+    }
+
+    // One shape per stroke of `layer` in paint order, tessellating only strokes without an
+    // up to date mesh
+    pub fn shapes(
+        &mut self,
+        layers: &[Layer],
+        layer: usize,
+        tessellator: &mut StrokeTessellator,
+    ) -> Vec<Shape> {
+        let Some(source) = layers.get(layer) else {
+            return Vec::new();
+        };
+
+        // Zooming changes how wide a physical pixel is, and with it the feathering
+        if self.pixels_per_unit != tessellator.pixels_per_unit() {
+            self.pixels_per_unit = tessellator.pixels_per_unit();
+            self.reset(layers);
+        }
+        if self.layers.len() != layers.len() {
+            self.reset(layers);
+        }
+        let Some(meshes) = self.layers.get_mut(layer) else {
+            return Vec::new();
+        };
+        if meshes.len() != source.strokes.len() {
+            // Strokes were changed without going through the canvas, start this layer over
+            meshes.clear();
+            meshes.resize_with(source.strokes.len(), || None);
+        }
+
+        let mut shapes: Vec<Option<Shape>> = source
+            .strokes
+            .iter()
+            .zip(meshes.iter_mut())
+            .map(|(stroke, cached)| {
+                // Layer opacity is baked into the color, so a new opacity also means a new mesh
+                let color = stroke.ink().gamma_multiply(source.opacity);
+                let mesh = match cached {
+                    Some(cached) if cached.color == color => Arc::clone(&cached.mesh),
+                    _ => {
+                        let mesh = Arc::new(tessellator.tessellate(stroke, color));
+                        *cached = Some(CachedMesh {
+                            color,
+                            mesh: Arc::clone(&mesh),
+                        });
+                        mesh
+                    }
+                };
+                Some(Shape::Mesh(mesh))
+            })
+            .collect();
+
+        source
+            .stroke_paint_order()
+            .into_iter()
+            .filter_map(|index| shapes.get_mut(index)?.take())
+            .collect()
+    }
+}
@@ -0,0 +1,177 @@
+use super::canvas::SingleStroke;
+use egui::epaint::Mesh;
+use egui::{Color32, Pos2, Vec2};
+
+// Thinnest a pressure-sensitive stroke gets, as a fraction of the brush width
+const MIN_PRESSURE: f32 = 0.2;
+// Pointer speed (points per second) at which simulated pressure bottoms out
+const MAX_SPEED: f32 = 3000.0;
+// How much of the previous width carries over, so simulated pressure doesn't jitter
+const VELOCITY_SMOOTHING: f32 = 0.7;
+// Segments used for each round end cap of a tapered outline
+const CAP_STEPS: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum PressureMode {
+    Constant,
+    // Touch force where the device reports it, otherwise constant
+    Force,
+    // Faster movement draws thinner lines, like a brush pen
+    Velocity,
+}
+
+impl PressureMode {
+    pub const ALL: [Self; 3] = [Self::Constant, Self::Force, Self::Velocity];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Constant => "Constant",
+            Self::Force => "Pressure",
+            Self::Velocity => "Velocity",
+        }
+    }
+}
+
+pub fn width_from_force(width: f32, force: f32) -> f32 {
+    width * (MIN_PRESSURE + (1.0 - MIN_PRESSURE) * force.clamp(0.0, 1.0))
+}
+
+pub fn width_from_velocity(width: f32, speed: f32, previous: Option<f32>) -> f32 {
+    let target = width_from_force(width, 1.0 - speed / MAX_SPEED);
+
+    previous.map_or(target, |previous| {
+        previous * VELOCITY_SMOOTHING + target * (1.0 - VELOCITY_SMOOTHING)
+    })
+}
+
+// Closed outline around a stroke: the left side, the end cap, the right side backwards
+// and the start cap. Each entry is (sample point, outward direction, half width).
+fn ring(stroke: &SingleStroke) -> Vec<(Pos2, Vec2, f32)> {
+    let chain: Vec<(Pos2, f32)> = stroke
+        .points
+        .iter()
+        .enumerate()
+        .map(|(index, point)| (*point, stroke.width_at(index)))
+        .collect();
+    let normals: Vec<Vec2> = (0..chain.len())
+        .map(|index| {
+            let before = index.checked_sub(1).and_then(|i| chain.get(i));
+            let after = chain.get(index + 1);
+            let point = chain.get(index).map_or(Pos2::ZERO, |(point, _)| *point);
+            let direction = |from: Pos2, to: Pos2| (to - from).normalized();
+
+            let tangent = match (before, after) {
+                (Some((before, _)), Some((after, _))) => {
+                    direction(*before, point) + direction(point, *after)
+                }
+                (Some((before, _)), None) => direction(*before, point),
+                (None, Some((after, _))) => direction(point, *after),
+                (None, None) => Vec2::X,
+            };
+            tangent.normalized().rot90()
+        })
+        .collect();
+
+    let (Some(&(first, first_width)), Some(&(last, last_width))) = (chain.first(), chain.last())
+    else {
+        return Vec::new();
+    };
+    let (Some(&first_normal), Some(&last_normal)) = (normals.first(), normals.last()) else {
+        return Vec::new();
+    };
+
+    let cap = |center: Pos2, normal: Vec2, width: f32| {
+        let forward = -normal.rot90();
+        (1..CAP_STEPS).map(move |step| {
+            let angle = std::f32::consts::PI * step as f32 / CAP_STEPS as f32;
+            (
+                center,
+                normal * angle.cos() + forward * angle.sin(),
+                width / 2.0,
+            )
+        })
+    };
+
+    let left = chain
+        .iter()
+        .zip(&normals)
+        .map(|(&(point, width), &normal)| (point, normal, width / 2.0));
+    let right = chain
+        .iter()
+        .zip(&normals)
+        .rev()
+        .map(|(&(point, width), &normal)| (point, -normal, width / 2.0));
+
+    left.chain(cap(last, last_normal, last_width))
+        .chain(right)
+        .chain(cap(first, -first_normal, first_width))
+        .collect()
+}
+
+// Filled outline polygon of a stroke
+pub fn outline(stroke: &SingleStroke) -> Vec<Pos2> {
+    ring(stroke)
+        .into_iter()
+        .map(|(point, outward, half_width)| point + outward * half_width)
+        .collect()
+}
+
+// Triangulates the round-ended outline of a stroke with a `feather` wide fade-out along
+// its edge, which stands in for the anti-aliasing egui gives regular line shapes
+pub fn stroke_mesh(stroke: &SingleStroke, color: Color32, feather: f32) -> Mesh {
+    let mut mesh = Mesh::default();
+    let (Some(&first), Some(&last)) = (stroke.points.first(), stroke.points.last()) else {
+        return mesh;
+    };
+    if stroke.points.len() < 2 {
+        return mesh;
+    }
+
+    let ring = ring(stroke);
+    let samples = stroke.points.len() as u32;
+    let caps = CAP_STEPS as u32 - 1;
+    let ring_len = ring.len() as u32;
+
+    // Inner ring at full color, outer ring transparent
+    for &(point, outward, half_width) in &ring {
+        mesh.colored_vertex(
+            point + outward * (half_width - feather / 2.0).max(0.0),
+            color,
+        );
+    }
+    for &(point, outward, half_width) in &ring {
+        mesh.colored_vertex(
+            point + outward * (half_width + feather / 2.0),
+            Color32::TRANSPARENT,
+        );
+    }
+
+    // Body: the left side runs forward from 0, the right side backward after the end cap
+    let right = |index: u32| samples + caps + (samples - 1 - index);
+    for left in 0..samples - 1 {
+        mesh.add_triangle(left, left + 1, right(left));
+        mesh.add_triangle(right(left), left + 1, right(left + 1));
+    }
+
+    // End caps fan out from the first and last samples
+    let mut fan = |center: Pos2, from: u32| {
+        let center_index = mesh.vertices.len() as u32;
+        mesh.colored_vertex(center, color);
+        for offset in 0..=caps {
+            let a = (from + offset) % ring_len;
+            let b = (from + offset + 1) % ring_len;
+            mesh.add_triangle(center_index, a, b);
+        }
+    };
+    fan(last, samples - 1);
+    fan(first, 2 * samples + caps - 1);
+
+    for inner in 0..ring_len {
+        let inner_next = (inner + 1) % ring_len;
+        let (outer, outer_next) = (inner + ring_len, inner_next + ring_len);
+        mesh.add_triangle(inner, inner_next, outer);
+        mesh.add_triangle(outer, inner_next, outer_next);
+    }
+
+    mesh
+}
@@ -0,0 +1,267 @@
+use super::canvas::SingleStroke;
+use egui::{Pos2, Rect, Vec2};
+
+// Screen-independent size of the grab handles, in canvas units
+pub const HANDLE_SIZE: f32 = 10.0;
+// Distance of the rotation handle above the selection box
+pub const ROTATE_HANDLE_OFFSET: f32 = 30.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum SelectMode {
+    Rectangle,
+    Lasso,
+}
+
+impl SelectMode {
+    pub const ALL: [Self; 2] = [Self::Rectangle, Self::Lasso];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Rectangle => "Rectangle",
+            Self::Lasso => "Lasso",
+        }
+    }
+}
+
+// Strokes picked on one layer, by index into that layer's strokes in ascending order
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Selection {
+    pub layer: usize,
+    pub strokes: Vec<usize>,
+}
+
+impl Selection {
+    pub fn is_empty(&self) -> bool {
+        self.strokes.is_empty()
+    }
+
+    pub fn selected<'a>(
+        &'a self,
+        strokes: &'a [SingleStroke],
+    ) -> impl Iterator<Item = &'a SingleStroke> {
+        self.strokes.iter().filter_map(|index| strokes.get(*index))
+    }
+
+    pub fn bounds(&self, strokes: &[SingleStroke]) -> Option<Rect> {
+        let mut bounds = Rect::NOTHING;
+        for stroke in self.selected(strokes) {
+            for point in &stroke.points {
+                bounds.extend_with(*point);
+            }
+        }
+
+        bounds.is_finite().then(|| {
+            if bounds.is_positive() {
+                bounds
+            } else {
+                // Perfectly straight strokes still get a grabbable box
+                bounds.expand(HANDLE_SIZE / 2.0)
+            }
+        })
+    }
+}
+
+// Part of the selection box under the cursor
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Handle {
+    // Scales around the opposite corner
+    Corner { anchor: Pos2 },
+    Rotate,
+    Inside,
+}
+
+pub fn handle_at(bounds: Rect, pos: Pos2) -> Option<Handle> {
+    if rotate_handle(bounds).distance(pos) <= HANDLE_SIZE {
+        return Some(Handle::Rotate);
+    }
+
+    let corners = [
+        (bounds.left_top(), bounds.right_bottom()),
+        (bounds.right_top(), bounds.left_bottom()),
+        (bounds.left_bottom(), bounds.right_top()),
+        (bounds.right_bottom(), bounds.left_top()),
+    ];
+    for (corner, anchor) in corners {
+        if Rect::from_center_size(corner, Vec2::splat(HANDLE_SIZE)).contains(pos) {
+            return Some(Handle::Corner { anchor });
+        }
+    }
+
+    bounds.contains(pos).then_some(Handle::Inside)
+}
+
+pub fn rotate_handle(bounds: Rect) -> Pos2 {
+    bounds.center_top() - Vec2::new(0.0, ROTATE_HANDLE_OFFSET)
+}
+
+// Indices of strokes with any point inside `rect`
+pub fn pick_in_rect(strokes: &[SingleStroke], rect: Rect) -> Vec<usize> {
+    pick_in_rect_among(strokes, 0..strokes.len(), rect)
+}
+
+// Indices of strokes with any point inside the closed polygon `lasso`
+pub fn pick_in_lasso(strokes: &[SingleStroke], lasso: &[Pos2]) -> Vec<usize> {
+    pick_in_lasso_among(strokes, 0..strokes.len(), lasso)
+}
+
+// Like `pick_in_rect`, but only tests the strokes at `candidates`
+pub fn pick_in_rect_among(
+    strokes: &[SingleStroke],
+    candidates: impl IntoIterator<Item = usize>,
+    rect: Rect,
+) -> Vec<usize> {
+    pick(strokes, candidates, |point| rect.contains(point))
+}
+
+// Like `pick_in_lasso`, but only tests the strokes at `candidates`
+pub fn pick_in_lasso_among(
+    strokes: &[SingleStroke],
+    candidates: impl IntoIterator<Item = usize>,
+    lasso: &[Pos2],
+) -> Vec<usize> {
+    if lasso.len() < 3 {
+        return Vec::new();
+    }
+    pick(strokes, candidates, |point| point_in_polygon(point, lasso))
+}
+
+fn pick(
+    strokes: &[SingleStroke],
+    candidates: impl IntoIterator<Item = usize>,
+    inside: impl Fn(Pos2) -> bool,
+) -> Vec<usize> {
+    candidates
+        .into_iter()
+        .filter(|index| {
+            strokes
+                .get(*index)
+                .is_some_and(|stroke| stroke.points.iter().any(|point| inside(*point)))
+        })
+        .collect()
+}
+
+// Even-odd ray casting test
+pub fn point_in_polygon(point: Pos2, polygon: &[Pos2]) -> bool {
+    let mut inside = false;
+    let mut previous = polygon.last().copied().unwrap_or(point);
+
+    for &current in polygon {
+        if (current.y > point.y) != (previous.y > point.y) {
+            let crossing_x = current.x
+                + (point.y - current.y) * (previous.x - current.x) / (previous.y - current.y);
+            if point.x < crossing_x {
+                inside = !inside;
+            }
+        }
+        previous = current;
+    }
+
+    inside
+}
+
+// Drag in progress with the Select tool
+#[derive(Clone, Debug)]
+pub enum Gesture {
+    Marquee {
+        start: Pos2,
+        current: Pos2,
+    },
+    Lasso {
+        points: Vec<Pos2>,
+    },
+    // `original` holds the selected strokes as they were when the drag started
+    Transform {
+        handle: Handle,
+        start: Pos2,
+        bounds: Rect,
+        original: Vec<SingleStroke>,
+    },
+}
+
+// Affine map applied to selected strokes while moving, scaling or rotating them
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    pub origin: Pos2,
+    pub scale: Vec2,
+    pub rotation: f32,
+    pub translation: Vec2,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            origin: Pos2::ZERO,
+            scale: Vec2::splat(1.0),
+            rotation: 0.0,
+            translation: Vec2::ZERO,
+        }
+    }
+}
+
+impl Transform {
+    pub fn translate(delta: Vec2) -> Self {
+        Self {
+            translation: delta,
+            ..Self::default()
+        }
+    }
+
+    pub fn scale_around(origin: Pos2, scale: Vec2) -> Self {
+        Self {
+            origin,
+            scale,
+            ..Self::default()
+        }
+    }
+
+    pub fn rotate_around(origin: Pos2, rotation: f32) -> Self {
+        Self {
+            origin,
+            rotation,
+            ..Self::default()
+        }
+    }
+
+    pub fn apply(&self, point: Pos2) -> Pos2 {
+        let local = (point - self.origin) * self.scale;
+        let rotated = egui::emath::Rot2::from_angle(self.rotation) * local;
+
+        self.origin + rotated + self.translation
+    }
+
+    // Stroke widths follow the average scale so thick lines stay proportional
+    pub fn apply_to_stroke(&self, stroke: &SingleStroke) -> SingleStroke {
+        let mut transformed = stroke.clone();
+        let width_scale = (self.scale.x * self.scale.y).abs().sqrt();
+        for point in &mut transformed.points {
+            *point = self.apply(*point);
+        }
+        for width in &mut transformed.widths {
+            *width *= width_scale;
+        }
+        transformed.stroke.width *= width_scale;
+
+        transformed
+    }
+}
+
+// Scale factors that move `start` to `current` while keeping `anchor` fixed
+pub fn scale_between(anchor: Pos2, start: Pos2, current: Pos2, uniform: bool) -> Vec2 {
+    let from = start - anchor;
+    let to = current - anchor;
+    let factor = |to: f32, from: f32| {
+        if from.abs() <= f32::EPSILON {
+            1.0
+        } else {
+            to / from
+        }
+    };
+
+    let scale = Vec2::new(factor(to.x, from.x), factor(to.y, from.y));
+    if uniform {
+        let uniform = scale.x.abs().max(scale.y.abs());
+        Vec2::new(uniform.copysign(scale.x), uniform.copysign(scale.y))
+    } else {
+        scale
+    }
+}
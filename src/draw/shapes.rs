@@ -0,0 +1,106 @@
+use egui::{Pos2, Rect, Vec2};
+
+// Points around an ellipse when it is turned into a polyline
+const ELLIPSE_STEPS: u32 = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum ShapeKind {
+    Rectangle,
+    Ellipse,
+    Line,
+}
+
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct Shape {
+    pub kind: ShapeKind,
+    pub stroke: egui::Stroke,
+    pub from: Pos2,
+    pub to: Pos2,
+}
+
+impl Shape {
+    pub fn new(kind: ShapeKind, stroke: egui::Stroke, from: Pos2, to: Pos2) -> Self {
+        Self {
+            kind,
+            stroke,
+            from,
+            to,
+        }
+    }
+
+    // Rectangles and ellipses fill the box spanned by the two drag points
+    pub fn bounds(&self) -> Rect {
+        Rect::from_two_pos(self.from, self.to)
+    }
+
+    // The drawn line as a polyline, closed for rectangles and ellipses
+    pub fn outline(&self) -> Vec<Pos2> {
+        let bounds = self.bounds();
+        match self.kind {
+            ShapeKind::Rectangle => vec![
+                bounds.left_top(),
+                bounds.right_top(),
+                bounds.right_bottom(),
+                bounds.left_bottom(),
+                bounds.left_top(),
+            ],
+            ShapeKind::Ellipse => (0..=ELLIPSE_STEPS)
+                .map(|step| {
+                    let angle = std::f32::consts::TAU * step as f32 / ELLIPSE_STEPS as f32;
+                    bounds.center() + Vec2::angled(angle) * bounds.size() / 2.0
+                })
+                .collect(),
+            ShapeKind::Line => vec![self.from, self.to],
+        }
+    }
+
+    pub fn to_egui_shape(&self, stroke: egui::Stroke) -> egui::Shape {
+        match self.kind {
+            ShapeKind::Rectangle => {
+                egui::Shape::rect_stroke(self.bounds(), 0.0, stroke, egui::StrokeKind::Middle)
+            }
+            ShapeKind::Ellipse => {
+                let bounds = self.bounds();
+                egui::Shape::ellipse_stroke(bounds.center(), bounds.size() / 2.0, stroke)
+            }
+            ShapeKind::Line => egui::Shape::line_segment([self.from, self.to], stroke),
+        }
+    }
+}
+
+/// Turns a drag from `anchor` to `cursor` into the two points defining a shape.
+///
+/// `constrain` (Shift) keeps rectangles square, ellipses circular and snaps lines to 45°.
+/// `centered` (Alt) treats the anchor as the center of the shape instead of a corner.
+pub fn drag_points(
+    kind: ShapeKind,
+    anchor: Pos2,
+    cursor: Pos2,
+    constrain: bool,
+    centered: bool,
+) -> (Pos2, Pos2) {
+    let mut delta = cursor - anchor;
+
+    if constrain {
+        delta = match kind {
+            ShapeKind::Rectangle | ShapeKind::Ellipse => {
+                let side = delta.x.abs().max(delta.y.abs());
+                Vec2::new(side.copysign(delta.x), side.copysign(delta.y))
+            }
+            ShapeKind::Line => snap_to_45(delta),
+        };
+    }
+
+    if centered {
+        (anchor - delta, anchor + delta)
+    } else {
+        (anchor, anchor + delta)
+    }
+}
+
+fn snap_to_45(delta: Vec2) -> Vec2 {
+    let step = std::f32::consts::FRAC_PI_4;
+    let angle = (delta.angle() / step).round() * step;
+
+    Vec2::angled(angle) * delta.length()
+}
@@ -0,0 +1,128 @@
+use super::canvas::SingleStroke;
+use egui::Pos2;
+
+// Largest deviation allowed at zoom 1, in canvas units; it shrinks as you zoom in
+pub const BASE_TOLERANCE: f32 = 0.5;
+
+pub fn tolerance(zoom: f32) -> f32 {
+    BASE_TOLERANCE / zoom.max(f32::EPSILON)
+}
+
+// Point counts before and after simplifying, for tuning the tolerance
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Reduction {
+    pub before: usize,
+    pub after: usize,
+}
+
+impl Reduction {
+    pub fn removed(&self) -> usize {
+        self.before.saturating_sub(self.after)
+    }
+
+    pub fn percent(&self) -> f32 {
+        if self.before == 0 {
+            0.0
+        } else {
+            self.removed() as f32 / self.before as f32 * 100.0
+        }
+    }
+}
+
+impl std::ops::AddAssign for Reduction {
+    fn add_assign(&mut self, other: Self) {
+        self.before += other.before;
+        self.after += other.after;
+    }
+}
+
+impl std::fmt::Display for Reduction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} → {} points ({:.0}% fewer)",
+            self.before,
+            self.after,
+            self.percent()
+        )
+    }
+}
+
+// Ramer–Douglas–Peucker: drops points lying within `tolerance` of the line through their
+// neighbours. Pressure strokes also keep points where the width bends by more than that.
+pub fn simplify(stroke: &SingleStroke, tolerance: f32) -> (SingleStroke, Reduction) {
+    let count = stroke.points.len();
+    let mut keep = vec![false; count];
+    if let (Some(first), Some(last)) = (keep.first_mut(), count.checked_sub(1)) {
+        *first = true;
+        if let Some(last) = keep.get_mut(last) {
+            *last = true;
+        }
+    }
+
+    // Ranges still to check, as (start, end) indices of kept points
+    let mut ranges = vec![(0, count.saturating_sub(1))];
+    while let Some((start, end)) = ranges.pop() {
+        if end <= start + 1 {
+            continue;
+        }
+
+        let farthest = (start + 1..end)
+            .map(|index| (index, deviation(stroke, start, end, index)))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+
+        if let Some((index, distance)) = farthest
+            && distance > tolerance
+        {
+            if let Some(flag) = keep.get_mut(index) {
+                *flag = true;
+            }
+            ranges.push((start, index));
+            ranges.push((index, end));
+        }
+    }
+
+    let simplified = SingleStroke {
+        stroke: stroke.stroke,
+        points: kept(&stroke.points, &keep),
+        widths: kept(&stroke.widths, &keep),
+        cap: stroke.cap,
+        kind: stroke.kind,
+    };
+    let reduction = Reduction {
+        before: count,
+        after: simplified.points.len(),
+    };
+
+    (simplified, reduction)
+}
+
+fn kept<T: Copy>(values: &[T], keep: &[bool]) -> Vec<T> {
+    values
+        .iter()
+        .zip(keep)
+        .filter(|(_, keep)| **keep)
+        .map(|(value, _)| *value)
+        .collect()
+}
+
+// How far the point at `index` strays from the straight run between `start` and `end`
+fn deviation(stroke: &SingleStroke, start: usize, end: usize, index: usize) -> f32 {
+    let point = |index: usize| stroke.points.get(index).copied().unwrap_or(Pos2::ZERO);
+    let (a, b, p) = (point(start), point(end), point(index));
+
+    let ab = b - a;
+    let t = if ab.length_sq() <= f32::EPSILON {
+        0.0
+    } else {
+        ((p - a).dot(ab) / ab.length_sq()).clamp(0.0, 1.0)
+    };
+    let distance = p.distance(a + ab * t);
+
+    if stroke.is_tapered() {
+        let expected = egui::lerp(stroke.width_at(start)..=stroke.width_at(end), t);
+        distance.max((stroke.width_at(index) - expected).abs())
+    } else {
+        distance
+    }
+}
@@ -0,0 +1,70 @@
+use super::canvas::SingleStroke;
+use egui::Pos2;
+use std::ops::RangeInclusive;
+
+pub const STABILIZER_RANGE: RangeInclusive<f32> = 0.0..=50.0;
+// Each pass doubles the number of points, so keep the maximum low
+pub const PASSES_RANGE: RangeInclusive<u32> = 0..=3;
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Smoothing {
+    // Lazy-mouse radius: the pen only moves once the cursor pulls it this far
+    pub stabilizer: f32,
+    // Chaikin passes applied when a stroke is finished
+    pub passes: u32,
+}
+
+impl Default for Smoothing {
+    fn default() -> Self {
+        Self {
+            stabilizer: 0.0,
+            passes: 1,
+        }
+    }
+}
+
+// Moves the pen towards the cursor only by as much as the cursor is outside `radius`,
+// like dragging the pen on a string
+pub fn lazy_follow(pen: Pos2, cursor: Pos2, radius: f32) -> Pos2 {
+    let distance = pen.distance(cursor);
+    if distance <= radius {
+        return pen;
+    }
+
+    pen + (cursor - pen) * ((distance - radius) / distance)
+}
+
+// Chaikin corner cutting. The end points stay where they were drawn.
+pub fn smooth(stroke: &SingleStroke, passes: u32) -> SingleStroke {
+    let mut smoothed = stroke.clone();
+
+    for _ in 0..passes {
+        if smoothed.points.len() < 3 {
+            break;
+        }
+        smoothed.points = chaikin(&smoothed.points, |a, b, t| a.lerp(b, t));
+        if smoothed.is_tapered() {
+            smoothed.widths = chaikin(&smoothed.widths, |a, b, t| egui::lerp(a..=b, t));
+        }
+    }
+
+    smoothed
+}
+
+fn chaikin<T: Copy>(values: &[T], lerp: impl Fn(T, T, f32) -> T) -> Vec<T> {
+    let (Some(&first), Some(&last)) = (values.first(), values.last()) else {
+        return values.to_vec();
+    };
+
+    let mut result = Vec::with_capacity(values.len() * 2);
+    result.push(first);
+    for pair in values.windows(2) {
+        if let [a, b] = *pair {
+            result.push(lerp(a, b, 0.25));
+            result.push(lerp(a, b, 0.75));
+        }
+    }
+    result.push(last);
+
+    result
+}
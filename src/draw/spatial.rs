@@ -0,0 +1,194 @@
+use super::canvas::{Layer, SingleStroke};
+use egui::{Pos2, Rect};
+use std::collections::BTreeMap;
+
+// Side of a grid cell in canvas units
+pub const CELL_SIZE: f32 = 64.0;
+
+// Row then column, so the cells of one row are next to each other in the map
+type Cell = (i32, i32);
+
+// Uniform grid over the segment bounding boxes of every stroke, one grid per layer.
+// Lookups return candidate stroke indices, which still need an exact test.
+#[derive(Debug, Default)]
+pub struct SpatialIndex {
+    layers: Vec<LayerIndex>,
+}
+
+#[derive(Debug, Default)]
+struct LayerIndex {
+    cells: BTreeMap<Cell, Vec<usize>>,
+    // Cells each stroke was entered into, in stroke order
+    stroke_cells: Vec<Vec<Cell>>,
+}
+
+impl SpatialIndex {
+    pub fn build(layers: &[Layer]) -> Self {
+        Self {
+            layers: layers
+                .iter()
+                .map(|layer| {
+                    let mut index = LayerIndex::default();
+                    for (position, stroke) in layer.strokes.iter().enumerate() {
+                        index.insert(position, stroke);
+                    }
+                    index
+                })
+                .collect(),
+        }
+    }
+
+    // Number of strokes indexed on `layer`
+    pub fn len(&self, layer: usize) -> Option<usize> {
+        self.layers.get(layer).map(|index| index.stroke_cells.len())
+    }
+
+    pub fn insert(&mut self, layer: usize, index: usize, stroke: &SingleStroke) {
+        if let Some(layer_index) = self.layers.get_mut(layer) {
+            layer_index.insert(index, stroke);
+        }
+    }
+
+    pub fn remove(&mut self, layer: usize, index: usize) {
+        if let Some(layer_index) = self.layers.get_mut(layer) {
+            layer_index.remove(index);
+        }
+    }
+
+    pub fn replace(&mut self, layer: usize, index: usize, stroke: &SingleStroke) {
+        if let Some(layer_index) = self.layers.get_mut(layer) {
+            layer_index.replace(index, stroke);
+        }
+    }
+
+    // Sorted indices of strokes on `layer` with a segment whose bounding box meets `area`
+    pub fn query(&self, layer: usize, area: Rect) -> Vec<usize> {
+        let Some(layer_index) = self.layers.get(layer) else {
+            return Vec::new();
+        };
+        if area.is_negative() {
+            return Vec::new();
+        }
+
+        let (min, max) = (cell_of(area.min), cell_of(area.max));
+        let rows = i64::from(max.0) - i64::from(min.0) + 1;
+        let columns = i64::from(max.1) - i64::from(min.1) + 1;
+
+        // Huge areas are cheaper to answer by walking the occupied cells instead
+        let mut found: Vec<usize> = if rows * columns > layer_index.cells.len() as i64 {
+            layer_index
+                .cells
+                .iter()
+                .filter(|((row, column), _)| {
+                    (min.0..=max.0).contains(row) && (min.1..=max.1).contains(column)
+                })
+                .flat_map(|(_, strokes)| strokes.iter().copied())
+                .collect()
+        } else {
+            (min.0..=max.0)
+                .flat_map(|row| {
+                    layer_index
+                        .cells
+                        .range((row, min.1)..=(row, max.1))
+                        .flat_map(|(_, strokes)| strokes.iter().copied())
+                })
+                .collect()
+        };
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+}
+
+impl LayerIndex {
+    fn insert(&mut self, index: usize, stroke: &SingleStroke) {
+        let index = index.min(self.stroke_cells.len());
+        if index < self.stroke_cells.len() {
+            self.shift(index, |stroke_index| stroke_index + 1);
+        }
+
+        let cells = stroke_cells(stroke);
+        self.enter(index, &cells);
+        self.stroke_cells.insert(index, cells);
+    }
+
+    fn remove(&mut self, index: usize) {
+        if index >= self.stroke_cells.len() {
+            return;
+        }
+
+        let cells = self.stroke_cells.remove(index);
+        self.leave(index, &cells);
+        self.shift(index, |stroke_index| stroke_index - 1);
+    }
+
+    fn replace(&mut self, index: usize, stroke: &SingleStroke) {
+        let cells = stroke_cells(stroke);
+        let Some(old) = self.stroke_cells.get_mut(index) else {
+            return;
+        };
+
+        let old = std::mem::replace(old, cells.clone());
+        self.leave(index, &old);
+        self.enter(index, &cells);
+    }
+
+    fn enter(&mut self, index: usize, cells: &[Cell]) {
+        for cell in cells {
+            self.cells.entry(*cell).or_default().push(index);
+        }
+    }
+
+    fn leave(&mut self, index: usize, cells: &[Cell]) {
+        for cell in cells {
+            if let Some(strokes) = self.cells.get_mut(cell) {
+                strokes.retain(|stroke_index| *stroke_index != index);
+                if strokes.is_empty() {
+                    self.cells.remove(cell);
+                }
+            }
+        }
+    }
+
+    // Renumbers every entry at or after `from`, after a stroke was inserted or removed
+    fn shift(&mut self, from: usize, renumber: impl Fn(usize) -> usize) {
+        for strokes in self.cells.values_mut() {
+            for stroke_index in strokes.iter_mut().filter(|index| **index >= from) {
+                *stroke_index = renumber(*stroke_index);
+            }
+        }
+    }
+}
+
+fn cell_of(pos: Pos2) -> Cell {
+    (
+        (pos.y / CELL_SIZE).floor() as i32,
+        (pos.x / CELL_SIZE).floor() as i32,
+    )
+}
+
+// Every cell touched by the bounding box of a segment of `stroke`
+fn stroke_cells(stroke: &SingleStroke) -> Vec<Cell> {
+    let mut cells = Vec::new();
+    let mut add_box = |a: Pos2, b: Pos2| {
+        let (min, max) = (cell_of(a.min(b)), cell_of(a.max(b)));
+        for row in min.0..=max.0 {
+            for column in min.1..=max.1 {
+                cells.push((row, column));
+            }
+        }
+    };
+
+    match stroke.points.as_slice() {
+        [point] => add_box(*point, *point),
+        _ => {
+            for [a, b] in stroke.segments() {
+                add_box(a, b);
+            }
+        }
+    }
+
+    cells.sort_unstable();
+    cells.dedup();
+    cells
+}
@@ -0,0 +1,146 @@
+use super::canvas::Action;
+use egui::text::{LayoutJob, TextFormat};
+use egui::{Align, Color32, FontId, Galley, Pos2, Rect};
+use std::ops::RangeInclusive;
+
+pub const SIZE_RANGE: RangeInclusive<f32> = 6.0..=200.0;
+pub const DEFAULT_SIZE: f32 = 24.0;
+// Distance between baselines, relative to the font size
+pub const LINE_HEIGHT: f32 = 1.25;
+// Distance from the top of a line to its baseline, relative to the font size
+pub const BASELINE: f32 = 0.93;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+impl TextAlign {
+    pub const ALL: [Self; 3] = [Self::Left, Self::Center, Self::Right];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Left => "Left",
+            Self::Center => "Center",
+            Self::Right => "Right",
+        }
+    }
+
+    pub fn to_align(self) -> Align {
+        match self {
+            Self::Left => Align::LEFT,
+            Self::Center => Align::Center,
+            Self::Right => Align::RIGHT,
+        }
+    }
+}
+
+// A text label placed on the canvas. Lines are separated by '\n'.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct TextBox {
+    pub text: String,
+    // Top of the first line; horizontally the left edge, center or right edge depending on `align`
+    pub pos: Pos2,
+    pub size: f32,
+    pub color: Color32,
+    pub align: TextAlign,
+}
+
+impl TextBox {
+    pub fn new(
+        text: impl Into<String>,
+        pos: Pos2,
+        size: f32,
+        color: Color32,
+        align: TextAlign,
+    ) -> Self {
+        Self {
+            text: text.into(),
+            pos,
+            size,
+            color,
+            align,
+        }
+    }
+
+    pub fn is_blank(&self) -> bool {
+        self.text.trim().is_empty()
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = &str> {
+        self.text.split('\n')
+    }
+
+    pub fn line_height(&self) -> f32 {
+        self.size * LINE_HEIGHT
+    }
+
+    // Lays the text out around x = 0, so the galley is painted at `pos`
+    pub fn layout_job(&self, color: Color32) -> LayoutJob {
+        let mut job = LayoutJob::single_section(
+            self.text.clone(),
+            TextFormat {
+                font_id: FontId::proportional(self.size),
+                line_height: Some(self.line_height()),
+                color,
+                ..TextFormat::default()
+            },
+        );
+        job.halign = self.align.to_align();
+        job
+    }
+
+    // Area covered by `galley`, laid out from `layout_job`, in canvas coordinates
+    pub fn bounds(&self, galley: &Galley) -> Rect {
+        galley.rect.translate(self.pos.to_vec2())
+    }
+
+    // Area the text covers when laid out by `painter`
+    pub fn painted_bounds(&self, painter: &egui::Painter) -> Rect {
+        self.bounds(&painter.layout_job(self.layout_job(self.color)))
+    }
+}
+
+// A text being typed on `layer`, either a new one or an existing one opened again
+#[derive(Clone, Debug)]
+pub struct Editing {
+    pub layer: usize,
+    // Position of the text in the layer while re-editing, `None` for a new text
+    pub index: Option<usize>,
+    pub text: TextBox,
+}
+
+impl Editing {
+    // The action recording the edit, given the texts currently on the layer. Emptying a text
+    // removes it; an unchanged or blank new text records nothing.
+    pub fn action(self, texts: &[TextBox]) -> Option<Action> {
+        let Some(index) = self.index else {
+            return (!self.text.is_blank()).then(|| Action::AddText {
+                text: self.text,
+                index: texts.len(),
+                layer: self.layer,
+            });
+        };
+
+        let before = texts.get(index)?.clone();
+        if self.text.is_blank() {
+            Some(Action::RemoveText {
+                text: before,
+                index,
+                layer: self.layer,
+            })
+        } else if self.text == before {
+            None
+        } else {
+            Some(Action::ModifyText {
+                before,
+                after: self.text,
+                index,
+                layer: self.layer,
+            })
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use super::raster::{self, Pixmap};
+use crate::draw::canvas::Canvas;
+
+pub const SCALES: [f32; 3] = [1.0, 2.0, 4.0];
+
+/// Rasterizes the canvas on the CPU and encodes it as a PNG.
+///
+/// # Errors
+///
+/// Returns an error if the PNG encoder fails.
+pub fn canvas_to_png(canvas: &Canvas, scale: f32) -> Result<Vec<u8>, png::EncodingError> {
+    encode_png(&raster::rasterize(canvas, scale))
+}
+
+/// Encodes a pixmap as an 8-bit RGBA PNG.
+///
+/// # Errors
+///
+/// Returns an error if the PNG encoder fails.
+pub fn encode_png(pixmap: &Pixmap) -> Result<Vec<u8>, png::EncodingError> {
+    let mut bytes = Vec::new();
+
+    {
+        let mut encoder = png::Encoder::new(&mut bytes, pixmap.width, pixmap.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let data: Vec<u8> = pixmap
+            .pixels()
+            .iter()
+            .flat_map(|color| color.to_srgba_unmultiplied())
+            .collect();
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&data)?;
+    }
+
+    Ok(bytes)
+}
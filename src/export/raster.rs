@@ -0,0 +1,510 @@
+use crate::draw::brush::StrokeCap;
+use crate::draw::canvas::Canvas;
+use crate::draw::shapes::{Shape, ShapeKind};
+use crate::draw::text::TextBox;
+use egui::epaint::AlphaFromCoverage;
+use egui::epaint::text::{FontDefinitions, Fonts};
+use egui::{Color32, ColorImage, Galley, Pos2, Rect, Vec2};
+use std::ops::Range;
+use std::sync::Arc;
+
+// Largest side of the font atlas used for text in exports
+const FONT_TEXTURE_SIDE: usize = 4096;
+
+// CPU rasterizer mirroring what the egui painter shows, so exports work without a GPU context.
+// Coverage is computed from signed distances, giving the same ~1px feathered edge egui uses.
+pub struct Pixmap {
+    pub width: u32,
+    pub height: u32,
+    pixels: Vec<Color32>,
+}
+
+impl Pixmap {
+    pub fn new(width: u32, height: u32, fill: Color32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![fill; width as usize * height as usize],
+        }
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> Option<Color32> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.pixels
+            .get(y as usize * self.width as usize + x as usize)
+            .copied()
+    }
+
+    pub fn pixels(&self) -> &[Color32] {
+        &self.pixels
+    }
+
+    // Source-over blend of a premultiplied color scaled by `coverage`, in gamma space like egui
+    fn blend(&mut self, x: u32, y: u32, color: Color32, coverage: f32) {
+        let index = y as usize * self.width as usize + x as usize;
+        let Some(dst) = self.pixels.get_mut(index) else {
+            return;
+        };
+
+        let src = egui::Rgba::from_rgba_premultiplied(
+            f32::from(color.r()) / 255.0,
+            f32::from(color.g()) / 255.0,
+            f32::from(color.b()) / 255.0,
+            f32::from(color.a()) / 255.0,
+        ) * coverage;
+        let inverse = 1.0 - src.a();
+        let channel = |s: f32, d: u8| s * 255.0 + f32::from(d) * inverse;
+
+        *dst = Color32::from_rgba_premultiplied(
+            channel(src.r(), dst.r()).round() as u8,
+            channel(src.g(), dst.g()).round() as u8,
+            channel(src.b(), dst.b()).round() as u8,
+            channel(src.a(), dst.a()).round() as u8,
+        );
+    }
+
+    // Pixel columns and rows touched by `bounds`, clipped to the pixmap
+    fn pixel_range(&self, bounds: Rect) -> (Range<u32>, Range<u32>) {
+        let bounds = bounds.expand(1.0);
+        let min_x = bounds.min.x.floor().max(0.0) as u32;
+        let min_y = bounds.min.y.floor().max(0.0) as u32;
+        let max_x = (bounds.max.x.ceil().max(0.0) as u32).min(self.width);
+        let max_y = (bounds.max.y.ceil().max(0.0) as u32).min(self.height);
+
+        (min_x..max_x.max(min_x), min_y..max_y.max(min_y))
+    }
+
+    // Fills every pixel within `bounds` using a signed distance function (negative inside)
+    fn fill_sdf(&mut self, bounds: Rect, color: Color32, sdf: impl Fn(Pos2) -> f32) {
+        let (columns, rows) = self.pixel_range(bounds);
+
+        for y in rows {
+            for x in columns.clone() {
+                let coverage = (0.5 - sdf(pixel_center(x, y))).clamp(0.0, 1.0);
+                if coverage > 0.0 {
+                    self.blend(x, y, color, coverage);
+                }
+            }
+        }
+    }
+
+    // Fills the area inside an odd number of `contours`, one row of pixel centers at a time
+    pub fn polygon(&mut self, contours: &[Vec<Pos2>], color: Color32) {
+        let edges: Vec<[Pos2; 2]> = contours
+            .iter()
+            .flat_map(|contour| {
+                contour
+                    .iter()
+                    .zip(contour.iter().cycle().skip(1))
+                    .map(|(a, b)| [*a, *b])
+            })
+            .collect();
+        let bounds = Rect::from_points(&contours.concat());
+        let (columns, rows) = self.pixel_range(bounds);
+
+        for y in rows {
+            let center_y = y as f32 + 0.5;
+            let mut crossings: Vec<f32> = edges
+                .iter()
+                .filter(|[a, b]| (a.y > center_y) != (b.y > center_y))
+                .map(|[a, b]| a.x + (center_y - a.y) * (b.x - a.x) / (b.y - a.y))
+                .collect();
+            crossings.sort_by(f32::total_cmp);
+
+            for span in crossings.chunks_exact(2) {
+                let [start, end] = span else {
+                    continue;
+                };
+                for x in columns.clone() {
+                    let center_x = x as f32 + 0.5;
+                    if *start <= center_x && center_x < *end {
+                        self.blend(x, y, color, 1.0);
+                    }
+                }
+            }
+        }
+    }
+
+    // Copies glyph coverage from the font atlas into `rect`, tinted with `color`. `uv` is in texels.
+    pub fn glyph(&mut self, rect: Rect, uv: Rect, atlas: &ColorImage, color: Color32) {
+        if !rect.is_positive() {
+            return;
+        }
+        let [atlas_width, _] = atlas.size;
+        let (columns, rows) = self.pixel_range(rect);
+
+        for y in rows {
+            for x in columns.clone() {
+                let center = pixel_center(x, y);
+                if !rect.contains(center) {
+                    continue;
+                }
+                let texel = uv.min + (center - rect.min) / rect.size() * uv.size();
+                let coverage = atlas
+                    .pixels
+                    .get(texel.y.floor() as usize * atlas_width + texel.x.floor() as usize)
+                    .map_or(0.0, |texel| f32::from(texel.a()) / 255.0);
+                if coverage > 0.0 {
+                    self.blend(x, y, color, coverage);
+                }
+            }
+        }
+    }
+
+    pub fn line_segment(&mut self, [a, b]: [Pos2; 2], width: f32, color: Color32) {
+        let half_width = width / 2.0;
+        let bounds = Rect::from_two_pos(a, b).expand(half_width);
+
+        self.fill_sdf(bounds, color, |p| segment_sdf(p, a, b, half_width));
+    }
+
+    // Draws a whole stroke with round joins. Coverage is collected first and blended once,
+    // so segments overlapping at the joins don't darken translucent strokes.
+    pub fn polyline(&mut self, points: &[Pos2], widths: &[f32], color: Color32, cap: StrokeCap) {
+        let samples: Vec<(Pos2, f32)> =
+            points.iter().copied().zip(widths.iter().copied()).collect();
+        let pairs = samples.windows(2).filter_map(|pair| match pair {
+            [a, b] => Some((*a, *b)),
+            _ => None,
+        });
+        // A lone point still leaves a dot
+        let single = (samples.len() == 1)
+            .then(|| samples.first().map(|a| (*a, *a)))
+            .flatten();
+        let segments: Vec<((Pos2, f32), (Pos2, f32))> = pairs.chain(single).collect();
+        let segment_bounds = |((a, width_a), (b, width_b)): ((Pos2, f32), (Pos2, f32))| {
+            Rect::from_two_pos(a, b).expand(width_a.max(width_b) / 2.0)
+        };
+
+        let bounds = segments.iter().fold(Rect::NOTHING, |bounds, segment| {
+            bounds.union(segment_bounds(*segment))
+        });
+        let (columns, rows) = self.pixel_range(bounds);
+        let stride = columns.len();
+        let mut coverage = vec![0.0_f32; stride * rows.len()];
+        let ends = flat_ends(points, widths, cap);
+
+        for (index, segment) in segments.into_iter().enumerate() {
+            let ((a, width_a), (b, width_b)) = segment;
+            let (segment_columns, segment_rows) = self.pixel_range(segment_bounds(segment));
+            // Only segments the round end of a neighbour could reach past are cut
+            let planes: Vec<(Pos2, Vec2)> = ends
+                .iter()
+                .filter(|end| end.segments.contains(&index))
+                .map(|end| (end.point, end.inward))
+                .collect();
+
+            for y in segment_rows {
+                for x in segment_columns.clone() {
+                    let p = pixel_center(x, y);
+                    let sdf = planes.iter().fold(
+                        tapered_sdf(p, a, b, width_a, width_b),
+                        |sdf, (point, inward)| sdf.max((*point - p).dot(*inward)),
+                    );
+                    let index = (y - rows.start) as usize * stride + (x - columns.start) as usize;
+                    if let Some(value) = coverage.get_mut(index) {
+                        *value = value.max((0.5 - sdf).clamp(0.0, 1.0));
+                    }
+                }
+            }
+        }
+
+        for (index, value) in coverage.into_iter().enumerate() {
+            if value > 0.0 {
+                let x = columns.start + (index % stride.max(1)) as u32;
+                let y = rows.start + (index / stride.max(1)) as u32;
+                self.blend(x, y, color, value);
+            }
+        }
+    }
+
+    pub fn rect_stroke(&mut self, rect: Rect, width: f32, color: Color32) {
+        let half_width = width / 2.0;
+        let outer = rect.expand(half_width);
+        let inner = rect.shrink(half_width);
+
+        self.fill_sdf(outer, color, |p| {
+            let outside_inner = if inner.is_positive() {
+                -box_sdf(p, inner)
+            } else {
+                f32::NEG_INFINITY
+            };
+            box_sdf(p, outer).max(outside_inner)
+        });
+    }
+
+    pub fn ellipse_stroke(&mut self, center: Pos2, radius: Vec2, width: f32, color: Color32) {
+        let half_width = width / 2.0;
+        let bounds = Rect::from_center_size(center, radius * 2.0).expand(half_width);
+
+        self.fill_sdf(bounds, color, |p| {
+            ellipse_sdf(p - center, radius).abs() - half_width
+        });
+    }
+}
+
+// Where a flat-ended stroke is cut off, and which of its segments the cut applies to
+struct FlatEnd {
+    point: Pos2,
+    inward: Vec2,
+    segments: Range<usize>,
+}
+
+fn flat_ends(points: &[Pos2], widths: &[f32], cap: StrokeCap) -> Vec<FlatEnd> {
+    let (Some(&first), Some(&last)) = (points.first(), points.last()) else {
+        return Vec::new();
+    };
+    if cap == StrokeCap::Round {
+        return Vec::new();
+    }
+    let (Some(&next), Some(&before)) = (
+        points.iter().find(|point| **point != first),
+        points.iter().rev().find(|point| **point != last),
+    ) else {
+        return Vec::new();
+    };
+
+    // Segments starting within half a width of an end, measured along the stroke
+    let reach = widths.iter().copied().fold(0.0_f32, f32::max) / 2.0;
+    let lengths: Vec<f32> = points
+        .windows(2)
+        .map(|pair| match pair {
+            [a, b] => a.distance(*b),
+            _ => 0.0,
+        })
+        .collect();
+    let from_start = lengths
+        .iter()
+        .scan(0.0, |arc, length| {
+            let start = *arc;
+            *arc += length;
+            Some(start)
+        })
+        .take_while(|arc| *arc < reach)
+        .count();
+    let from_end = lengths
+        .iter()
+        .rev()
+        .scan(0.0, |arc, length| {
+            let start = *arc;
+            *arc += length;
+            Some(start)
+        })
+        .take_while(|arc| *arc < reach)
+        .count();
+
+    vec![
+        FlatEnd {
+            point: first,
+            inward: (next - first).normalized(),
+            segments: 0..from_start,
+        },
+        FlatEnd {
+            point: last,
+            inward: (before - last).normalized(),
+            segments: lengths.len() - from_end..lengths.len(),
+        },
+    ]
+}
+
+fn pixel_center(x: u32, y: u32) -> Pos2 {
+    Pos2::new(x as f32 + 0.5, y as f32 + 0.5)
+}
+
+// Segment with round ends whose width changes linearly from `width_a` to `width_b`
+fn tapered_sdf(p: Pos2, a: Pos2, b: Pos2, width_a: f32, width_b: f32) -> f32 {
+    let ab = b - a;
+    let length_sq = ab.length_sq();
+    let t = if length_sq <= f32::EPSILON {
+        0.0
+    } else {
+        ((p - a).dot(ab) / length_sq).clamp(0.0, 1.0)
+    };
+    let half_width = egui::lerp(width_a..=width_b, t) * 0.5;
+
+    p.distance(a + ab * t) - half_width
+}
+
+// Segments are drawn with butt ends, matching `Painter::line_segment`
+fn segment_sdf(p: Pos2, a: Pos2, b: Pos2, half_width: f32) -> f32 {
+    let ab = b - a;
+    let length = ab.length();
+    if length <= f32::EPSILON {
+        return f32::INFINITY;
+    }
+
+    let direction = ab / length;
+    let along = (p - a).dot(direction);
+    let across = (p - a).dot(direction.rot90()).abs();
+    let past_ends = (-along).max(along - length);
+
+    (across - half_width).max(past_ends)
+}
+
+fn box_sdf(p: Pos2, rect: Rect) -> f32 {
+    let d = (p - rect.center()).abs() - rect.size() / 2.0;
+    let outside = d.max(Vec2::ZERO).length();
+    let inside = d.x.max(d.y).min(0.0);
+
+    outside + inside
+}
+
+// Approximate distance to an ellipse outline (negative inside)
+fn ellipse_sdf(p: Vec2, radius: Vec2) -> f32 {
+    if radius.x <= f32::EPSILON || radius.y <= f32::EPSILON {
+        return segment_sdf(
+            Pos2::ZERO + p,
+            Pos2::ZERO - radius,
+            Pos2::ZERO + radius,
+            0.0,
+        );
+    }
+
+    let k0 = (p / radius).length();
+    let k1 = (p / (radius * radius)).length();
+    if k1 <= f32::EPSILON {
+        return -radius.x.min(radius.y);
+    }
+
+    k0 * (k0 - 1.0) / k1
+}
+
+// Draws the visible layers of `canvas` at `scale` pixels per canvas unit
+pub fn rasterize(canvas: &Canvas, scale: f32) -> Pixmap {
+    let area = canvas.canvas_area;
+    let size = area.size() * scale;
+    let mut pixmap = Pixmap::new(
+        size.x.ceil().max(1.0) as u32,
+        size.y.ceil().max(1.0) as u32,
+        Color32::WHITE,
+    );
+    let to_pixels = |p: Pos2| Pos2::ZERO + (p - area.min) * scale;
+    // Only loaded once a layer has text, since building the fonts is slow
+    let mut fonts: Option<Fonts> = None;
+
+    for layer in canvas.layers.iter().filter(|layer| layer.visible) {
+        for fill in &layer.fills {
+            let contours: Vec<Vec<Pos2>> = fill
+                .contours
+                .iter()
+                .map(|contour| contour.iter().map(|p| to_pixels(*p)).collect())
+                .collect();
+            pixmap.polygon(&contours, fill.color.gamma_multiply(layer.opacity));
+        }
+
+        // Each stroke is blended once, so highlighter ink doesn't darken where it overlaps
+        for stroke in layer
+            .stroke_paint_order()
+            .into_iter()
+            .filter_map(|index| layer.strokes.get(index))
+        {
+            let color = stroke.ink().gamma_multiply(layer.opacity);
+            let points: Vec<Pos2> = stroke.capped_points().into_iter().map(to_pixels).collect();
+            let widths: Vec<f32> = (0..points.len())
+                .map(|index| stroke.width_at(index) * scale)
+                .collect();
+
+            pixmap.polyline(&points, &widths, color, stroke.end_cap());
+        }
+
+        for shape in &layer.shapes {
+            let color = shape.stroke.color.gamma_multiply(layer.opacity);
+            let width = shape.stroke.width * scale;
+            let from = to_pixels(shape.from);
+            let to = to_pixels(shape.to);
+
+            paint_shape(&mut pixmap, shape, from, to, width, color);
+        }
+
+        if !layer.texts.is_empty() {
+            let fonts = fonts.get_or_insert_with(|| {
+                Fonts::new(
+                    FONT_TEXTURE_SIDE,
+                    AlphaFromCoverage::LIGHT_MODE_DEFAULT,
+                    FontDefinitions::default(),
+                )
+            });
+            paint_texts(
+                &mut pixmap,
+                fonts,
+                &layer.texts,
+                layer.opacity,
+                area.min,
+                scale,
+            );
+        }
+    }
+
+    pixmap
+}
+
+fn paint_shape(
+    pixmap: &mut Pixmap,
+    shape: &Shape,
+    from: Pos2,
+    to: Pos2,
+    width: f32,
+    color: Color32,
+) {
+    let bounds = Rect::from_two_pos(from, to);
+
+    match shape.kind {
+        ShapeKind::Rectangle => pixmap.rect_stroke(bounds, width, color),
+        ShapeKind::Ellipse => {
+            pixmap.ellipse_stroke(bounds.center(), bounds.size() / 2.0, width, color);
+        }
+        ShapeKind::Line => pixmap.line_segment([from, to], width, color),
+    }
+}
+
+// Text is laid out by egui at the export scale, so one texel of the font atlas is one pixel
+fn paint_texts(
+    pixmap: &mut Pixmap,
+    fonts: &mut Fonts,
+    texts: &[TextBox],
+    opacity: f32,
+    origin: Pos2,
+    scale: f32,
+) {
+    let galleys: Vec<(Pos2, Arc<Galley>)> = texts
+        .iter()
+        .map(|text| {
+            let job = text.layout_job(text.color.gamma_multiply(opacity));
+            (text.pos, fonts.with_pixels_per_point(scale).layout_job(job))
+        })
+        .collect();
+    // Laying out adds glyphs to the atlas, so it is read afterwards
+    let atlas = fonts.image();
+
+    for (pos, galley) in galleys {
+        for row in &galley.rows {
+            let offset = pos + row.pos.to_vec2() - origin;
+            let mesh = &row.visuals.mesh;
+
+            // Every glyph is a quad of two triangles
+            for quad in mesh.indices.chunks_exact(6) {
+                let vertices: Vec<_> = quad
+                    .iter()
+                    .filter_map(|index| mesh.vertices.get(*index as usize))
+                    .collect();
+                let Some(color) = vertices.first().map(|vertex| vertex.color) else {
+                    continue;
+                };
+                let corners: Vec<Pos2> = vertices
+                    .iter()
+                    .map(|vertex| Pos2::ZERO + (offset + vertex.pos.to_vec2()) * scale)
+                    .collect();
+                let uvs: Vec<Pos2> = vertices.iter().map(|vertex| vertex.uv).collect();
+
+                pixmap.glyph(
+                    Rect::from_points(&corners),
+                    Rect::from_points(&uvs),
+                    &atlas,
+                    color,
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use crate::draw::canvas::{Canvas, SingleStroke};
+use crate::draw::fill::Fill;
+use crate::draw::pressure;
+use crate::draw::shapes::{Shape, ShapeKind};
+use crate::draw::text::{self, TextAlign, TextBox};
+use egui::{Pos2, Stroke};
+use std::fmt::Write as _;
+
+// Renders the visible layers of the canvas as a standalone SVG document
+pub fn canvas_to_svg(canvas: &Canvas) -> String {
+    let area = canvas.canvas_area;
+    let mut svg = String::new();
+
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}" width="{}" height="{}">"#,
+        area.min.x,
+        area.min.y,
+        area.width(),
+        area.height(),
+        area.width(),
+        area.height(),
+    )
+    .ok();
+    writeln!(
+        svg,
+        r##"  <rect x="{}" y="{}" width="{}" height="{}" fill="#ffffff"/>"##,
+        area.min.x,
+        area.min.y,
+        area.width(),
+        area.height(),
+    )
+    .ok();
+
+    // Layer names needn't be unique or valid XML ids, so the id comes from the position
+    for (index, layer) in canvas
+        .layers
+        .iter()
+        .enumerate()
+        .filter(|(_, layer)| layer.visible)
+    {
+        // The canvas fades every object by the layer opacity on its own, so overlaps within
+        // the layer show through each other; a group opacity would fade them as one
+        let mut layer = layer.clone();
+        layer.multiply_colors(layer.opacity);
+
+        writeln!(
+            svg,
+            r#"  <g id="layer-{}" data-name="{}">"#,
+            index + 1,
+            escape(&layer.name),
+        )
+        .ok();
+        for fill in &layer.fills {
+            if let Some(path) = fill_to_path(fill) {
+                writeln!(svg, "    {path}").ok();
+            }
+        }
+        for stroke in layer
+            .stroke_paint_order()
+            .into_iter()
+            .filter_map(|index| layer.strokes.get(index))
+        {
+            if let Some(path) = stroke_to_path(stroke) {
+                writeln!(svg, "    {path}").ok();
+            }
+        }
+        for shape in &layer.shapes {
+            writeln!(svg, "    {}", shape_to_element(shape)).ok();
+        }
+        for text in &layer.texts {
+            writeln!(svg, "    {}", text_to_element(text)).ok();
+        }
+        svg.push_str("  </g>\n");
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+pub fn stroke_to_path(stroke: &SingleStroke) -> Option<String> {
+    if stroke.is_tapered() {
+        return tapered_path(stroke);
+    }
+
+    let data = path_data(&stroke.points);
+    if data.is_empty() {
+        return None;
+    }
+
+    Some(format!(
+        r#"<path d="{}" fill="none" {} stroke-linecap="{}" stroke-linejoin="round"/>"#,
+        data,
+        stroke_attributes(Stroke::new(stroke.stroke.width, stroke.ink())),
+        stroke.end_cap().svg_name()
+    ))
+}
+
+// Pressure strokes become their filled outline so the varying width survives
+fn tapered_path(stroke: &SingleStroke) -> Option<String> {
+    let data = path_data(&pressure::outline(stroke));
+    if data.is_empty() {
+        return None;
+    }
+
+    let [r, g, b, a] = stroke.ink().to_srgba_unmultiplied();
+    Some(format!(
+        r##"<path d="{} Z" fill="#{r:02x}{g:02x}{b:02x}" fill-opacity="{}" stroke="none"/>"##,
+        data,
+        f32::from(a) / 255.0,
+    ))
+}
+
+// All contours in one path, so the holes stay open under the even-odd rule
+fn fill_to_path(fill: &Fill) -> Option<String> {
+    let data: Vec<String> = fill
+        .contours
+        .iter()
+        .filter(|contour| !contour.is_empty())
+        .map(|contour| format!("{} Z", path_data(contour)))
+        .collect();
+    if data.is_empty() {
+        return None;
+    }
+
+    let [r, g, b, a] = fill.color.to_srgba_unmultiplied();
+    Some(format!(
+        r##"<path d="{}" fill="#{r:02x}{g:02x}{b:02x}" fill-opacity="{}" fill-rule="evenodd" stroke="none"/>"##,
+        data.join(" "),
+        f32::from(a) / 255.0,
+    ))
+}
+
+// Moves to the first point and draws lines through the rest
+fn path_data(points: &[Pos2]) -> String {
+    let mut data = String::new();
+    for (index, point) in points.iter().enumerate() {
+        let command = if index == 0 { 'M' } else { 'L' };
+        if index > 0 {
+            data.push(' ');
+        }
+        write!(data, "{command}{} {}", point.x, point.y).ok();
+    }
+    data
+}
+
+fn shape_to_element(shape: &Shape) -> String {
+    let bounds = shape.bounds();
+    let stroke = stroke_attributes(shape.stroke);
+
+    match shape.kind {
+        ShapeKind::Rectangle => format!(
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="none" {stroke}/>"#,
+            bounds.min.x,
+            bounds.min.y,
+            bounds.width(),
+            bounds.height(),
+        ),
+        ShapeKind::Ellipse => format!(
+            r#"<ellipse cx="{}" cy="{}" rx="{}" ry="{}" fill="none" {stroke}/>"#,
+            bounds.center().x,
+            bounds.center().y,
+            bounds.width() / 2.0,
+            bounds.height() / 2.0,
+        ),
+        ShapeKind::Line => format!(
+            r#"<line x1="{}" y1="{}" x2="{}" y2="{}" {stroke} stroke-linecap="butt"/>"#,
+            shape.from.x, shape.from.y, shape.to.x, shape.to.y,
+        ),
+    }
+}
+
+// One <tspan> per line, each placed on its own baseline
+fn text_to_element(text: &TextBox) -> String {
+    let anchor = match text.align {
+        TextAlign::Left => "start",
+        TextAlign::Center => "middle",
+        TextAlign::Right => "end",
+    };
+    let first_baseline = text.pos.y + text.size * text::BASELINE;
+    let mut lines = String::new();
+    for (index, line) in text.lines().enumerate() {
+        write!(
+            lines,
+            r#"<tspan x="{}" y="{}">{}</tspan>"#,
+            text.pos.x,
+            first_baseline + index as f32 * text.line_height(),
+            escape(line),
+        )
+        .ok();
+    }
+
+    let [r, g, b, a] = text.color.to_srgba_unmultiplied();
+    format!(
+        r##"<text font-family="sans-serif" font-size="{}" fill="#{r:02x}{g:02x}{b:02x}" fill-opacity="{}" text-anchor="{anchor}" xml:space="preserve">{lines}</text>"##,
+        text.size,
+        f32::from(a) / 255.0,
+    )
+}
+
+fn stroke_attributes(stroke: egui::Stroke) -> String {
+    let [r, g, b, a] = stroke.color.to_srgba_unmultiplied();
+
+    format!(
+        r##"stroke="#{r:02x}{g:02x}{b:02x}" stroke-opacity="{}" stroke-width="{}""##,
+        f32::from(a) / 255.0,
+        stroke.width
+    )
+}
+
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
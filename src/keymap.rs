@@ -0,0 +1,255 @@
+use crate::draw::brush;
+use crate::toolbar::main::Tool;
+use egui::{Key, KeyboardShortcut, Modifiers};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
+pub enum Command {
+    Undo,
+    Redo,
+    SelectTool(Tool),
+    // Index into the brush presets
+    ApplyPreset(usize),
+    IncreaseWidth,
+    DecreaseWidth,
+    ZoomIn,
+    ZoomOut,
+    ZoomFit,
+    DeleteSelection,
+    DuplicateSelection,
+    SimplifyAll,
+    Save,
+    Open,
+}
+
+impl Command {
+    pub fn label(&self) -> String {
+        match self {
+            Self::Undo => "Undo".to_owned(),
+            Self::Redo => "Redo".to_owned(),
+            Self::SelectTool(tool) => format!("{} tool", tool.name()),
+            Self::ApplyPreset(index) => format!("Brush preset {}", index + 1),
+            Self::IncreaseWidth => "Increase brush width".to_owned(),
+            Self::DecreaseWidth => "Decrease brush width".to_owned(),
+            Self::ZoomIn => "Zoom in".to_owned(),
+            Self::ZoomOut => "Zoom out".to_owned(),
+            Self::ZoomFit => "Zoom to fit".to_owned(),
+            Self::DeleteSelection => "Delete selection".to_owned(),
+            Self::DuplicateSelection => "Duplicate selection".to_owned(),
+            Self::SimplifyAll => "Simplify all strokes".to_owned(),
+            Self::Save => "Save".to_owned(),
+            Self::Open => "Open".to_owned(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct Binding {
+    pub command: Command,
+    pub shortcut: Option<KeyboardShortcut>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(from = "SavedKeymap")]
+pub struct Keymap {
+    pub bindings: Vec<Binding>,
+}
+
+// A keymap as it was saved, possibly by a build that had fewer commands
+#[derive(Default, serde::Deserialize)]
+#[serde(default)]
+struct SavedKeymap {
+    bindings: Vec<Binding>,
+}
+
+// Commands the saved keymap doesn't know about get their default shortcut, or none if that
+// shortcut has since been given to another command
+impl From<SavedKeymap> for Keymap {
+    fn from(saved: SavedKeymap) -> Self {
+        let mut keymap = Self {
+            bindings: saved.bindings,
+        };
+
+        for default in Self::default().bindings {
+            if keymap
+                .bindings
+                .iter()
+                .any(|binding| binding.command == default.command)
+            {
+                continue;
+            }
+            let taken = default.shortcut.is_some_and(|shortcut| {
+                keymap
+                    .bindings
+                    .iter()
+                    .any(|binding| binding.shortcut == Some(shortcut))
+            });
+            keymap.bindings.push(Binding {
+                command: default.command,
+                shortcut: default.shortcut.filter(|_| !taken),
+            });
+        }
+
+        keymap
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let command = |modifiers, key| Some(KeyboardShortcut::new(modifiers, key));
+        let plain = |key| Some(KeyboardShortcut::new(Modifiers::NONE, key));
+
+        let mut bindings = vec![
+            Binding {
+                command: Command::Undo,
+                shortcut: command(Modifiers::COMMAND, Key::Z),
+            },
+            Binding {
+                command: Command::Redo,
+                shortcut: command(Modifiers::COMMAND | Modifiers::SHIFT, Key::Z),
+            },
+            Binding {
+                command: Command::IncreaseWidth,
+                shortcut: plain(Key::CloseBracket),
+            },
+            Binding {
+                command: Command::DecreaseWidth,
+                shortcut: plain(Key::OpenBracket),
+            },
+            Binding {
+                command: Command::ZoomIn,
+                shortcut: command(Modifiers::COMMAND, Key::Equals),
+            },
+            Binding {
+                command: Command::ZoomOut,
+                shortcut: command(Modifiers::COMMAND, Key::Minus),
+            },
+            Binding {
+                command: Command::ZoomFit,
+                shortcut: command(Modifiers::COMMAND, Key::Num0),
+            },
+            Binding {
+                command: Command::DeleteSelection,
+                shortcut: plain(Key::Delete),
+            },
+            Binding {
+                command: Command::DuplicateSelection,
+                shortcut: command(Modifiers::COMMAND, Key::D),
+            },
+            Binding {
+                command: Command::SimplifyAll,
+                shortcut: None,
+            },
+            Binding {
+                command: Command::Save,
+                shortcut: command(Modifiers::COMMAND, Key::S),
+            },
+            Binding {
+                command: Command::Open,
+                shortcut: command(Modifiers::COMMAND, Key::O),
+            },
+        ];
+
+        bindings.extend(Tool::ALL.into_iter().map(|tool| Binding {
+            command: Command::SelectTool(tool),
+            shortcut: tool.default_key().and_then(plain),
+        }));
+
+        let number_keys = [
+            Key::Num1,
+            Key::Num2,
+            Key::Num3,
+            Key::Num4,
+            Key::Num5,
+            Key::Num6,
+            Key::Num7,
+            Key::Num8,
+            Key::Num9,
+        ];
+        bindings.extend(
+            number_keys
+                .into_iter()
+                .take(brush::SHORTCUT_SLOTS)
+                .enumerate()
+                .map(|(index, key)| Binding {
+                    command: Command::ApplyPreset(index),
+                    shortcut: plain(key),
+                }),
+        );
+
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    pub fn shortcut(&self, command: Command) -> Option<KeyboardShortcut> {
+        self.bindings
+            .iter()
+            .find(|binding| binding.command == command)
+            .and_then(|binding| binding.shortcut)
+    }
+
+    pub fn set_shortcut(&mut self, command: Command, shortcut: Option<KeyboardShortcut>) {
+        match self
+            .bindings
+            .iter_mut()
+            .find(|binding| binding.command == command)
+        {
+            Some(binding) => binding.shortcut = shortcut,
+            None => self.bindings.push(Binding { command, shortcut }),
+        }
+    }
+
+    // Pairs of commands bound to the same shortcut
+    pub fn conflicts(&self) -> Vec<(Command, Command, KeyboardShortcut)> {
+        let mut conflicts = Vec::new();
+
+        for (i, first) in self.bindings.iter().enumerate() {
+            for second in self.bindings.iter().skip(i + 1) {
+                if let (Some(a), Some(b)) = (first.shortcut, second.shortcut)
+                    && a == b
+                {
+                    conflicts.push((first.command, second.command, a));
+                }
+            }
+        }
+
+        conflicts
+    }
+
+    pub fn has_conflict(&self, command: Command) -> bool {
+        self.conflicts()
+            .iter()
+            .any(|(a, b, _)| *a == command || *b == command)
+    }
+
+    // Consumes this frame's key presses and returns the triggered commands. Shortcuts with
+    // more modifiers are checked first, since egui lets Ctrl+Z also match Ctrl+Shift+Z.
+    pub fn triggered(&self, ctx: &egui::Context) -> Vec<Command> {
+        let mut bindings: Vec<(Command, KeyboardShortcut)> = self
+            .bindings
+            .iter()
+            .filter_map(|binding| Some((binding.command, binding.shortcut?)))
+            .collect();
+        bindings.sort_by_key(|(_, shortcut)| std::cmp::Reverse(modifier_count(shortcut.modifiers)));
+
+        ctx.input_mut(|input| {
+            bindings
+                .into_iter()
+                .filter(|(_, shortcut)| input.consume_shortcut(shortcut))
+                .map(|(command, _)| command)
+                .collect()
+        })
+    }
+}
+
+fn modifier_count(modifiers: Modifiers) -> usize {
+    [
+        modifiers.alt,
+        modifiers.ctrl || modifiers.command,
+        modifiers.shift,
+        modifiers.mac_cmd,
+    ]
+    .into_iter()
+    .filter(|pressed| *pressed)
+    .count()
+}
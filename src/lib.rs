@@ -3,13 +3,53 @@
 mod app;
 pub use app::SimplePaintApp;
 pub mod draw {
+    pub mod brush;
     pub mod canvas;
+    pub mod eraser;
+    pub mod eyedropper;
+    pub mod fill;
+    pub mod highlighter;
+    pub mod history;
+    pub mod mesh_cache;
+    pub mod pressure;
+    pub mod selection;
+    pub mod shapes;
+    pub mod simplify;
+    pub mod smoothing;
+    pub mod spatial;
+    pub mod text;
 }
 
 pub mod utils;
 
+pub mod document {
+    pub mod clipboard;
+    pub mod file;
+    pub mod format;
+    pub mod migrations;
+    pub mod palette;
+}
+
+pub mod export {
+    pub mod png;
+    pub mod raster;
+    pub mod svg;
+}
+
 pub mod toolbar {
     pub mod main;
 }
 
+pub mod keymap;
+
 pub mod modals;
+
+pub mod panels {
+    pub mod layers;
+    pub mod palette;
+}
+
+pub mod tools {
+    pub mod select;
+    pub mod text;
+}
@@ -2,7 +2,8 @@ use std::num::ParseFloatError;
 
 use super::SimplePaintApp;
 use crate::draw::canvas::Canvas;
-use egui::Margin;
+use crate::keymap::{Command, Keymap};
+use egui::{KeyboardShortcut, Margin};
 
 #[derive(serde::Deserialize, serde::Serialize)]
 pub struct InitialModal {
@@ -96,3 +97,172 @@ pub fn initial_modal(ctx: &egui::Context, app: &mut SimplePaintApp) {
             });
         });
 }
+
+pub fn error_modal(ctx: &egui::Context, app: &mut SimplePaintApp) {
+    let Some(error) = &app.error else {
+        return;
+    };
+    let message = error.to_string();
+
+    egui::Window::new("Error Modal")
+        .max_width(300.0)
+        .title_bar(false)
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+        .frame(
+            egui::Frame::new()
+                .inner_margin(Margin::symmetric(40, 20))
+                .fill(egui::Color32::from_hex("#ebeded").unwrap_or_default())
+                .corner_radius(10.0),
+        )
+        .show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.label(egui::RichText::new("Something went wrong").size(16.0));
+                ui.add_space(10.0);
+                ui.label(message);
+                ui.add_space(10.0);
+                if ui.add(egui::Button::new("Dismiss")).clicked() {
+                    app.error = None;
+                }
+            });
+        });
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct SessionSettings {
+    pub restore_session: bool,
+    pub restore_history: bool,
+}
+
+impl Default for SessionSettings {
+    fn default() -> Self {
+        Self {
+            restore_session: true,
+            restore_history: true,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Preferences {
+    pub open: bool,
+    // Command waiting for the next key press to become its shortcut
+    pub capturing: Option<Command>,
+}
+
+pub fn preferences_window(ctx: &egui::Context, app: &mut SimplePaintApp) {
+    if let Some(command) = app.preferences.capturing {
+        capture_shortcut(ctx, app, command);
+    }
+
+    let mut open = app.preferences.open;
+
+    egui::Window::new("Preferences")
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.label(egui::RichText::new("Keyboard Shortcuts").size(16.0));
+            ui.add_space(10.0);
+
+            egui::Grid::new("keymap grid").striped(true).show(ui, |ui| {
+                let bindings = app.keymap.bindings.clone();
+                for binding in bindings {
+                    keymap_row(ui, app, binding.command, binding.shortcut);
+                    ui.end_row();
+                }
+            });
+
+            ui.add_space(10.0);
+            for (first, second, shortcut) in app.keymap.conflicts() {
+                ui.colored_label(
+                    egui::Color32::RED,
+                    format!(
+                        "{} is bound to both {} and {}",
+                        ctx.format_shortcut(&shortcut),
+                        first.label(),
+                        second.label()
+                    ),
+                );
+            }
+
+            ui.add_space(10.0);
+            if ui.button("Reset to Defaults").clicked() {
+                app.keymap = Keymap::default();
+                app.preferences.capturing = None;
+            }
+
+            ui.separator();
+            ui.label(egui::RichText::new("Session").size(16.0));
+            ui.add_space(10.0);
+            ui.checkbox(
+                &mut app.session.restore_session,
+                "Restore the previous drawing on startup",
+            );
+            ui.add_enabled(
+                app.session.restore_session,
+                egui::Checkbox::new(
+                    &mut app.session.restore_history,
+                    "Also restore undo history",
+                ),
+            );
+        });
+
+    app.preferences.open = open;
+    if !open {
+        app.preferences.capturing = None;
+    }
+}
+
+fn keymap_row(
+    ui: &mut egui::Ui,
+    app: &mut SimplePaintApp,
+    command: Command,
+    shortcut: Option<KeyboardShortcut>,
+) {
+    let label = egui::RichText::new(command.label());
+    if app.keymap.has_conflict(command) {
+        ui.label(label.color(egui::Color32::RED));
+    } else {
+        ui.label(label);
+    }
+
+    let text = if app.preferences.capturing == Some(command) {
+        "Press a key…".to_owned()
+    } else {
+        shortcut.map_or_else(|| "—".to_owned(), |s| ui.ctx().format_shortcut(&s))
+    };
+
+    if ui.button(text).clicked() {
+        app.preferences.capturing = Some(command);
+    }
+    if ui.button("Clear").clicked() {
+        app.keymap.set_shortcut(command, None);
+    }
+}
+
+// Binds the next key press to `command`, or cancels on Escape
+fn capture_shortcut(ctx: &egui::Context, app: &mut SimplePaintApp, command: Command) {
+    let pressed = ctx.input(|input| {
+        input.events.iter().find_map(|event| match event {
+            egui::Event::Key {
+                key,
+                pressed: true,
+                modifiers,
+                ..
+            } => Some(KeyboardShortcut::new(*modifiers, *key)),
+            _ => None,
+        })
+    });
+
+    let Some(shortcut) = pressed else {
+        return;
+    };
+
+    if shortcut.logical_key != egui::Key::Escape {
+        app.keymap.set_shortcut(command, Some(shortcut));
+    }
+    app.preferences.capturing = None;
+}
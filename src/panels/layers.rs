@@ -0,0 +1,204 @@
+use super::super::SimplePaintApp;
+use crate::draw::canvas::{Action, Layer, LayerSettings};
+use egui::Margin;
+
+pub fn layers_panel(app: &mut SimplePaintApp, ui: &mut egui::Ui) {
+    egui::Frame::NONE
+        .inner_margin(Margin::symmetric(8, 8))
+        .show(ui, |ui| {
+            ui.heading("Layers");
+            ui.add_space(5.0);
+
+            layer_buttons(app, ui);
+            ui.separator();
+
+            // Topmost layer is listed first
+            for index in (0..app.canvas.layers.len()).rev() {
+                layer_row(app, ui, index);
+            }
+
+            ui.separator();
+
+            opacity_slider(app, ui);
+        });
+}
+
+// Dragging previews the opacity on the layer and records it once the drag ends
+fn opacity_slider(app: &mut SimplePaintApp, ui: &mut egui::Ui) {
+    let index = app.canvas.active_layer;
+    let Some(before) = app.canvas.active_layer().map(Layer::settings) else {
+        return;
+    };
+
+    let mut opacity = before.opacity;
+    let response = ui
+        .horizontal(|ui| {
+            ui.label("Opacity");
+            ui.add(egui::Slider::new(&mut opacity, 0.0..=1.0))
+        })
+        .inner;
+
+    if response.changed() {
+        if response.dragged() {
+            app.layer_opacity_drag.get_or_insert((index, before));
+            if let Some(layer) = app.canvas.active_layer_mut() {
+                layer.opacity = opacity;
+            }
+        } else {
+            app.run(Action::ModifyLayer {
+                before,
+                after: LayerSettings { opacity, ..before },
+                index,
+            });
+        }
+    }
+
+    if response.drag_stopped()
+        && let Some((index, before)) = app.layer_opacity_drag.take()
+        && let Some(after) = app.canvas.layers.get(index).map(Layer::settings)
+        && after != before
+    {
+        app.run(Action::ModifyLayer {
+            before,
+            after,
+            index,
+        });
+    }
+}
+
+fn layer_buttons(app: &mut SimplePaintApp, ui: &mut egui::Ui) {
+    let layer_count = app.canvas.layers.len();
+    let active = app.canvas.active_layer;
+
+    ui.horizontal_wrapped(|ui| {
+        if ui.button("Add").clicked() {
+            let layer = Layer::new(format!("Layer {}", layer_count + 1));
+            app.run(Action::AddLayer {
+                layer,
+                index: active + 1,
+            });
+        }
+
+        // A canvas always keeps at least one layer
+        if ui
+            .add_enabled(layer_count > 1, egui::Button::new("Delete"))
+            .clicked()
+            && let Some(layer) = app.canvas.active_layer().cloned()
+        {
+            app.run(Action::DeleteLayer {
+                layer,
+                index: active,
+            });
+        }
+
+        if ui
+            .add_enabled(active + 1 < layer_count, egui::Button::new("Up"))
+            .clicked()
+        {
+            app.run(Action::MoveLayer {
+                from: active,
+                to: active + 1,
+            });
+        }
+
+        if ui
+            .add_enabled(active > 0, egui::Button::new("Down"))
+            .clicked()
+        {
+            app.run(Action::MoveLayer {
+                from: active,
+                to: active - 1,
+            });
+        }
+
+        if ui
+            .add_enabled(active > 0, egui::Button::new("Merge Down"))
+            .clicked()
+            && let Some(upper) = app.canvas.active_layer().cloned()
+            && let Some(lower) = app.canvas.layers.get(active - 1).cloned()
+        {
+            app.run(Action::MergeDown {
+                upper,
+                lower,
+                index: active,
+            });
+        }
+    });
+}
+
+fn layer_row(app: &mut SimplePaintApp, ui: &mut egui::Ui, index: usize) {
+    let is_renaming = app
+        .layer_rename
+        .as_ref()
+        .is_some_and(|(renaming, _)| *renaming == index);
+
+    ui.horizontal(|ui| {
+        let Some(layer) = app.canvas.layers.get(index) else {
+            return;
+        };
+        let before = layer.settings();
+        let name = layer.name.clone();
+
+        let mut after = before;
+        let visible = ui.checkbox(&mut after.visible, "").on_hover_text("Visible");
+        let locked = ui
+            .toggle_value(&mut after.locked, "🔒")
+            .on_hover_text("Locked");
+        if visible.changed() || locked.changed() {
+            app.run(Action::ModifyLayer {
+                before,
+                after,
+                index,
+            });
+        }
+
+        if is_renaming {
+            rename_field(app, ui, index);
+            return;
+        }
+
+        let response = ui.selectable_label(app.canvas.active_layer == index, &name);
+
+        if response.clicked() {
+            app.canvas.active_layer = index;
+        }
+        if response.double_clicked() {
+            app.layer_rename = Some((index, name));
+            ui.memory_mut(|mem| mem.request_focus(rename_id(index)));
+        }
+    });
+}
+
+fn rename_field(app: &mut SimplePaintApp, ui: &mut egui::Ui, index: usize) {
+    let Some((_, name)) = &mut app.layer_rename else {
+        return;
+    };
+
+    let response = ui.add(
+        egui::TextEdit::singleline(name)
+            .id(rename_id(index))
+            .desired_width(100.0),
+    );
+
+    if response.lost_focus() {
+        let after = name.trim().to_owned();
+        app.layer_rename = None;
+
+        let Some(layer) = app.canvas.layers.get(index) else {
+            return;
+        };
+
+        if !after.is_empty() && after != layer.name {
+            let before = layer.name.clone();
+            app.run(Action::RenameLayer {
+                before,
+                after,
+                index,
+            });
+        }
+    }
+}
+
+fn rename_id(index: usize) -> egui::Id {
+    egui::Id::new(("layer rename", index))
+}
@@ -0,0 +1,144 @@
+use super::super::SimplePaintApp;
+use crate::document::palette::{self, Palette, PaletteFormat};
+use egui::{Color32, Margin, Response, Sense, Stroke, Vec2};
+
+const SWATCH_SIZE: f32 = 18.0;
+
+pub fn palette_panel(app: &mut SimplePaintApp, ui: &mut egui::Ui) {
+    egui::Frame::NONE
+        .inner_margin(Margin::symmetric(8, 8))
+        .show(ui, |ui| {
+            ui.heading("Palette");
+            ui.add_space(5.0);
+
+            ui.label("Recent");
+            let recent = app.palettes.recent.clone();
+            swatch_row(app, ui, &recent);
+            ui.separator();
+
+            palette_buttons(app, ui);
+            active_palette(app, ui);
+            ui.separator();
+
+            // Scanned only while open, since it walks every stroke
+            egui::CollapsingHeader::new("Document colors").show(ui, |ui| {
+                let document = palette::document_colors(&app.canvas);
+                let colors: Vec<Color32> = document.colors().collect();
+                swatch_row(app, ui, &colors);
+                if ui
+                    .add_enabled(!colors.is_empty(), egui::Button::new("Save as Palette"))
+                    .clicked()
+                {
+                    app.palettes.insert(document);
+                }
+            });
+        });
+}
+
+fn palette_buttons(app: &mut SimplePaintApp, ui: &mut egui::Ui) {
+    let selected = app
+        .palettes
+        .active()
+        .map(|palette| palette.name.clone())
+        .unwrap_or_default();
+    egui::ComboBox::from_id_salt("palette")
+        .selected_text(selected)
+        .show_ui(ui, |ui| {
+            for (index, palette) in app.palettes.palettes.iter().enumerate() {
+                ui.selectable_value(&mut app.palettes.active, index, &palette.name);
+            }
+        });
+
+    ui.horizontal_wrapped(|ui| {
+        if ui.button("New").clicked() {
+            let name = format!("Palette {}", app.palettes.palettes.len() + 1);
+            app.palettes.insert(Palette::new(name));
+        }
+        // Keep at least one palette to add colors to
+        if ui
+            .add_enabled(app.palettes.palettes.len() > 1, egui::Button::new("Delete"))
+            .clicked()
+        {
+            app.palettes.remove_active();
+        }
+        if ui.button("Import").clicked() {
+            app.import_palette_dialog();
+        }
+        ui.menu_button("Export", |ui| {
+            for format in PaletteFormat::ALL {
+                if ui.button(format.name()).clicked() {
+                    app.export_palette_dialog(format);
+                }
+            }
+        });
+    });
+}
+
+fn active_palette(app: &mut SimplePaintApp, ui: &mut egui::Ui) {
+    let current = app.stroke_type.color;
+    let Some(palette) = app.palettes.active_mut() else {
+        return;
+    };
+
+    ui.add(egui::TextEdit::singleline(&mut palette.name).hint_text("Palette name"));
+
+    let mut picked = None;
+    let mut removed = None;
+    ui.horizontal_wrapped(|ui| {
+        for (index, entry) in palette.swatches.iter().enumerate() {
+            let response = swatch(ui, entry.color, current).on_hover_text(entry.label());
+            if response.clicked() {
+                picked = Some(entry.color);
+            }
+            response.context_menu(|ui| {
+                if ui.button("Remove").clicked() {
+                    removed = Some(index);
+                }
+            });
+        }
+    });
+    if ui.button("Add Current Color").clicked() {
+        palette.add(current);
+    }
+
+    if let Some(index) = removed {
+        palette.swatches.remove(index);
+    }
+    if let Some(color) = picked {
+        app.stroke_type.color = color;
+    }
+}
+
+// Swatches that set the pen color when clicked
+fn swatch_row(app: &mut SimplePaintApp, ui: &mut egui::Ui, colors: &[Color32]) {
+    if colors.is_empty() {
+        ui.weak("None yet");
+        return;
+    }
+
+    ui.horizontal_wrapped(|ui| {
+        for color in colors {
+            if swatch(ui, *color, app.stroke_type.color)
+                .on_hover_text(color.to_hex())
+                .clicked()
+            {
+                app.stroke_type.color = *color;
+            }
+        }
+    });
+}
+
+// A color square, outlined more strongly when it is the current color
+fn swatch(ui: &mut egui::Ui, color: Color32, current: Color32) -> Response {
+    let (rect, response) = ui.allocate_exact_size(Vec2::splat(SWATCH_SIZE), Sense::click());
+    let outline = if color == current {
+        Stroke::new(2.0, ui.visuals().selection.stroke.color)
+    } else {
+        Stroke::new(1.0, Color32::GRAY)
+    };
+
+    ui.painter().rect_filled(rect, 2.0, color);
+    ui.painter()
+        .rect_stroke(rect, 2.0, outline, egui::StrokeKind::Inside);
+    response
+}
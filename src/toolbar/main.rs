@@ -1,11 +1,84 @@
 use super::super::SimplePaintApp;
+use crate::draw::brush::{BrushKind, BrushPreset, StrokeCap};
 use crate::draw::canvas;
+use crate::draw::eraser::EraserMode;
+use crate::draw::fill;
+use crate::draw::pressure::PressureMode;
+use crate::draw::selection::SelectMode;
+use crate::draw::shapes::ShapeKind;
+use crate::draw::smoothing;
+use crate::draw::text::{self, TextAlign};
+use crate::keymap::Command;
+use crate::tools::select;
 use egui::{InnerResponse, Margin};
+use std::ops::RangeInclusive;
 
-#[derive(PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub const WIDTH_RANGE: RangeInclusive<f32> = 0.5..=50.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
 pub enum Tool {
+    Select,
     Pen,
     Erase,
+    Rectangle,
+    Ellipse,
+    Line,
+    Fill,
+    Text,
+    Eyedropper,
+}
+
+impl Tool {
+    pub const ALL: [Self; 9] = [
+        Self::Select,
+        Self::Pen,
+        Self::Erase,
+        Self::Rectangle,
+        Self::Ellipse,
+        Self::Line,
+        Self::Fill,
+        Self::Text,
+        Self::Eyedropper,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Select => "Select",
+            Self::Pen => "Pen",
+            Self::Erase => "Eraser",
+            Self::Rectangle => "Rectangle",
+            Self::Ellipse => "Ellipse",
+            Self::Line => "Line",
+            Self::Fill => "Fill",
+            Self::Text => "Text",
+            Self::Eyedropper => "Eyedropper",
+        }
+    }
+
+    pub fn default_key(&self) -> Option<egui::Key> {
+        match self {
+            Self::Select => Some(egui::Key::V),
+            Self::Pen => Some(egui::Key::P),
+            Self::Erase => Some(egui::Key::E),
+            Self::Rectangle => Some(egui::Key::R),
+            Self::Ellipse => Some(egui::Key::O),
+            Self::Line => Some(egui::Key::L),
+            Self::Fill => Some(egui::Key::G),
+            Self::Text => Some(egui::Key::T),
+            Self::Eyedropper => Some(egui::Key::I),
+        }
+    }
+
+    pub fn shape_kind(&self) -> Option<ShapeKind> {
+        match self {
+            Self::Rectangle => Some(ShapeKind::Rectangle),
+            Self::Ellipse => Some(ShapeKind::Ellipse),
+            Self::Line => Some(ShapeKind::Line),
+            Self::Select | Self::Pen | Self::Erase | Self::Fill | Self::Text | Self::Eyedropper => {
+                None
+            }
+        }
+    }
 }
 
 pub fn toolbar(app: &mut SimplePaintApp, ui: &mut egui::Ui) -> InnerResponse<()> {
@@ -16,13 +89,19 @@ pub fn toolbar(app: &mut SimplePaintApp, ui: &mut egui::Ui) -> InnerResponse<()>
             ui.add_space(30.0);
 
             // Undo
-            if ui.add(egui::Button::new("Undo")).clicked() {
-                app.history.undo(&mut app.canvas);
+            if ui
+                .add_enabled(app.can_undo(), egui::Button::new("Undo"))
+                .clicked()
+            {
+                app.undo();
             }
 
             // Redo
-            if ui.add(egui::Button::new("Redo")).clicked() {
-                app.history.redo(&mut app.canvas);
+            if ui
+                .add_enabled(app.can_redo(), egui::Button::new("Redo"))
+                .clicked()
+            {
+                app.redo();
             }
 
             // Color Palette
@@ -34,16 +113,23 @@ pub fn toolbar(app: &mut SimplePaintApp, ui: &mut egui::Ui) -> InnerResponse<()>
             // Pen Width
             egui::Frame::NONE.show(ui, |ui| {
                 ui.label("Width");
-                ui.add(egui::Slider::new(&mut app.stroke_type.width, 0.5..=50.0));
+                ui.add(egui::Slider::new(&mut app.stroke_type.width, WIDTH_RANGE));
             });
 
+            // Brush Presets
+            preset_picker(app, ui);
+            preset_menu(app, ui);
+
+            // Settings of the current tool
+            tool_options(app, ui);
+
             // Zoom
             egui::Frame::NONE
                 .inner_margin(Margin::symmetric(30, 0))
                 .show(ui, |ui| {
                     ui.label("Zoom");
                     let zoom = egui::DragValue::new(&mut app.canvas.zoom)
-                        .range(0.01..=10.0)
+                        .range(canvas::ZOOM_RANGE)
                         .speed(0.01)
                         .custom_formatter(|n, _| {
                             let n = n * 100.0;
@@ -59,6 +145,179 @@ pub fn toolbar(app: &mut SimplePaintApp, ui: &mut egui::Ui) -> InnerResponse<()>
         })
 }
 
+fn tool_options(app: &mut SimplePaintApp, ui: &mut egui::Ui) {
+    // Selection
+    if app.tool == Tool::Select {
+        egui::ComboBox::from_id_salt("select mode")
+            .selected_text(app.select_mode.name())
+            .show_ui(ui, |ui| {
+                for mode in SelectMode::ALL {
+                    ui.selectable_value(&mut app.select_mode, mode, mode.name());
+                }
+            });
+
+        let has_selection = !app.selection.is_empty();
+        if ui
+            .add_enabled(has_selection, egui::Button::new("Delete"))
+            .clicked()
+        {
+            select::delete_selection(app);
+        }
+        if ui
+            .add_enabled(has_selection, egui::Button::new("Duplicate"))
+            .clicked()
+        {
+            select::duplicate_selection(app);
+        }
+    }
+
+    // Pen width source
+    if app.tool == Tool::Pen {
+        egui::ComboBox::from_id_salt("pressure mode")
+            .selected_text(app.pressure_mode.name())
+            .show_ui(ui, |ui| {
+                for mode in PressureMode::ALL {
+                    ui.selectable_value(&mut app.pressure_mode, mode, mode.name());
+                }
+            });
+
+        egui::ComboBox::from_id_salt("brush kind")
+            .selected_text(app.brush_kind.name())
+            .show_ui(ui, |ui| {
+                for kind in BrushKind::ALL {
+                    ui.selectable_value(&mut app.brush_kind, kind, kind.name());
+                }
+            });
+        egui::ComboBox::from_id_salt("stroke cap")
+            .selected_text(app.stroke_cap.name())
+            .show_ui(ui, |ui| {
+                for cap in StrokeCap::ALL {
+                    ui.selectable_value(&mut app.stroke_cap, cap, cap.name());
+                }
+            });
+
+        ui.label("Stabilizer");
+        ui.add(egui::Slider::new(
+            &mut app.smoothing.stabilizer,
+            smoothing::STABILIZER_RANGE,
+        ));
+        ui.label("Smoothing");
+        ui.add(egui::Slider::new(
+            &mut app.smoothing.passes,
+            smoothing::PASSES_RANGE,
+        ));
+    }
+
+    // Eraser Mode
+    if app.tool == Tool::Erase {
+        egui::ComboBox::from_id_salt("eraser mode")
+            .selected_text(app.eraser_mode.name())
+            .show_ui(ui, |ui| {
+                for mode in EraserMode::ALL {
+                    ui.selectable_value(&mut app.eraser_mode, mode, mode.name());
+                }
+            });
+    }
+
+    // Largest gap the bucket closes
+    if app.tool == Tool::Fill {
+        ui.label("Gap");
+        ui.add(egui::Slider::new(&mut app.fill_gap, fill::GAP_RANGE));
+    }
+
+    // Text formatting, also applied to the text being edited
+    if app.tool == Tool::Text {
+        ui.label("Size");
+        ui.add(egui::Slider::new(&mut app.text_size, text::SIZE_RANGE));
+        egui::ComboBox::from_id_salt("text align")
+            .selected_text(app.text_align.name())
+            .show_ui(ui, |ui| {
+                for align in TextAlign::ALL {
+                    ui.selectable_value(&mut app.text_align, align, align.name());
+                }
+            });
+    }
+}
+
+// Index of the preset the pen is set up as, if any
+fn current_preset(app: &SimplePaintApp) -> Option<usize> {
+    app.brush_presets.iter().position(|preset| {
+        preset.matches(
+            app.stroke_type,
+            app.smoothing,
+            app.stroke_cap,
+            app.brush_kind,
+        )
+    })
+}
+
+fn preset_picker(app: &mut SimplePaintApp, ui: &mut egui::Ui) {
+    let current = current_preset(app);
+    let selected = current
+        .and_then(|index| app.brush_presets.get(index))
+        .map_or_else(|| "Custom".to_owned(), |preset| preset.name.clone());
+
+    egui::ComboBox::from_id_salt("brush preset")
+        .selected_text(selected)
+        .show_ui(ui, |ui| {
+            let names: Vec<String> = app
+                .brush_presets
+                .iter()
+                .map(|preset| preset.name.clone())
+                .collect();
+            for (index, name) in names.into_iter().enumerate() {
+                let mut response = ui.selectable_label(current == Some(index), name);
+                if let Some(shortcut) = app.keymap.shortcut(Command::ApplyPreset(index)) {
+                    response = response.on_hover_text(ui.ctx().format_shortcut(&shortcut));
+                }
+                if response.clicked() {
+                    app.apply_preset(index);
+                }
+            }
+        });
+}
+
+fn preset_menu(app: &mut SimplePaintApp, ui: &mut egui::Ui) {
+    ui.menu_button("Presets", |ui| {
+        match current_preset(app) {
+            Some(index) => {
+                if let Some(preset) = app.brush_presets.get_mut(index) {
+                    ui.text_edit_singleline(&mut preset.name);
+                }
+                if ui.button("Delete Preset").clicked() {
+                    app.brush_presets.remove(index);
+                }
+            }
+            None => {
+                if ui.button("Save Brush as Preset").clicked() {
+                    let name = format!("Brush {}", app.brush_presets.len() + 1);
+                    app.brush_presets.push(BrushPreset::from_brush(
+                        name,
+                        app.stroke_type,
+                        app.smoothing,
+                        app.stroke_cap,
+                        app.brush_kind,
+                    ));
+                }
+            }
+        }
+
+        ui.separator();
+        if ui.button("Import Pack…").clicked() {
+            app.import_presets_dialog();
+        }
+        if ui
+            .add_enabled(
+                !app.brush_presets.is_empty(),
+                egui::Button::new("Export Pack…"),
+            )
+            .clicked()
+        {
+            app.export_presets_dialog();
+        }
+    });
+}
+
 fn tool_frame(app: &mut SimplePaintApp, ui: &mut egui::Ui) {
     egui::Frame::NONE
         .stroke(egui::Stroke::new(
@@ -69,6 +328,12 @@ fn tool_frame(app: &mut SimplePaintApp, ui: &mut egui::Ui) {
         .outer_margin(Margin::symmetric(20, 0))
         .show(ui, |ui| {
             egui::Grid::new("tool grid").show(ui, |ui| {
+                // Select
+                ui.selectable_value(
+                    &mut app.tool,
+                    Tool::Select,
+                    egui::RichText::new("Select").text_style(egui::TextStyle::Monospace),
+                );
                 // Pen
                 ui.selectable_value(
                     &mut app.tool,
@@ -85,6 +350,38 @@ fn tool_frame(app: &mut SimplePaintApp, ui: &mut egui::Ui) {
                         // .size(14.0)
                         .text_style(egui::TextStyle::Monospace),
                 );
+                // Shapes
+                ui.selectable_value(
+                    &mut app.tool,
+                    Tool::Rectangle,
+                    egui::RichText::new("Rect").text_style(egui::TextStyle::Monospace),
+                );
+                ui.selectable_value(
+                    &mut app.tool,
+                    Tool::Ellipse,
+                    egui::RichText::new("Ellipse").text_style(egui::TextStyle::Monospace),
+                );
+                ui.selectable_value(
+                    &mut app.tool,
+                    Tool::Line,
+                    egui::RichText::new("Line").text_style(egui::TextStyle::Monospace),
+                );
+                // Bucket
+                ui.selectable_value(
+                    &mut app.tool,
+                    Tool::Fill,
+                    egui::RichText::new("Fill").text_style(egui::TextStyle::Monospace),
+                );
+                ui.selectable_value(
+                    &mut app.tool,
+                    Tool::Text,
+                    egui::RichText::new("Text").text_style(egui::TextStyle::Monospace),
+                );
+                ui.selectable_value(
+                    &mut app.tool,
+                    Tool::Eyedropper,
+                    egui::RichText::new("Picker").text_style(egui::TextStyle::Monospace),
+                );
             })
         });
 }
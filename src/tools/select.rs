@@ -0,0 +1,315 @@
+use super::super::SimplePaintApp;
+use crate::document::clipboard;
+use crate::draw::canvas::{Action, Layer, SingleStroke};
+use crate::draw::selection::{
+    self, Gesture, HANDLE_SIZE, Handle, SelectMode, Selection, Transform,
+};
+use crate::toolbar::main::Tool;
+use egui::{Color32, Pos2, Rect, Response, Stroke, Vec2};
+
+const SELECTION_COLOR: Color32 = Color32::from_rgb(30, 120, 230);
+// Offset of duplicated strokes from the originals
+const DUPLICATE_OFFSET: Vec2 = Vec2::splat(10.0);
+
+pub fn select_tool(app: &mut SimplePaintApp, response: &Response) {
+    if !app.canvas.active_layer_editable() {
+        return;
+    }
+
+    let layer = app.canvas.active_layer;
+    if app.selection.layer != layer {
+        app.selection = Selection {
+            layer,
+            strokes: Vec::new(),
+        };
+    }
+
+    let Some(pos) = response.interact_pointer_pos() else {
+        return;
+    };
+
+    if response.drag_started() {
+        start_gesture(app, pos);
+    }
+    if response.dragged() {
+        let constrain = response.ctx.input(|i| i.modifiers.shift);
+        update_gesture(app, pos, constrain);
+    }
+    if response.drag_stopped() {
+        finish_gesture(app);
+    }
+
+    // Clicking empty canvas drops the selection
+    if response.clicked() && selection_handle(app, pos).is_none() {
+        app.selection.strokes.clear();
+    }
+}
+
+// Strokes on a layer that has since been hidden or locked stay as they are
+pub fn selection_editable(app: &SimplePaintApp) -> bool {
+    app.canvas
+        .layers
+        .get(app.selection.layer)
+        .is_some_and(Layer::is_editable)
+}
+
+fn selection_bounds(app: &SimplePaintApp) -> Option<Rect> {
+    let strokes = app.canvas.strokes(app.selection.layer)?;
+    app.selection.bounds(strokes)
+}
+
+fn selection_handle(app: &SimplePaintApp, pos: Pos2) -> Option<(Handle, Rect)> {
+    let bounds = selection_bounds(app)?;
+    selection::handle_at(bounds, pos).map(|handle| (handle, bounds))
+}
+
+fn selected_strokes(app: &SimplePaintApp) -> Vec<SingleStroke> {
+    app.canvas
+        .strokes(app.selection.layer)
+        .map(|strokes| app.selection.selected(strokes).cloned().collect())
+        .unwrap_or_default()
+}
+
+fn start_gesture(app: &mut SimplePaintApp, pos: Pos2) {
+    app.select_gesture = Some(match selection_handle(app, pos) {
+        Some((handle, bounds)) => Gesture::Transform {
+            handle,
+            start: pos,
+            bounds,
+            original: selected_strokes(app),
+        },
+        None => match app.select_mode {
+            SelectMode::Rectangle => Gesture::Marquee {
+                start: pos,
+                current: pos,
+            },
+            SelectMode::Lasso => Gesture::Lasso { points: vec![pos] },
+        },
+    });
+}
+
+fn update_gesture(app: &mut SimplePaintApp, pos: Pos2, constrain: bool) {
+    match &mut app.select_gesture {
+        Some(Gesture::Marquee { current, .. }) => *current = pos,
+        Some(Gesture::Lasso { points }) => {
+            if points.last().is_none_or(|last| last.distance(pos) > 1.0) {
+                points.push(pos);
+            }
+        }
+        Some(Gesture::Transform {
+            handle,
+            start,
+            bounds,
+            original,
+        }) => {
+            let transform = match *handle {
+                Handle::Inside => Transform::translate(pos - *start),
+                Handle::Corner { anchor } => Transform::scale_around(
+                    anchor,
+                    selection::scale_between(anchor, *start, pos, constrain),
+                ),
+                Handle::Rotate => {
+                    let center = bounds.center();
+                    let mut angle = (pos - center).angle() - (*start - center).angle();
+                    if constrain {
+                        let step = 15_f32.to_radians();
+                        angle = (angle / step).round() * step;
+                    }
+                    Transform::rotate_around(center, angle)
+                }
+            };
+
+            // Preview directly on the canvas; the action is recorded once the drag ends
+            let moved: Vec<SingleStroke> = original
+                .iter()
+                .map(|stroke| transform.apply_to_stroke(stroke))
+                .collect();
+            app.canvas
+                .replace_strokes(app.selection.layer, &app.selection.strokes, &moved);
+        }
+        None => {}
+    }
+}
+
+pub fn finish_gesture(app: &mut SimplePaintApp) {
+    let layer = app.selection.layer;
+    let strokes = app.canvas.strokes(layer).cloned().unwrap_or_default();
+
+    match app.select_gesture.take() {
+        Some(Gesture::Marquee { start, current }) => {
+            let rect = Rect::from_two_pos(start, current);
+            let candidates = app.canvas.strokes_in(layer, rect);
+            app.selection.strokes = selection::pick_in_rect_among(&strokes, candidates, rect);
+        }
+        Some(Gesture::Lasso { points }) => {
+            let candidates = app.canvas.strokes_in(layer, Rect::from_points(&points));
+            app.selection.strokes = selection::pick_in_lasso_among(&strokes, candidates, &points);
+        }
+        Some(Gesture::Transform { original, .. }) => {
+            let after = selected_strokes(app);
+            if after != original {
+                app.run(Action::ModifyStrokes {
+                    before: original,
+                    after,
+                    indices: app.selection.strokes.clone(),
+                    layer,
+                });
+            }
+        }
+        None => {}
+    }
+}
+
+pub fn delete_selection(app: &mut SimplePaintApp) {
+    if !selection_editable(app) {
+        return;
+    }
+    let layer = app.selection.layer;
+    let Some(strokes) = app.canvas.strokes(layer) else {
+        return;
+    };
+
+    // Highest index first so the remaining indices stay valid
+    let actions: Vec<Action> = app
+        .selection
+        .strokes
+        .iter()
+        .rev()
+        .filter_map(|index| {
+            Some(Action::RemoveStroke {
+                stroke: strokes.get(*index)?.clone(),
+                index: *index,
+                layer,
+            })
+        })
+        .collect();
+
+    app.history.begin();
+    for action in actions {
+        app.run(action);
+    }
+    app.history.commit();
+
+    app.selection.strokes.clear();
+}
+
+pub fn duplicate_selection(app: &mut SimplePaintApp) {
+    if !selection_editable(app) {
+        return;
+    }
+    let layer = app.selection.layer;
+    let first_index = app.canvas.strokes(layer).map_or(0, Vec::len);
+    let copies: Vec<SingleStroke> = selected_strokes(app)
+        .iter()
+        .map(|stroke| Transform::translate(DUPLICATE_OFFSET).apply_to_stroke(stroke))
+        .collect();
+
+    app.history.begin();
+    for (offset, stroke) in copies.into_iter().enumerate() {
+        app.run(Action::AddStroke {
+            stroke,
+            index: first_index + offset,
+            layer,
+        });
+    }
+    app.history.commit();
+
+    // The copies become the new selection so they can be moved into place
+    let last_index = app.canvas.strokes(layer).map_or(0, Vec::len);
+    app.selection.strokes = (first_index..last_index).collect();
+}
+
+pub fn copy_selection(app: &SimplePaintApp, ctx: &egui::Context) {
+    let strokes = selected_strokes(app);
+    if !strokes.is_empty() {
+        ctx.copy_text(clipboard::strokes_to_clipboard(&strokes));
+    }
+}
+
+pub fn cut_selection(app: &mut SimplePaintApp, ctx: &egui::Context) {
+    if !selection_editable(app) {
+        return;
+    }
+    copy_selection(app, ctx);
+    delete_selection(app);
+}
+
+// Adds pasted strokes to the active layer around the last cursor position and selects them
+pub fn paste(app: &mut SimplePaintApp, text: &str) {
+    if !app.canvas.active_layer_editable() {
+        return;
+    }
+    let Some(strokes) = clipboard::strokes_from_clipboard(text, app.stroke_type) else {
+        return;
+    };
+
+    let area = app.canvas.canvas_area;
+    let cursor = app.canvas_pointer.unwrap_or_else(|| area.center());
+    let layer = app.canvas.active_layer;
+    let first_index = app.canvas.strokes(layer).map_or(0, Vec::len);
+
+    app.history.begin();
+    for (offset, stroke) in clipboard::place_at(&strokes, cursor, area)
+        .into_iter()
+        .enumerate()
+    {
+        app.run(Action::AddStroke {
+            stroke,
+            index: first_index + offset,
+            layer,
+        });
+    }
+    app.history.commit();
+
+    let last_index = app.canvas.strokes(layer).map_or(0, Vec::len);
+    app.tool = Tool::Select;
+    app.selection = Selection {
+        layer,
+        strokes: (first_index..last_index).collect(),
+    };
+}
+
+pub fn paint_selection(app: &SimplePaintApp, painter: &egui::Painter) {
+    let outline = Stroke::new(1.5, SELECTION_COLOR);
+
+    match &app.select_gesture {
+        Some(Gesture::Marquee { start, current }) => {
+            painter.rect(
+                Rect::from_two_pos(*start, *current),
+                0.0,
+                SELECTION_COLOR.gamma_multiply(0.1),
+                outline,
+                egui::StrokeKind::Middle,
+            );
+        }
+        Some(Gesture::Lasso { points }) => {
+            painter.add(egui::Shape::closed_line(points.clone(), outline));
+        }
+        _ => {}
+    }
+
+    let Some(bounds) = selection_bounds(app) else {
+        return;
+    };
+
+    painter.rect_stroke(bounds, 0.0, outline, egui::StrokeKind::Middle);
+
+    let rotate_handle = selection::rotate_handle(bounds);
+    painter.line_segment([bounds.center_top(), rotate_handle], outline);
+    painter.circle(rotate_handle, HANDLE_SIZE / 2.0, Color32::WHITE, outline);
+
+    for corner in [
+        bounds.left_top(),
+        bounds.right_top(),
+        bounds.left_bottom(),
+        bounds.right_bottom(),
+    ] {
+        painter.rect(
+            Rect::from_center_size(corner, Vec2::splat(HANDLE_SIZE)),
+            0.0,
+            Color32::WHITE,
+            outline,
+            egui::StrokeKind::Middle,
+        );
+    }
+}
@@ -0,0 +1,167 @@
+use super::super::SimplePaintApp;
+use crate::draw::text::{Editing, TextBox};
+use crate::toolbar::main::Tool;
+use egui::{Color32, Pos2, Rect, Response, Stroke, Vec2};
+
+const EDITING_COLOR: Color32 = Color32::from_rgb(30, 120, 230);
+const EDITOR_WIDTH: f32 = 240.0;
+// Space between the text on the canvas and the editor below it, in screen points
+const EDITOR_GAP: f32 = 8.0;
+
+// Pressing the canvas places a new text there, closing the one being edited
+pub fn text_tool(app: &mut SimplePaintApp, response: &Response) {
+    if !app.canvas.active_layer_editable() {
+        return;
+    }
+
+    // The canvas only senses drags, which start as soon as it is pressed
+    if response.drag_started()
+        && let Some(pos) = response.interact_pointer_pos()
+    {
+        finish_editing(app);
+        app.text_edit = Some(Editing {
+            layer: app.canvas.active_layer,
+            index: None,
+            text: TextBox::new(
+                String::new(),
+                pos,
+                app.text_size,
+                app.stroke_type.color,
+                app.text_align,
+            ),
+        });
+    }
+}
+
+// Opens the text under a double-click on the active layer for editing. Returns whether it did.
+pub fn edit_on_double_click(
+    app: &mut SimplePaintApp,
+    response: &Response,
+    painter: &egui::Painter,
+) -> bool {
+    let double_clicked = response.ctx.input(|i| {
+        i.pointer
+            .button_double_clicked(egui::PointerButton::Primary)
+    });
+    let Some(pos) = response.interact_pointer_pos().or(response.hover_pos()) else {
+        return false;
+    };
+    if !double_clicked
+        || !app.canvas.active_layer_editable()
+        || text_at(app, painter, pos).is_none()
+    {
+        return false;
+    }
+
+    // Closing the current edit can remove a text, so look again afterwards
+    finish_editing(app);
+    let layer = app.canvas.active_layer;
+    let Some((index, text)) = text_at(app, painter, pos)
+        .and_then(|index| Some((index, app.canvas.texts(layer)?.get(index)?.clone())))
+    else {
+        return false;
+    };
+
+    // The toolbar shows the formatting of the text being edited
+    app.text_size = text.size;
+    app.text_align = text.align;
+    app.stroke_type.color = text.color;
+    app.tool = Tool::Text;
+    app.text_edit = Some(Editing {
+        layer,
+        index: Some(index),
+        text,
+    });
+    true
+}
+
+// Topmost text on the active layer under `pos`
+fn text_at(app: &SimplePaintApp, painter: &egui::Painter, pos: Pos2) -> Option<usize> {
+    let texts = app.canvas.texts(app.canvas.active_layer)?;
+    texts
+        .iter()
+        .rposition(|text| text.painted_bounds(painter).contains(pos))
+}
+
+// Records the text being edited, if it changed
+pub fn finish_editing(app: &mut SimplePaintApp) {
+    let Some(editing) = app.text_edit.take() else {
+        return;
+    };
+    if !editing.text.is_blank() {
+        app.palettes.use_color(editing.text.color);
+    }
+    let texts = app.canvas.texts(editing.layer).cloned().unwrap_or_default();
+    if let Some(action) = editing.action(&texts) {
+        app.run(action);
+    }
+}
+
+// Previews the text being edited on the canvas, with a text field to type into below it
+pub fn text_editor(app: &mut SimplePaintApp, painter: &egui::Painter) {
+    let (size, align, color) = (app.text_size, app.text_align, app.stroke_type.color);
+    let Some(editing) = &mut app.text_edit else {
+        return;
+    };
+
+    // Formatting follows the toolbar while editing
+    editing.text.size = size;
+    editing.text.align = align;
+    editing.text.color = color;
+
+    let galley = painter.layout_job(editing.text.layout_job(color));
+    // Keep a caret-sized box around empty text so it's clear where it goes
+    let outline = editing.text.bounds(&galley).union(Rect::from_min_size(
+        editing.text.pos,
+        Vec2::new(1.0, editing.text.line_height()),
+    ));
+    painter.galley(editing.text.pos, galley, color);
+    painter.rect_stroke(
+        outline,
+        0.0,
+        Stroke::new(1.0, EDITING_COLOR),
+        egui::StrokeKind::Outside,
+    );
+
+    let ctx = painter.ctx();
+    let to_screen = ctx
+        .layer_transform_to_global(painter.layer_id())
+        .unwrap_or_default();
+    let mut done = false;
+    let mut cancelled = false;
+
+    egui::Area::new(egui::Id::new("text editor"))
+        .order(egui::Order::Foreground)
+        .fixed_pos(to_screen * outline.left_bottom() + Vec2::new(0.0, EDITOR_GAP))
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                let response = ui.add(
+                    egui::TextEdit::multiline(&mut editing.text.text)
+                        .desired_width(EDITOR_WIDTH)
+                        .desired_rows(3)
+                        .hint_text("Type here"),
+                );
+                // Typing goes to the text until it is closed
+                if ui.memory(|memory| memory.focused().is_none()) {
+                    response.request_focus();
+                }
+
+                ui.horizontal(|ui| {
+                    done = ui.button("Done").clicked();
+                    cancelled = ui.button("Cancel").clicked();
+                });
+            });
+        });
+
+    let (escape, commit) = ctx.input(|i| {
+        (
+            i.key_pressed(egui::Key::Escape),
+            i.modifiers.command && i.key_pressed(egui::Key::Enter),
+        )
+    });
+    if cancelled || escape {
+        app.text_edit = None;
+    } else if done || commit {
+        finish_editing(app);
+    }
+}
@@ -1,10 +1,11 @@
-use crate::draw::canvas::Segment;
 use egui::Pos2;
 
-pub fn cursor_to_segment_distance(cursor_pos: Pos2, segment: &Segment) -> f32 {
-    let [endpoint_a, endpoint_b] = segment.segment;
+pub fn cursor_to_segment_distance(cursor_pos: Pos2, [endpoint_a, endpoint_b]: [Pos2; 2]) -> f32 {
     let vector_ab = endpoint_b - endpoint_a;
     let vector_ac = cursor_pos - endpoint_a;
+    if vector_ab.length_sq() <= f32::EPSILON {
+        return cursor_pos.distance(endpoint_a);
+    }
 
     let t = (vector_ac.dot(vector_ab) / vector_ab.dot(vector_ab)).clamp(0.0, 1.0);
     let closest_point = endpoint_a + vector_ab * t;
@@ -0,0 +1,185 @@
+use eframe_template::SimplePaintApp;
+use eframe_template::document::clipboard;
+use eframe_template::document::file::{self, DocumentError};
+use eframe_template::draw::brush::{self, BrushKind, BrushPreset, PresetPack, StrokeCap};
+use eframe_template::draw::canvas::{Action, Canvas, SingleStroke};
+use eframe_template::draw::smoothing::Smoothing;
+use eframe_template::export::{raster, svg};
+use eframe_template::keymap::{Command, Keymap};
+use egui::{Color32, Key, Modifiers, Pos2, Stroke, Vec2};
+
+fn temp_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("simple_paint_{}_{name}", std::process::id()))
+}
+
+fn line(cap: StrokeCap) -> SingleStroke {
+    SingleStroke {
+        cap,
+        ..SingleStroke::new(
+            Stroke::new(10.0, Color32::BLACK),
+            vec![Pos2::new(20.0, 25.0), Pos2::new(80.0, 25.0)],
+        )
+    }
+}
+
+#[test]
+fn presets_capture_and_restore_the_brush() {
+    let mut app = SimplePaintApp::default();
+    let smoothing = Smoothing {
+        stabilizer: 12.0,
+        passes: 3,
+    };
+    let stroke = Stroke::new(14.0, Color32::from_rgba_unmultiplied(255, 0, 255, 128));
+    let preset = BrushPreset::from_brush(
+        "Wash",
+        stroke,
+        smoothing,
+        StrokeCap::Butt,
+        BrushKind::Highlighter,
+    );
+    assert_eq!(preset.color, Color32::from_rgb(255, 0, 255));
+    app.brush_presets.push(preset);
+
+    app.run_command(Command::ApplyPreset(app.brush_presets.len() - 1));
+    assert_eq!(app.stroke_type, stroke);
+    assert_eq!(app.smoothing, smoothing);
+    assert_eq!(app.stroke_cap, StrokeCap::Butt);
+    assert_eq!(app.brush_kind, BrushKind::Highlighter);
+
+    // Indices without a preset leave the brush alone
+    app.run_command(Command::ApplyPreset(40));
+    assert_eq!(app.stroke_type, stroke);
+}
+
+#[test]
+fn number_keys_select_the_first_nine_presets() {
+    let keymap = Keymap::default();
+    assert_eq!(
+        keymap.shortcut(Command::ApplyPreset(0)),
+        Some(egui::KeyboardShortcut::new(Modifiers::NONE, Key::Num1))
+    );
+    assert_eq!(
+        keymap.shortcut(Command::ApplyPreset(brush::SHORTCUT_SLOTS - 1)),
+        Some(egui::KeyboardShortcut::new(Modifiers::NONE, Key::Num9))
+    );
+    assert_eq!(
+        keymap.shortcut(Command::ApplyPreset(brush::SHORTCUT_SLOTS)),
+        None
+    );
+
+    // The default brush is one of the default presets
+    let app = SimplePaintApp::default();
+    assert!(app.brush_presets.iter().any(|preset| preset.matches(
+        app.stroke_type,
+        app.smoothing,
+        app.stroke_cap,
+        app.brush_kind
+    )));
+}
+
+#[test]
+fn shared_values_are_clamped_when_applied() {
+    let preset = BrushPreset {
+        name: "Broken".to_owned(),
+        width: 9000.0,
+        color: Color32::RED,
+        opacity: 3.0,
+        smoothing: Smoothing {
+            stabilizer: -5.0,
+            passes: 40,
+        },
+        cap: StrokeCap::Round,
+        kind: BrushKind::Pen,
+    };
+
+    let stroke = preset.stroke();
+    assert_eq!(stroke.width, 50.0);
+    assert_eq!(stroke.color, Color32::RED);
+    assert_eq!(
+        preset.smoothing(),
+        Smoothing {
+            stabilizer: 0.0,
+            passes: 3,
+        }
+    );
+}
+
+#[test]
+fn preset_packs_round_trip_and_reject_newer_versions() {
+    let path = temp_path("presets.json");
+    let presets = brush::default_presets();
+    assert!(
+        file::export_presets(&path, &presets).is_ok(),
+        "export failed"
+    );
+    assert_eq!(
+        file::import_presets(&path).expect("exported pack imports"),
+        presets
+    );
+
+    let mut newer = PresetPack::new(presets);
+    newer.version = brush::PACK_VERSION + 1;
+    let json = serde_json::to_string(&newer).expect("pack serializes");
+    assert!(std::fs::write(&path, json).is_ok(), "write failed");
+    let result = file::import_presets(&path);
+    assert!(
+        matches!(result, Err(DocumentError::VersionMismatch { .. })),
+        "{result:?}"
+    );
+
+    assert!(std::fs::write(&path, "{}").is_ok(), "write failed");
+    let result = file::import_presets(&path);
+    assert!(
+        matches!(result, Err(DocumentError::Presets(_))),
+        "{result:?}"
+    );
+}
+
+#[test]
+fn caps_reach_svg_and_clipboard() {
+    let mut canvas = Canvas::new(Vec2::new(100.0, 50.0));
+    if let Some(layer) = canvas.layers.first_mut() {
+        layer.strokes.push(line(StrokeCap::Square));
+    }
+    let svg = svg::canvas_to_svg(&canvas);
+    assert!(svg.contains(r#"stroke-linecap="square""#), "{svg}");
+
+    let pasted = clipboard::strokes_from_clipboard(
+        r##"<svg><path d="M 0 0 L 10 10" stroke="#000000" stroke-linecap="butt"/></svg>"##,
+        Stroke::new(1.0, Color32::BLACK),
+    )
+    .expect("path is pasted");
+    assert_eq!(
+        pasted.first().map(|stroke| stroke.cap),
+        Some(StrokeCap::Butt)
+    );
+
+    // Round is the default and is left out of saved documents
+    let json = serde_json::to_string(&line(StrokeCap::Round)).expect("stroke serializes");
+    assert!(!json.contains("cap"), "{json}");
+}
+
+#[test]
+fn caps_change_how_far_exported_strokes_reach() {
+    let ink_at = |cap: StrokeCap, x: u32| {
+        let mut canvas = Canvas::new(Vec2::new(100.0, 50.0));
+        Action::AddStroke {
+            stroke: line(cap),
+            index: 0,
+            layer: 0,
+        }
+        .execute(&mut canvas);
+        let pixmap = raster::rasterize(&canvas, 1.0);
+        // Near the top edge of the stroke, where a round end has already curved away
+        pixmap.pixel(x, 20).is_some_and(|pixel| pixel.r() < 128)
+    };
+
+    // Past the end point by most of the half width
+    assert!(!ink_at(StrokeCap::Butt, 83), "flat end drawn past the end");
+    assert!(!ink_at(StrokeCap::Round, 83), "round end drawn square");
+    assert!(ink_at(StrokeCap::Square, 83), "square end missing");
+    // Just inside the end point every cap is drawn
+    for cap in StrokeCap::ALL {
+        assert!(ink_at(cap, 78), "{cap:?} end cut short");
+    }
+}
@@ -0,0 +1,130 @@
+use eframe_template::document::clipboard::{
+    place_at, strokes_from_clipboard, strokes_to_clipboard,
+};
+use eframe_template::draw::canvas::SingleStroke;
+use egui::{Color32, Pos2, Rect, Stroke};
+
+fn default_stroke() -> Stroke {
+    Stroke::new(3.0, Color32::BLACK)
+}
+
+fn stroke() -> SingleStroke {
+    SingleStroke::new(
+        Stroke::new(4.0, Color32::from_rgb(200, 10, 20)),
+        vec![
+            Pos2::new(10.0, 10.0),
+            Pos2::new(20.0, 10.0),
+            Pos2::new(20.0, 30.0),
+        ],
+    )
+}
+
+#[test]
+fn copied_strokes_paste_back_unchanged() {
+    let mut tapered = stroke();
+    tapered.widths = vec![1.0, 2.0, 3.0];
+    let text = strokes_to_clipboard(&[stroke(), tapered.clone()]);
+
+    assert!(text.starts_with("<svg"));
+    assert!(text.contains("<path"));
+    assert_eq!(
+        strokes_from_clipboard(&text, default_stroke()),
+        Some(vec![stroke(), tapered])
+    );
+}
+
+#[test]
+fn svg_paths_are_used_without_app_metadata() {
+    let text = r##"<svg xmlns="http://www.w3.org/2000/svg">
+  <path fill="none" stroke="#00ff00" stroke-width="5" d="M0 0 L10 0 L10 10"/>
+  <path d="M5 5 h10 M0 20 v10"/>
+</svg>"##;
+
+    let strokes = strokes_from_clipboard(text, default_stroke()).unwrap_or_default();
+    let points: Vec<Vec<Pos2>> = strokes.iter().map(|stroke| stroke.points.clone()).collect();
+
+    // Every subpath becomes its own stroke
+    assert_eq!(
+        points,
+        vec![
+            vec![
+                Pos2::new(0.0, 0.0),
+                Pos2::new(10.0, 0.0),
+                Pos2::new(10.0, 10.0)
+            ],
+            vec![Pos2::new(5.0, 5.0), Pos2::new(15.0, 5.0)],
+            vec![Pos2::new(0.0, 20.0), Pos2::new(0.0, 30.0)],
+        ]
+    );
+    assert_eq!(
+        strokes.first().map(|stroke| stroke.stroke),
+        Some(Stroke::new(5.0, Color32::from_rgb(0, 255, 0)))
+    );
+    assert_eq!(
+        strokes.get(1).map(|stroke| stroke.stroke),
+        Some(default_stroke())
+    );
+}
+
+#[test]
+fn bare_path_data_is_accepted() {
+    let strokes = strokes_from_clipboard("m10,10 20-5 v5 z", default_stroke()).unwrap_or_default();
+
+    assert_eq!(
+        strokes.first().map(|stroke| stroke.points.clone()),
+        Some(vec![
+            Pos2::new(10.0, 10.0),
+            Pos2::new(30.0, 5.0),
+            Pos2::new(30.0, 10.0),
+            Pos2::new(10.0, 10.0),
+        ])
+    );
+}
+
+#[test]
+fn curves_are_flattened_through_their_end_points() {
+    let strokes = strokes_from_clipboard("M0 0 C0 10 10 10 10 0 Q20 -10 30 0", default_stroke())
+        .unwrap_or_default();
+    let points = strokes
+        .first()
+        .map(|stroke| stroke.points.clone())
+        .unwrap_or_default();
+
+    assert!(points.len() > 3);
+    assert!(points.contains(&Pos2::new(10.0, 0.0)));
+    assert_eq!(points.last(), Some(&Pos2::new(30.0, 0.0)));
+}
+
+#[test]
+fn unrelated_text_is_rejected() {
+    assert_eq!(
+        strokes_from_clipboard("hello world", default_stroke()),
+        None
+    );
+    assert_eq!(strokes_from_clipboard("<p>hi</p>", default_stroke()), None);
+    assert_eq!(strokes_from_clipboard("{\"a\": 1}", default_stroke()), None);
+}
+
+#[test]
+fn pasted_strokes_are_centered_on_cursor_inside_area() {
+    let area = Rect::from_min_max(Pos2::ZERO, Pos2::new(100.0, 100.0));
+
+    let centered = place_at(&[stroke()], Pos2::new(50.0, 50.0), area);
+    assert_eq!(
+        centered
+            .first()
+            .and_then(|stroke| stroke.points.first())
+            .copied(),
+        Some(Pos2::new(45.0, 40.0))
+    );
+
+    // Near the corner the strokes are pushed back inside the canvas
+    let cornered = place_at(&[stroke()], Pos2::new(200.0, 100.0), area);
+    assert_eq!(
+        cornered
+            .first()
+            .and_then(|stroke| stroke.points.last())
+            .copied(),
+        Some(Pos2::new(100.0, 100.0))
+    );
+}
@@ -0,0 +1,55 @@
+use eframe_template::document::file::{self, DocumentError};
+use eframe_template::draw::canvas::Canvas;
+use std::path::PathBuf;
+
+fn temp_file(name: &str, contents: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("simple_paint_{}_{name}", std::process::id()));
+    std::fs::write(&path, contents).unwrap_or_default();
+    path
+}
+
+#[test]
+fn missing_file_is_io_error() {
+    let result = file::open(&std::env::temp_dir().join("simple_paint_does_not_exist.json"));
+
+    assert!(matches!(result, Err(DocumentError::Io(_))), "{result:?}");
+}
+
+#[test]
+fn garbage_is_parse_error() {
+    let path = temp_file("garbage.json", "this is not json");
+    let result = file::open(&path);
+
+    assert!(matches!(result, Err(DocumentError::Parse(_))), "{result:?}");
+}
+
+#[test]
+fn newer_version_is_rejected() {
+    let path = temp_file("future.json", r#"{ "version": 999 }"#);
+    let result = file::open(&path);
+
+    assert!(
+        matches!(
+            result,
+            Err(DocumentError::VersionMismatch { found: 999, .. })
+        ),
+        "{result:?}"
+    );
+}
+
+#[test]
+fn save_then_open_round_trips() {
+    let path = std::env::temp_dir().join(format!(
+        "simple_paint_{}_round_trip.json",
+        std::process::id()
+    ));
+    let canvas = Canvas::new(egui::Vec2::new(320.0, 240.0));
+
+    assert!(file::save(&path, &canvas).is_ok(), "save failed");
+    let opened = file::open(&path);
+
+    assert!(
+        opened.is_ok_and(|opened| opened.canvas_area == canvas.canvas_area),
+        "open failed"
+    );
+}
@@ -0,0 +1,193 @@
+use eframe_template::document::file::{self, DocumentError};
+use eframe_template::document::format::{DOCUMENT_TYPE, Document, FORMAT_VERSION};
+use eframe_template::draw::brush::{BrushKind, StrokeCap};
+use eframe_template::draw::canvas::{Canvas, SingleStroke};
+use eframe_template::draw::text::TextAlign;
+use egui::{Color32, Pos2};
+use std::path::Path;
+
+fn open_fixture(name: &str) -> Canvas {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name);
+
+    match file::open(&path) {
+        Ok(canvas) => canvas,
+        Err(err) => panic!("could not open {name}: {err}"),
+    }
+}
+
+#[test]
+fn opens_v0_flat_strokes() {
+    let canvas = open_fixture("v0_flat_strokes.json");
+
+    assert_eq!(canvas.canvas_area.size(), egui::vec2(400.0, 300.0));
+    assert_eq!(canvas.layers.len(), 1);
+    assert_eq!(canvas.strokes(0).map(Vec::len), Some(2));
+}
+
+#[test]
+fn opens_v0_layers() {
+    let canvas = open_fixture("v0_layers.json");
+
+    assert_eq!(canvas.layers.len(), 2);
+    assert_eq!(canvas.active_layer, 1);
+    assert!(canvas.layers.first().is_some_and(|layer| layer.locked));
+    assert!(
+        canvas
+            .layers
+            .get(1)
+            .is_some_and(|layer| !layer.visible && layer.shapes.len() == 1)
+    );
+}
+
+#[test]
+fn opens_v1() {
+    let canvas = open_fixture("v1.json");
+
+    assert_eq!(canvas.layers.len(), 1);
+    assert!(
+        canvas
+            .active_layer()
+            .is_some_and(|layer| layer.strokes.len() == 1 && layer.shapes.len() == 1)
+    );
+}
+
+#[test]
+fn opens_v2_widths() {
+    let canvas = open_fixture("v2.json");
+    let tapered: Vec<bool> = canvas
+        .strokes(0)
+        .map(|strokes| strokes.iter().map(SingleStroke::is_tapered).collect())
+        .unwrap_or_default();
+
+    assert_eq!(tapered, vec![true, false]);
+}
+
+#[test]
+fn v2_segments_become_polylines() {
+    let canvas = open_fixture("v2_gaps.json");
+    let strokes = canvas.strokes(0).cloned().unwrap_or_default();
+    let points: Vec<Vec<Pos2>> = strokes.iter().map(|stroke| stroke.points.clone()).collect();
+
+    // The gap in the first stroke splits it in two
+    assert_eq!(
+        points,
+        vec![
+            vec![
+                Pos2::new(10.0, 10.0),
+                Pos2::new(20.0, 10.0),
+                Pos2::new(30.0, 10.0)
+            ],
+            vec![Pos2::new(50.0, 10.0), Pos2::new(60.0, 10.0)],
+            vec![
+                Pos2::new(10.0, 50.0),
+                Pos2::new(20.0, 50.0),
+                Pos2::new(30.0, 50.0)
+            ],
+        ]
+    );
+    assert!(strokes.iter().take(2).all(|stroke| !stroke.is_tapered()));
+    assert_eq!(
+        strokes.get(2).map(|stroke| stroke.widths.clone()),
+        Some(vec![2.0, 4.0, 6.0])
+    );
+}
+
+#[test]
+fn opens_v3() {
+    let canvas = open_fixture("v3.json");
+
+    assert_eq!(canvas.strokes(0).map(Vec::len), Some(1));
+    assert_eq!(canvas.fills(0).map(Vec::len), Some(0));
+}
+
+#[test]
+fn opens_v4_fills() {
+    let canvas = open_fixture("v4.json");
+    let fills = canvas.fills(0).cloned().unwrap_or_default();
+
+    assert_eq!(fills.len(), 1);
+    assert!(
+        fills
+            .first()
+            .is_some_and(|fill| fill.color == Color32::RED && fill.contains(Pos2::new(30.0, 30.0)))
+    );
+}
+
+#[test]
+fn opens_v5_texts() {
+    let canvas = open_fixture("v5.json");
+    let texts = canvas.texts(0).cloned().unwrap_or_default();
+
+    assert_eq!(texts.len(), 1);
+    assert!(texts.first().is_some_and(|text| text.text == "Hello\nthere"
+        && text.align == TextAlign::Center
+        && text.color == Color32::BLUE));
+    // Older versions gain an empty list
+    assert_eq!(open_fixture("v4.json").texts(0).map(Vec::len), Some(0));
+}
+
+#[test]
+fn opens_v6_caps() {
+    let canvas = open_fixture("v6.json");
+    let caps: Vec<StrokeCap> = canvas
+        .strokes(0)
+        .map(|strokes| strokes.iter().map(|stroke| stroke.cap).collect())
+        .unwrap_or_default();
+
+    assert_eq!(caps, vec![StrokeCap::Square, StrokeCap::Round]);
+    let older = open_fixture("v3.json");
+    assert!(
+        older
+            .strokes(0)
+            .is_some_and(|strokes| strokes.iter().all(|stroke| stroke.cap == StrokeCap::Round))
+    );
+}
+
+#[test]
+fn opens_v7_highlighters() {
+    let canvas = open_fixture("v7.json");
+    let kinds: Vec<BrushKind> = canvas
+        .strokes(0)
+        .map(|strokes| strokes.iter().map(|stroke| stroke.kind).collect())
+        .unwrap_or_default();
+
+    assert_eq!(kinds, vec![BrushKind::Pen, BrushKind::Highlighter]);
+    let older = open_fixture("v6.json");
+    assert!(
+        older
+            .strokes(0)
+            .is_some_and(|strokes| strokes.iter().all(|stroke| stroke.kind == BrushKind::Pen))
+    );
+}
+
+#[test]
+fn saves_current_version_without_transient_state() {
+    let canvas = open_fixture("v0_flat_strokes.json");
+    let value = serde_json::to_value(Document::from_canvas(&canvas)).unwrap_or_default();
+
+    assert_eq!(value.get("type"), Some(&DOCUMENT_TYPE.into()));
+    assert_eq!(value.get("version"), Some(&FORMAT_VERSION.into()));
+    for transient in [
+        "current_stroke",
+        "last_cursor_pos",
+        "canvas_viewport",
+        "zoom",
+    ] {
+        assert!(value.get(transient).is_none(), "{transient} was saved");
+    }
+}
+
+#[test]
+fn rejects_other_document_types() {
+    let result = file::from_value(serde_json::json!({
+        "type": "something_else",
+        "version": 1,
+    }));
+
+    assert!(
+        matches!(result, Err(DocumentError::UnknownType(_))),
+        "{result:?}"
+    );
+}
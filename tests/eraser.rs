@@ -0,0 +1,139 @@
+use eframe_template::draw::brush::{BrushKind, StrokeCap};
+use eframe_template::draw::canvas::{Action, Canvas, SingleStroke};
+use eframe_template::draw::eraser::{EraserMode, erase_actions};
+use egui::{Color32, Pos2, Stroke, Vec2};
+
+// A horizontal stroke from x = 0 to x = 100 made of ten segments
+fn line_stroke() -> SingleStroke {
+    SingleStroke::new(
+        Stroke::new(2.0, Color32::BLACK),
+        (0..=10).map(|i| Pos2::new(i as f32 * 10.0, 50.0)).collect(),
+    )
+}
+
+fn xs(strokes: &[SingleStroke]) -> Vec<Vec<f32>> {
+    strokes
+        .iter()
+        .map(|stroke| stroke.points.iter().map(|point| point.x).collect())
+        .collect()
+}
+
+fn erase(mode: EraserMode, at: Pos2) -> Vec<SingleStroke> {
+    let mut canvas = Canvas::new(Vec2::new(200.0, 100.0));
+    for (index, stroke) in [line_stroke(), line_stroke()].into_iter().enumerate() {
+        Action::AddStroke {
+            stroke,
+            index,
+            layer: 0,
+        }
+        .execute(&mut canvas);
+    }
+
+    let strokes = canvas.strokes(0).cloned().unwrap_or_default();
+    for action in erase_actions(mode, &strokes, 0, at, 3.0) {
+        action.execute(&mut canvas);
+    }
+
+    canvas.strokes(0).cloned().unwrap_or_default()
+}
+
+#[test]
+fn stroke_mode_removes_touched_strokes() {
+    assert!(erase(EraserMode::Stroke, Pos2::new(55.0, 50.0)).is_empty());
+}
+
+#[test]
+fn partial_mode_removes_touched_segments() {
+    let strokes = erase(EraserMode::Partial, Pos2::new(55.0, 50.0));
+    let first_half = vec![0.0, 10.0, 20.0, 30.0, 40.0, 50.0];
+    let second_half = vec![60.0, 70.0, 80.0, 90.0, 100.0];
+
+    assert_eq!(
+        xs(&strokes),
+        vec![
+            first_half.clone(),
+            second_half.clone(),
+            first_half,
+            second_half
+        ]
+    );
+}
+
+#[test]
+fn split_mode_cuts_along_the_eraser_edge() {
+    let strokes = erase(EraserMode::Split, Pos2::new(55.0, 50.0));
+    let first_half = vec![0.0, 10.0, 20.0, 30.0, 40.0, 50.0, 52.0];
+    let second_half = vec![58.0, 60.0, 70.0, 80.0, 90.0, 100.0];
+
+    assert_eq!(
+        xs(&strokes),
+        vec![
+            first_half.clone(),
+            second_half.clone(),
+            first_half,
+            second_half
+        ]
+    );
+}
+
+#[test]
+fn split_mode_at_the_end_shortens_the_stroke() {
+    let strokes = erase(EraserMode::Split, Pos2::new(100.0, 50.0));
+    let shortened: Vec<f32> = (0..10).map(|i| i as f32 * 10.0).chain([97.0]).collect();
+
+    assert_eq!(xs(&strokes), vec![shortened.clone(), shortened]);
+}
+
+#[test]
+fn split_mode_keeps_interpolated_widths() {
+    let mut stroke = SingleStroke::new(Stroke::new(2.0, Color32::BLACK), Vec::new());
+    stroke.push(Pos2::new(0.0, 0.0), Some(2.0));
+    stroke.push(Pos2::new(10.0, 0.0), Some(12.0));
+
+    let actions = erase_actions(EraserMode::Split, &[stroke], 0, Pos2::new(10.0, 0.0), 5.0);
+    let after = actions.iter().find_map(|action| match action {
+        Action::ModifyStroke { after, .. } => after.clone(),
+        _ => None,
+    });
+
+    assert_eq!(after.map(|stroke| stroke.widths), Some(vec![2.0, 7.0]));
+}
+
+#[test]
+fn pieces_keep_the_brush_of_the_stroke() {
+    let highlight = SingleStroke {
+        cap: StrokeCap::Square,
+        kind: BrushKind::Highlighter,
+        ..line_stroke()
+    };
+
+    for mode in [EraserMode::Partial, EraserMode::Split] {
+        let actions = erase_actions(mode, &[highlight.clone()], 0, Pos2::new(55.0, 50.0), 3.0);
+        let pieces: Vec<SingleStroke> = actions
+            .iter()
+            .filter_map(|action| match action {
+                Action::ModifyStroke { after, .. } => after.clone(),
+                Action::AddStroke { stroke, .. } => Some(stroke.clone()),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(pieces.len(), 2, "{mode:?}");
+        for piece in pieces {
+            assert_eq!(
+                (piece.cap, piece.kind),
+                (StrokeCap::Square, BrushKind::Highlighter),
+                "{mode:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn missing_the_stroke_does_nothing() {
+    let strokes = line_stroke();
+
+    for mode in EraserMode::ALL {
+        assert!(erase_actions(mode, &[strokes.clone()], 0, Pos2::new(50.0, 90.0), 3.0).is_empty());
+    }
+}
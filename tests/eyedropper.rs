@@ -0,0 +1,146 @@
+use eframe_template::SimplePaintApp;
+use eframe_template::draw::canvas::{Action, Canvas, Layer, SingleStroke};
+use eframe_template::draw::eyedropper::{self, BACKGROUND};
+use eframe_template::draw::fill::Fill;
+use eframe_template::draw::shapes::{Shape, ShapeKind};
+use eframe_template::draw::text::{TextAlign, TextBox};
+use egui::{Color32, Pos2, Rect, Stroke, Vec2};
+
+fn line(y: f32, width: f32, color: Color32) -> SingleStroke {
+    SingleStroke::new(
+        Stroke::new(width, color),
+        vec![Pos2::new(0.0, y), Pos2::new(200.0, y)],
+    )
+}
+
+fn canvas_with(layers: Vec<Layer>) -> Canvas {
+    let mut canvas = Canvas::new(Vec2::new(200.0, 200.0));
+    canvas.layers = layers;
+    canvas.rebuild_caches();
+    canvas
+}
+
+// For layers without texts, which need laying out
+fn color_at(canvas: &Canvas, pos: Pos2) -> Color32 {
+    eyedropper::color_at(canvas, pos, |_| Rect::NOTHING)
+}
+
+// Runs `paint` inside a frame, where egui can lay out text
+fn with_painter(mut paint: impl FnMut(&egui::Painter)) {
+    let ctx = egui::Context::default();
+    let _output = ctx.run(egui::RawInput::default(), |ctx| {
+        paint(&ctx.layer_painter(egui::LayerId::background()));
+    });
+}
+
+fn layer(strokes: Vec<SingleStroke>) -> Layer {
+    let mut layer = Layer::new("Layer");
+    layer.strokes = strokes;
+    layer
+}
+
+#[test]
+fn picks_the_topmost_stroke_or_the_background() {
+    let canvas = canvas_with(vec![
+        layer(vec![line(100.0, 20.0, Color32::RED)]),
+        layer(vec![
+            line(100.0, 4.0, Color32::GREEN),
+            line(104.0, 4.0, Color32::BLUE),
+        ]),
+    ]);
+
+    assert_eq!(color_at(&canvas, Pos2::new(50.0, 104.0)), Color32::BLUE);
+    assert_eq!(color_at(&canvas, Pos2::new(50.0, 100.5)), Color32::GREEN);
+    // Only the wide stroke on the lower layer reaches this far
+    assert_eq!(color_at(&canvas, Pos2::new(50.0, 93.0)), Color32::RED);
+    assert_eq!(color_at(&canvas, Pos2::new(50.0, 20.0)), BACKGROUND);
+}
+
+#[test]
+fn hidden_layers_are_skipped() {
+    let mut top = layer(vec![line(100.0, 10.0, Color32::GREEN)]);
+    top.visible = false;
+    let canvas = canvas_with(vec![layer(vec![line(100.0, 10.0, Color32::RED)]), top]);
+
+    assert_eq!(color_at(&canvas, Pos2::new(50.0, 100.0)), Color32::RED);
+}
+
+#[test]
+fn picks_pressure_strokes_shapes_and_fills() {
+    let mut tapered = line(50.0, 2.0, Color32::RED);
+    tapered.widths = vec![2.0, 30.0];
+    let mut layer = layer(vec![tapered]);
+    layer.shapes.push(Shape::new(
+        ShapeKind::Rectangle,
+        Stroke::new(6.0, Color32::GREEN),
+        Pos2::new(100.0, 100.0),
+        Pos2::new(180.0, 180.0),
+    ));
+    layer.fills.push(Fill::new(
+        Color32::BLUE,
+        vec![vec![
+            Pos2::new(100.0, 100.0),
+            Pos2::new(180.0, 100.0),
+            Pos2::new(180.0, 180.0),
+            Pos2::new(100.0, 180.0),
+        ]],
+    ));
+    let canvas = canvas_with(vec![layer]);
+
+    // Wider toward the end of the stroke
+    assert_eq!(color_at(&canvas, Pos2::new(190.0, 62.0)), Color32::RED);
+    assert_eq!(color_at(&canvas, Pos2::new(10.0, 62.0)), BACKGROUND);
+    // The outline sits above the fill inside it
+    assert_eq!(color_at(&canvas, Pos2::new(102.0, 140.0)), Color32::GREEN);
+    assert_eq!(color_at(&canvas, Pos2::new(140.0, 140.0)), Color32::BLUE);
+}
+
+#[test]
+fn picked_color_does_not_depend_on_zoom() {
+    let mut app = SimplePaintApp::default();
+    app.canvas = Canvas::new(Vec2::new(200.0, 200.0));
+    app.run(Action::AddStroke {
+        stroke: line(100.0, 1.0, Color32::GOLD),
+        index: 0,
+        layer: 0,
+    });
+
+    with_painter(|painter| {
+        for zoom in [0.05, 1.0, 8.0] {
+            app.canvas.set_zoom(zoom);
+            app.stroke_type.color = Color32::BLACK;
+            app.pick_color(Pos2::new(75.0, 100.4), painter);
+            assert_eq!(app.stroke_type.color, Color32::GOLD, "at zoom {zoom}");
+        }
+    });
+}
+
+#[test]
+fn picks_strokes_scaled_past_the_widest_pen() {
+    let canvas = canvas_with(vec![layer(vec![line(100.0, 160.0, Color32::RED)])]);
+
+    // Further from the centreline than any pen width reaches
+    assert_eq!(color_at(&canvas, Pos2::new(50.0, 170.0)), Color32::RED);
+    assert_eq!(color_at(&canvas, Pos2::new(50.0, 185.0)), BACKGROUND);
+}
+
+#[test]
+fn texts_are_picked_above_everything_else() {
+    let mut layer = layer(vec![line(100.0, 40.0, Color32::RED)]);
+    layer.texts.push(TextBox::new(
+        "Label",
+        Pos2::new(20.0, 90.0),
+        20.0,
+        Color32::BLUE,
+        TextAlign::Left,
+    ));
+    let canvas = canvas_with(vec![layer]);
+
+    with_painter(|painter| {
+        let color_at =
+            |pos| eyedropper::color_at(&canvas, pos, |text| text.painted_bounds(painter));
+        assert_eq!(color_at(Pos2::new(30.0, 100.0)), Color32::BLUE);
+        // Beside the text the stroke beneath shows
+        assert_eq!(color_at(Pos2::new(150.0, 100.0)), Color32::RED);
+    });
+}
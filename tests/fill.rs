@@ -0,0 +1,147 @@
+use eframe_template::SimplePaintApp;
+use eframe_template::draw::canvas::{Action, Canvas, Layer, SingleStroke};
+use eframe_template::draw::fill::{self, Fill};
+use eframe_template::export::{raster, svg};
+use egui::{Color32, Pos2, Rect, Stroke};
+
+const AREA: Rect = Rect::from_min_max(Pos2::ZERO, Pos2::new(400.0, 400.0));
+
+// Outline of the square from `min` to `max`, leaving `gap` open at the top left corner
+fn square(min: f32, max: f32, gap: f32) -> SingleStroke {
+    SingleStroke::new(
+        Stroke::new(4.0, Color32::BLACK),
+        vec![
+            Pos2::new(min + gap, min),
+            Pos2::new(max, min),
+            Pos2::new(max, max),
+            Pos2::new(min, max),
+            Pos2::new(min, min),
+        ],
+    )
+}
+
+fn layer(strokes: Vec<SingleStroke>) -> Layer {
+    let mut layer = Layer::new("Layer 1");
+    layer.strokes = strokes;
+    layer
+}
+
+fn region(strokes: Vec<SingleStroke>, seed: Pos2, gap: f32) -> Option<Fill> {
+    fill::find_region(AREA, &fill::walls(&layer(strokes)), seed, gap)
+        .map(|contours| Fill::new(Color32::RED, contours))
+}
+
+#[test]
+fn fills_the_inside_of_a_closed_outline() {
+    let fill = region(
+        vec![square(100.0, 300.0, 0.0)],
+        Pos2::new(200.0, 200.0),
+        0.0,
+    )
+    .expect("square should be fillable");
+
+    assert!(fill.contains(Pos2::new(200.0, 200.0)));
+    assert!(!fill.contains(Pos2::new(50.0, 50.0)));
+
+    // The fill reaches under the outline, up to about its middle
+    let bounds = fill.bounds();
+    assert!((bounds.min.x - 100.0).abs() < 1.5, "{bounds:?}");
+    assert!((bounds.max.y - 300.0).abs() < 1.5, "{bounds:?}");
+}
+
+#[test]
+fn gaps_within_tolerance_are_bridged() {
+    let seed = Pos2::new(200.0, 200.0);
+    let outside = Pos2::new(50.0, 50.0);
+
+    let leaky = region(vec![square(100.0, 300.0, 8.0)], seed, 2.0).expect("leaks to the edge");
+    assert!(
+        leaky.contains(outside),
+        "gap wider than tolerance was closed"
+    );
+
+    let bridged = region(vec![square(100.0, 300.0, 8.0)], seed, 12.0).expect("gap is bridged");
+    assert!(bridged.contains(seed));
+    assert!(!bridged.contains(outside), "fill leaked through the gap");
+}
+
+#[test]
+fn enclosed_outlines_become_holes() {
+    let strokes = vec![square(100.0, 300.0, 0.0), square(180.0, 220.0, 0.0)];
+    let fill = region(strokes, Pos2::new(130.0, 130.0), 0.0).expect("ring should be fillable");
+
+    assert!(fill.contains(Pos2::new(130.0, 130.0)));
+    assert!(!fill.contains(Pos2::new(200.0, 200.0)), "hole was filled");
+
+    // The triangles cover the ring but not the hole
+    let covered = |point: Pos2| {
+        fill.triangles()
+            .chunks_exact(3)
+            .any(|triangle| eframe_template::draw::selection::point_in_polygon(point, triangle))
+    };
+    assert!(covered(Pos2::new(130.0, 130.0)));
+    assert!(!covered(Pos2::new(200.0, 200.0)));
+}
+
+#[test]
+fn clicking_a_line_fills_nothing() {
+    assert!(
+        region(
+            vec![square(100.0, 300.0, 0.0)],
+            Pos2::new(100.0, 200.0),
+            0.0
+        )
+        .is_none()
+    );
+    assert!(region(Vec::new(), Pos2::new(-10.0, 10.0), 0.0).is_none());
+}
+
+#[test]
+fn fill_is_one_undo_step() {
+    let mut app = SimplePaintApp::default();
+    app.canvas = Canvas::new(AREA.size());
+    app.stroke_type.color = Color32::BLUE;
+    app.run(Action::AddStroke {
+        stroke: square(100.0, 300.0, 0.0),
+        index: 0,
+        layer: 0,
+    });
+
+    app.fill_at(Pos2::new(200.0, 200.0));
+    assert_eq!(app.canvas.fills(0).map(Vec::len), Some(1));
+    assert_eq!(
+        app.canvas
+            .fills(0)
+            .and_then(|fills| fills.first())
+            .map(|fill| fill.color),
+        Some(Color32::BLUE)
+    );
+
+    app.undo();
+    assert_eq!(app.canvas.fills(0).map(Vec::len), Some(0));
+    app.redo();
+    assert_eq!(app.canvas.fills(0).map(Vec::len), Some(1));
+}
+
+#[test]
+fn fills_are_exported_beneath_strokes() {
+    let mut canvas = Canvas::new(AREA.size());
+    let stroke = square(100.0, 300.0, 0.0);
+    let fill = region(vec![stroke.clone()], Pos2::new(200.0, 200.0), 0.0).expect("fillable");
+    if let Some(layer) = canvas.layers.first_mut() {
+        layer.strokes.push(stroke);
+        layer.fills.push(fill);
+    }
+
+    let svg = svg::canvas_to_svg(&canvas);
+    let fill_at = svg
+        .find(r#"fill-rule="evenodd""#)
+        .expect("fill missing from SVG");
+    let stroke_at = svg.find(r#"fill="none""#).expect("stroke missing from SVG");
+    assert!(fill_at < stroke_at, "fill should come before its outline");
+
+    let pixmap = raster::rasterize(&canvas, 1.0);
+    assert_eq!(pixmap.pixel(200, 200), Some(Color32::RED));
+    assert_eq!(pixmap.pixel(50, 50), Some(Color32::WHITE));
+    assert_eq!(pixmap.pixel(100, 200), Some(Color32::BLACK));
+}
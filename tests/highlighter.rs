@@ -0,0 +1,165 @@
+use eframe_template::draw::brush::{BrushKind, StrokeCap};
+use eframe_template::draw::canvas::{Action, Canvas, SingleStroke};
+use eframe_template::draw::highlighter::{self, OPACITY};
+use eframe_template::draw::mesh_cache::StrokeTessellator;
+use eframe_template::export::{raster, svg};
+use egui::epaint::TessellationOptions;
+use egui::{Color32, Pos2, Shape, Stroke, Vec2};
+
+fn highlight(points: Vec<Pos2>, cap: StrokeCap) -> SingleStroke {
+    SingleStroke {
+        cap,
+        kind: BrushKind::Highlighter,
+        ..SingleStroke::new(Stroke::new(10.0, Color32::YELLOW), points)
+    }
+}
+
+fn pen(points: Vec<Pos2>) -> SingleStroke {
+    SingleStroke::new(Stroke::new(4.0, Color32::BLACK), points)
+}
+
+fn canvas_with(strokes: Vec<SingleStroke>) -> Canvas {
+    let mut canvas = Canvas::new(Vec2::new(100.0, 100.0));
+    for (index, stroke) in strokes.into_iter().enumerate() {
+        Action::AddStroke {
+            stroke,
+            index,
+            layer: 0,
+        }
+        .execute(&mut canvas);
+    }
+    canvas
+}
+
+fn area(triangles: &[Pos2]) -> f32 {
+    triangles
+        .chunks_exact(3)
+        .map(|triangle| match triangle {
+            [a, b, c] => ((*b - *a).x * (*c - *a).y - (*b - *a).y * (*c - *a).x).abs() / 2.0,
+            _ => 0.0,
+        })
+        .sum()
+}
+
+#[test]
+fn passes_over_the_same_spot_cover_it_once() {
+    // There and back along the same line, then across it
+    let stroke = highlight(
+        vec![
+            Pos2::new(10.0, 50.0),
+            Pos2::new(90.0, 50.0),
+            Pos2::new(10.0, 50.0),
+        ],
+        StrokeCap::Butt,
+    );
+    let covered = area(&highlighter::triangles(&stroke));
+    // The 80 by 10 band and the half disc around the turn
+    let expected = 800.0 + std::f32::consts::PI * 25.0 / 2.0;
+    assert!((covered - expected).abs() < 2.0, "covered {covered}");
+
+    let cross = highlight(
+        vec![
+            Pos2::new(10.0, 50.0),
+            Pos2::new(90.0, 50.0),
+            Pos2::new(50.0, 90.0),
+            Pos2::new(50.0, 10.0),
+        ],
+        StrokeCap::Butt,
+    );
+    let separate: f32 = cross
+        .points
+        .windows(2)
+        .map(|pair| {
+            area(&highlighter::triangles(&highlight(
+                pair.to_vec(),
+                StrokeCap::Butt,
+            )))
+        })
+        .sum();
+    // Where the last segment crosses the first, a 10 by 10 square is only covered once
+    assert!(
+        area(&highlighter::triangles(&cross)) < separate - 90.0,
+        "overlap counted twice"
+    );
+}
+
+#[test]
+fn highlighter_ink_is_painted_beneath_pen_strokes() {
+    let mut canvas = canvas_with(vec![
+        pen(vec![Pos2::new(10.0, 50.0), Pos2::new(90.0, 50.0)]),
+        highlight(
+            vec![Pos2::new(50.0, 10.0), Pos2::new(50.0, 90.0)],
+            StrokeCap::Round,
+        ),
+    ]);
+    let mut tessellator = StrokeTessellator::new(1.0, TessellationOptions::default(), [1, 1]);
+    let shapes = canvas.stroke_shapes(0, &mut tessellator);
+
+    let colors: Vec<Option<Color32>> = shapes
+        .iter()
+        .map(|shape| match shape {
+            Shape::Mesh(mesh) => mesh.vertices.first().map(|vertex| vertex.color),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        colors.first(),
+        Some(&Some(Color32::YELLOW.gamma_multiply(OPACITY)))
+    );
+    assert_eq!(colors.len(), 2);
+
+    // Exports and the eyedropper follow the same order
+    let svg = svg::canvas_to_svg(&canvas);
+    let yellow = svg
+        .find(r##"stroke="#ffff00""##)
+        .expect("highlighter exported");
+    let black = svg.find(r##"stroke="#000000""##).expect("pen exported");
+    assert!(yellow < black, "{svg}");
+    assert!(svg.contains(r#"stroke-opacity="0.4""#), "{svg}");
+    assert_eq!(
+        eframe_template::draw::eyedropper::color_at(&canvas, Pos2::new(50.0, 50.0), |_| {
+            egui::Rect::NOTHING
+        }),
+        Color32::BLACK
+    );
+
+    let pixmap = raster::rasterize(&canvas, 1.0);
+    assert_eq!(
+        pixmap.pixel(50, 50).map(|pixel| pixel.r() < 64),
+        Some(true),
+        "pen ink is on top"
+    );
+}
+
+#[test]
+fn png_export_does_not_darken_overlaps() {
+    let canvas = canvas_with(vec![highlight(
+        vec![
+            Pos2::new(10.0, 50.0),
+            Pos2::new(90.0, 50.0),
+            Pos2::new(50.0, 90.0),
+            Pos2::new(50.0, 10.0),
+        ],
+        StrokeCap::Butt,
+    )]);
+    let pixmap = raster::rasterize(&canvas, 1.0);
+
+    let once = pixmap.pixel(20, 50).expect("inside the image");
+    let crossing = pixmap.pixel(50, 50).expect("inside the image");
+    assert_eq!(once, crossing);
+    assert_ne!(once, Color32::WHITE, "highlighter missing");
+    // Translucent, so the paper shows through
+    assert!(once.b() > 100, "{once:?}");
+}
+
+#[test]
+fn only_highlighters_store_their_kind() {
+    let pen = serde_json::to_string(&pen(vec![Pos2::ZERO, Pos2::new(1.0, 1.0)]))
+        .expect("stroke serializes");
+    assert!(!pen.contains("kind"), "{pen}");
+
+    let stroke = highlight(vec![Pos2::ZERO, Pos2::new(1.0, 1.0)], StrokeCap::Round);
+    let json = serde_json::to_string(&stroke).expect("stroke serializes");
+    let read: SingleStroke = serde_json::from_str(&json).expect("stroke deserializes");
+    assert_eq!(read.kind, BrushKind::Highlighter);
+}
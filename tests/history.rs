@@ -0,0 +1,568 @@
+use eframe_template::draw::canvas::{Action, Canvas, Layer, LayerSettings, SingleStroke};
+use eframe_template::draw::history::History;
+use eframe_template::draw::mesh_cache::StrokeTessellator;
+use eframe_template::draw::shapes::{Shape, ShapeKind};
+use egui::epaint::TessellationOptions;
+use egui::{Color32, Pos2, Stroke, Vec2};
+use proptest::prelude::*;
+
+#[derive(Clone, Debug)]
+enum Op {
+    Add { position: usize, seed: u8 },
+    Remove { position: usize },
+    Modify { position: usize, seed: u8 },
+    Undo,
+    Redo,
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        (any::<usize>(), any::<u8>()).prop_map(|(position, seed)| Op::Add { position, seed }),
+        any::<usize>().prop_map(|position| Op::Remove { position }),
+        (any::<usize>(), any::<u8>()).prop_map(|(position, seed)| Op::Modify { position, seed }),
+        Just(Op::Undo),
+        Just(Op::Redo),
+    ]
+}
+
+fn stroke(seed: u8) -> SingleStroke {
+    let x = f32::from(seed);
+    SingleStroke::new(
+        Stroke::new(x + 1.0, Color32::BLACK),
+        vec![Pos2::new(x, 0.0), Pos2::new(x, 10.0)],
+    )
+}
+
+fn strokes(canvas: &Canvas) -> Vec<SingleStroke> {
+    canvas.strokes(0).cloned().unwrap_or_default()
+}
+
+// Turns an op into an action against the current strokes, and the strokes it should produce
+fn plan(op: &Op, current: &[SingleStroke]) -> Option<(Action, Vec<SingleStroke>)> {
+    let mut expected = current.to_vec();
+
+    let action = match *op {
+        Op::Add { position, seed } => {
+            let index = position % (current.len() + 1);
+            expected.insert(index, stroke(seed));
+            Action::AddStroke {
+                stroke: stroke(seed),
+                index,
+                layer: 0,
+            }
+        }
+        Op::Remove { position } if !current.is_empty() => {
+            let index = position % current.len();
+            let removed = expected.remove(index);
+            Action::RemoveStroke {
+                stroke: removed,
+                index,
+                layer: 0,
+            }
+        }
+        Op::Modify { position, seed } if !current.is_empty() => {
+            let index = position % current.len();
+            let after = stroke(seed);
+            let before = std::mem::replace(expected.get_mut(index)?, after.clone());
+            Action::ModifyStroke {
+                before: Some(before),
+                after: Some(after),
+                index,
+                layer: 0,
+            }
+        }
+        _ => return None,
+    };
+
+    Some((action, expected))
+}
+
+proptest! {
+    // Replays random edits against a model that simply snapshots every state
+    #[test]
+    fn history_matches_snapshot_model(ops in prop::collection::vec(op(), 0..64)) {
+        let mut canvas = Canvas::new(Vec2::new(100.0, 100.0));
+        let mut history = History::default();
+        let mut states = vec![strokes(&canvas)];
+        let mut cursor: usize = 0;
+
+        for op in &ops {
+            match op {
+                Op::Undo => {
+                    history.undo(&mut canvas);
+                    cursor = cursor.saturating_sub(1);
+                }
+                Op::Redo => {
+                    history.redo(&mut canvas);
+                    if cursor + 1 < states.len() {
+                        cursor += 1;
+                    }
+                }
+                _ => {
+                    if let Some((action, expected)) = plan(op, &strokes(&canvas)) {
+                        history.execute(action, &mut canvas);
+                        states.truncate(cursor + 1);
+                        states.push(expected);
+                        cursor += 1;
+                    }
+                }
+            }
+
+            prop_assert_eq!(Some(strokes(&canvas)), states.get(cursor).cloned());
+            prop_assert_eq!(history.can_undo(), cursor > 0);
+            prop_assert_eq!(history.can_redo(), cursor + 1 < states.len());
+        }
+
+        // Everything can be unwound back to the start and replayed to the end
+        while history.can_undo() {
+            history.undo(&mut canvas);
+        }
+        prop_assert_eq!(Some(strokes(&canvas)), states.first().cloned());
+
+        while history.can_redo() {
+            history.redo(&mut canvas);
+        }
+        prop_assert_eq!(Some(strokes(&canvas)), states.last().cloned());
+    }
+}
+
+#[test]
+fn redone_action_can_be_undone_again() {
+    let mut canvas = Canvas::new(Vec2::new(100.0, 100.0));
+    let mut history = History::default();
+
+    history.execute(
+        Action::AddStroke {
+            stroke: stroke(1),
+            index: 0,
+            layer: 0,
+        },
+        &mut canvas,
+    );
+    history.undo(&mut canvas);
+    history.redo(&mut canvas);
+    history.undo(&mut canvas);
+
+    assert!(
+        strokes(&canvas).is_empty(),
+        "stroke survived undo after redo"
+    );
+}
+
+#[test]
+fn undoing_add_removes_the_added_stroke_not_the_last_one() {
+    let mut canvas = Canvas::new(Vec2::new(100.0, 100.0));
+    let mut history = History::default();
+
+    history.execute(
+        Action::AddStroke {
+            stroke: stroke(1),
+            index: 0,
+            layer: 0,
+        },
+        &mut canvas,
+    );
+    history.execute(
+        Action::AddStroke {
+            stroke: stroke(2),
+            index: 0,
+            layer: 0,
+        },
+        &mut canvas,
+    );
+    history.undo(&mut canvas);
+
+    assert_eq!(strokes(&canvas), vec![stroke(1)]);
+}
+
+#[test]
+fn transaction_is_undone_as_one_step() {
+    let mut canvas = Canvas::new(Vec2::new(100.0, 100.0));
+    let mut history = History::default();
+
+    history.execute(
+        Action::AddStroke {
+            stroke: stroke(1),
+            index: 0,
+            layer: 0,
+        },
+        &mut canvas,
+    );
+
+    history.begin();
+    for seed in 2..5 {
+        let before = strokes(&canvas).first().cloned();
+        history.execute(
+            Action::ModifyStroke {
+                before,
+                after: Some(stroke(seed)),
+                index: 0,
+                layer: 0,
+            },
+            &mut canvas,
+        );
+    }
+    history.commit();
+    assert_eq!(strokes(&canvas), vec![stroke(4)]);
+
+    history.undo(&mut canvas);
+    assert_eq!(strokes(&canvas), vec![stroke(1)]);
+
+    history.redo(&mut canvas);
+    assert_eq!(strokes(&canvas), vec![stroke(4)]);
+}
+
+#[test]
+fn undo_closes_an_open_transaction() {
+    let mut canvas = Canvas::new(Vec2::new(100.0, 100.0));
+    let mut history = History::default();
+
+    history.begin();
+    for index in 0..3 {
+        history.execute(
+            Action::AddStroke {
+                stroke: stroke(index),
+                index: usize::from(index),
+                layer: 0,
+            },
+            &mut canvas,
+        );
+    }
+    history.undo(&mut canvas);
+
+    assert!(!history.in_transaction(), "transaction left open");
+    assert!(
+        strokes(&canvas).is_empty(),
+        "only part of the transaction was undone"
+    );
+}
+
+// Strongest color the stroke meshes of `layer` are painted with, layer opacity included
+fn painted(canvas: &mut Canvas, layer: usize) -> Vec<Color32> {
+    let mut tessellator = StrokeTessellator::new(1.0, TessellationOptions::default(), [1, 1]);
+    canvas
+        .stroke_shapes(layer, &mut tessellator)
+        .iter()
+        .filter_map(|shape| match shape {
+            egui::Shape::Mesh(mesh) => mesh
+                .vertices
+                .iter()
+                .map(|vertex| vertex.color)
+                .max_by_key(|color| color.a()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn merging_down_keeps_how_both_layers_look() {
+    let red = Color32::from_rgba_unmultiplied(255, 0, 0, 200);
+    for (upper, lower) in [(1.0, 0.5), (0.3, 0.8), (0.6, 0.9), (0.7, 0.0)] {
+        let mut canvas = Canvas::new(Vec2::new(100.0, 100.0));
+        let mut history = History::default();
+        history.execute(
+            Action::AddStroke {
+                stroke: SingleStroke::new(
+                    Stroke::new(4.0, red),
+                    vec![Pos2::new(10.0, 10.0), Pos2::new(90.0, 10.0)],
+                ),
+                index: 0,
+                layer: 0,
+            },
+            &mut canvas,
+        );
+        history.execute(
+            Action::AddLayer {
+                layer: Layer::new("Top"),
+                index: 1,
+            },
+            &mut canvas,
+        );
+        history.execute(
+            Action::AddStroke {
+                stroke: stroke(3),
+                index: 0,
+                layer: 1,
+            },
+            &mut canvas,
+        );
+        for (layer, opacity) in canvas.layers.iter_mut().zip([lower, upper]) {
+            layer.opacity = opacity;
+        }
+        canvas.rebuild_caches();
+
+        let mut before = painted(&mut canvas, 0);
+        before.extend(painted(&mut canvas, 1));
+        let (Some(upper_layer), Some(lower_layer)) = (canvas.layers.get(1), canvas.layers.first())
+        else {
+            panic!("both layers exist");
+        };
+        history.execute(
+            Action::MergeDown {
+                upper: upper_layer.clone(),
+                lower: lower_layer.clone(),
+                index: 1,
+            },
+            &mut canvas,
+        );
+        assert_eq!(canvas.layers.len(), 1);
+        let merged = painted(&mut canvas, 0);
+        assert_eq!(merged.len(), before.len());
+        for (merged, before) in merged.iter().zip(&before) {
+            assert!(
+                merged.a().abs_diff(before.a()) <= 1,
+                "{upper} over {lower}: {before:?} became {merged:?}"
+            );
+        }
+
+        history.undo(&mut canvas);
+        let opacities: Vec<f32> = canvas.layers.iter().map(|layer| layer.opacity).collect();
+        assert_eq!(opacities, vec![lower, upper]);
+        let mut restored = painted(&mut canvas, 0);
+        restored.extend(painted(&mut canvas, 1));
+        assert_eq!(restored, before);
+    }
+}
+
+#[test]
+fn layer_actions_out_of_range_are_ignored() {
+    let mut canvas = Canvas::new(Vec2::new(100.0, 100.0));
+    let actions = [
+        Action::AddLayer {
+            layer: Layer::new("Far"),
+            index: 5,
+        },
+        Action::DeleteLayer {
+            layer: Layer::new("Far"),
+            index: 3,
+        },
+        Action::MoveLayer { from: 0, to: 3 },
+        Action::MergeDown {
+            upper: Layer::new("Layer 1"),
+            lower: Layer::new("Below"),
+            index: 0,
+        },
+    ];
+
+    for action in &actions {
+        action.execute(&mut canvas);
+        action.undo(&mut canvas);
+    }
+    let names: Vec<&str> = canvas
+        .layers
+        .iter()
+        .map(|layer| layer.name.as_str())
+        .collect();
+    assert_eq!(names, vec!["Layer 1"]);
+    assert_eq!(canvas.active_layer, 0);
+}
+
+#[test]
+fn object_actions_out_of_range_are_ignored() {
+    let mut canvas = Canvas::new(Vec2::new(100.0, 100.0));
+    Action::AddStroke {
+        stroke: stroke(1),
+        index: 0,
+        layer: 0,
+    }
+    .execute(&mut canvas);
+    let line = Shape::new(
+        ShapeKind::Line,
+        Stroke::new(1.0, Color32::BLACK),
+        Pos2::ZERO,
+        Pos2::new(10.0, 10.0),
+    );
+    let actions = [
+        Action::RemoveStroke {
+            stroke: stroke(2),
+            index: 3,
+            layer: 0,
+        },
+        Action::RemoveShape {
+            shape: line,
+            index: 1,
+            layer: 0,
+        },
+    ];
+
+    for action in &actions {
+        action.execute(&mut canvas);
+        action.undo(&mut canvas);
+    }
+    assert_eq!(strokes(&canvas), vec![stroke(1)]);
+    assert_eq!(canvas.shapes(0).map(Vec::len), Some(0));
+}
+
+fn names(canvas: &Canvas) -> Vec<String> {
+    canvas
+        .layers
+        .iter()
+        .map(|layer| layer.name.clone())
+        .collect()
+}
+
+// Three layers, "A" at the bottom, each with one stroke
+fn three_layers() -> Canvas {
+    let mut canvas = Canvas::new(Vec2::new(100.0, 100.0));
+    canvas.layers = ["A", "B", "C"]
+        .into_iter()
+        .zip(1..)
+        .map(|(name, seed)| {
+            let mut layer = Layer::new(name);
+            layer.strokes.push(stroke(seed));
+            layer
+        })
+        .collect();
+    canvas.rebuild_caches();
+    canvas
+}
+
+// Runs `action`, checks the result, then undoes and redoes it twice over
+fn round_trip(action: Action, active: usize, expected: &[&str]) {
+    let mut canvas = three_layers();
+    canvas.active_layer = active;
+    let before = names(&canvas);
+    let mut history = History::default();
+
+    history.execute(action, &mut canvas);
+    for _ in 0..2 {
+        assert_eq!(names(&canvas), expected, "redone layers");
+        assert!(
+            canvas.active_layer < canvas.layers.len(),
+            "active layer out of bounds after {expected:?}"
+        );
+
+        history.undo(&mut canvas);
+        assert_eq!(names(&canvas), before, "undone layers");
+        assert!(
+            canvas.active_layer < canvas.layers.len(),
+            "active layer out of bounds after undo to {before:?}"
+        );
+        let strokes: Vec<usize> = canvas
+            .layers
+            .iter()
+            .map(|layer| layer.strokes.len())
+            .collect();
+        assert_eq!(strokes, vec![1, 1, 1], "strokes moved between layers");
+
+        history.redo(&mut canvas);
+    }
+}
+
+#[test]
+fn layer_additions_round_trip() {
+    for (index, expected) in [
+        (0, ["New", "A", "B", "C"]),
+        (2, ["A", "B", "New", "C"]),
+        (3, ["A", "B", "C", "New"]),
+    ] {
+        let action = Action::AddLayer {
+            layer: Layer::new("New"),
+            index,
+        };
+        round_trip(action, 2, &expected);
+    }
+}
+
+#[test]
+fn layer_deletions_round_trip() {
+    let canvas = three_layers();
+    for (index, expected) in [(0, ["B", "C"]), (1, ["A", "C"]), (2, ["A", "B"])] {
+        let layer = canvas.layers.get(index).cloned().expect("layer exists");
+        // The active layer is the one deleted, or the last one when it sits below
+        for active in [index, 2] {
+            let action = Action::DeleteLayer {
+                layer: layer.clone(),
+                index,
+            };
+            round_trip(action, active, &expected);
+        }
+    }
+}
+
+#[test]
+fn layer_moves_round_trip() {
+    for (from, to, expected) in [
+        (0, 1, ["B", "A", "C"]),
+        (1, 0, ["B", "A", "C"]),
+        (1, 2, ["A", "C", "B"]),
+        (2, 1, ["A", "C", "B"]),
+        (0, 2, ["B", "C", "A"]),
+    ] {
+        round_trip(Action::MoveLayer { from, to }, from, &expected);
+    }
+}
+
+#[test]
+fn layer_renames_round_trip() {
+    for (index, before, expected) in [
+        (0, "A", ["Ground", "B", "C"]),
+        (2, "C", ["A", "B", "Ground"]),
+    ] {
+        let action = Action::RenameLayer {
+            before: before.to_owned(),
+            after: "Ground".to_owned(),
+            index,
+        };
+        round_trip(action, index, &expected);
+    }
+}
+
+#[test]
+fn merge_downs_round_trip() {
+    let canvas = three_layers();
+    for (index, expected) in [(1, ["A", "C"]), (2, ["A", "B"])] {
+        let upper = canvas.layers.get(index).cloned().expect("layer exists");
+        let lower = canvas.layers.get(index - 1).cloned().expect("layer exists");
+        let action = Action::MergeDown {
+            upper,
+            lower,
+            index,
+        };
+        round_trip(action, index, &expected);
+    }
+
+    // The merged strokes end up on the layer below, in front of its own
+    let mut canvas = three_layers();
+    let upper = canvas.layers.get(2).cloned().expect("layer exists");
+    let lower = canvas.layers.get(1).cloned().expect("layer exists");
+    Action::MergeDown {
+        upper,
+        lower,
+        index: 2,
+    }
+    .execute(&mut canvas);
+    assert_eq!(canvas.strokes(1), Some(&vec![stroke(2), stroke(3)]));
+    assert_eq!(canvas.active_layer, 1);
+}
+
+#[test]
+fn layer_settings_are_undoable() {
+    let mut canvas = three_layers();
+    let mut history = History::default();
+    let before = canvas
+        .layers
+        .first()
+        .map(Layer::settings)
+        .expect("layer exists");
+    let after = LayerSettings {
+        visible: false,
+        locked: true,
+        opacity: 0.25,
+    };
+
+    history.execute(
+        Action::ModifyLayer {
+            before,
+            after,
+            index: 0,
+        },
+        &mut canvas,
+    );
+    assert_eq!(canvas.layers.first().map(Layer::settings), Some(after));
+
+    history.undo(&mut canvas);
+    assert_eq!(canvas.layers.first().map(Layer::settings), Some(before));
+    history.redo(&mut canvas);
+    assert_eq!(canvas.layers.first().map(Layer::settings), Some(after));
+}
@@ -0,0 +1,77 @@
+use eframe_template::keymap::{Command, Keymap};
+use eframe_template::toolbar::main::Tool;
+use egui::{Key, KeyboardShortcut, Modifiers};
+
+#[test]
+fn default_keymap_has_no_conflicts() {
+    assert!(Keymap::default().conflicts().is_empty());
+}
+
+#[test]
+fn every_tool_has_a_default_shortcut() {
+    let keymap = Keymap::default();
+
+    for tool in Tool::ALL {
+        assert!(
+            keymap.shortcut(Command::SelectTool(tool)).is_some(),
+            "{tool:?} has no shortcut"
+        );
+    }
+}
+
+#[test]
+fn rebinding_onto_a_used_shortcut_is_a_conflict() {
+    let mut keymap = Keymap::default();
+    let undo = keymap.shortcut(Command::Undo);
+
+    keymap.set_shortcut(Command::ZoomFit, undo);
+
+    assert!(keymap.has_conflict(Command::Undo));
+    assert!(keymap.has_conflict(Command::ZoomFit));
+    assert!(!keymap.has_conflict(Command::Redo));
+
+    keymap.set_shortcut(
+        Command::ZoomFit,
+        Some(KeyboardShortcut::new(Modifiers::COMMAND, Key::F)),
+    );
+    assert!(keymap.conflicts().is_empty());
+}
+
+#[test]
+fn keymap_round_trips_through_serde() {
+    let mut keymap = Keymap::default();
+    keymap.set_shortcut(Command::Save, None);
+
+    let json = serde_json::to_string(&keymap).unwrap_or_default();
+    let restored: Option<Keymap> = serde_json::from_str(&json).ok();
+
+    assert_eq!(restored, Some(keymap));
+}
+
+#[test]
+fn commands_missing_from_a_saved_keymap_get_their_defaults() {
+    let defaults = Keymap::default();
+    let mut saved = defaults.clone();
+    saved
+        .bindings
+        .retain(|binding| binding.command != Command::ZoomFit);
+
+    let json = serde_json::to_string(&saved).unwrap_or_default();
+    let restored: Option<Keymap> = serde_json::from_str(&json).ok();
+    assert_eq!(
+        restored.and_then(|keymap| keymap.shortcut(Command::ZoomFit)),
+        defaults.shortcut(Command::ZoomFit)
+    );
+
+    // A default shortcut the user has since given to another command is left unbound
+    saved.set_shortcut(Command::Save, defaults.shortcut(Command::ZoomFit));
+    let json = serde_json::to_string(&saved).unwrap_or_default();
+    let restored: Keymap = serde_json::from_str(&json).unwrap_or_default();
+    assert!(
+        restored
+            .bindings
+            .iter()
+            .any(|binding| binding.command == Command::ZoomFit && binding.shortcut.is_none())
+    );
+    assert!(restored.conflicts().is_empty());
+}
@@ -0,0 +1,232 @@
+use eframe_template::draw::canvas::{Action, Canvas, SingleStroke};
+use eframe_template::draw::history::History;
+use eframe_template::draw::mesh_cache::StrokeTessellator;
+use egui::epaint::TessellationOptions;
+use egui::{Color32, Mesh, Pos2, Shape, Stroke, Vec2};
+
+fn tessellator(pixels_per_unit: f32) -> StrokeTessellator {
+    StrokeTessellator::new(pixels_per_unit, TessellationOptions::default(), [1, 1])
+}
+
+fn stroke(y: f32) -> SingleStroke {
+    SingleStroke::new(
+        Stroke::new(4.0, Color32::BLACK),
+        vec![
+            Pos2::new(10.0, y),
+            Pos2::new(50.0, y),
+            Pos2::new(90.0, y + 20.0),
+        ],
+    )
+}
+
+// Opacity the mesh paints at `point`, interpolated across the triangles that cover it
+fn coverage(mesh: &Mesh, point: Pos2) -> f32 {
+    mesh.indices
+        .chunks_exact(3)
+        .filter_map(|triangle| {
+            let vertex = |index: &u32| mesh.vertices.get(*index as usize);
+            let [a, b, c] = [
+                vertex(triangle.first()?)?,
+                vertex(triangle.get(1)?)?,
+                vertex(triangle.get(2)?)?,
+            ];
+            let cross = |u: Vec2, v: Vec2| u.x * v.y - u.y * v.x;
+            let area = cross(b.pos - a.pos, c.pos - a.pos);
+            if area.abs() <= f32::EPSILON {
+                return None;
+            }
+            let u = cross(point - a.pos, c.pos - a.pos) / area;
+            let v = cross(b.pos - a.pos, point - a.pos) / area;
+            let alpha = |vertex: &egui::epaint::Vertex| f32::from(vertex.color.a()) / 255.0;
+            (u >= 0.0 && v >= 0.0 && u + v <= 1.0)
+                .then(|| alpha(a) * (1.0 - u - v) + alpha(b) * u + alpha(c) * v)
+        })
+        .fold(0.0, f32::max)
+}
+
+fn canvas_with_strokes(history: &mut History, count: usize) -> Canvas {
+    let mut canvas = Canvas::new(Vec2::new(200.0, 200.0));
+    for index in 0..count {
+        history.execute(
+            Action::AddStroke {
+                stroke: stroke(index as f32 * 30.0),
+                index,
+                layer: 0,
+            },
+            &mut canvas,
+        );
+    }
+    canvas
+}
+
+fn cached(canvas: &Canvas, count: usize) -> Vec<bool> {
+    (0..count)
+        .map(|index| canvas.is_mesh_cached(0, index))
+        .collect()
+}
+
+#[test]
+fn strokes_are_tessellated_once() {
+    let mut history = History::default();
+    let mut canvas = canvas_with_strokes(&mut history, 3);
+    let mut tessellator = tessellator(1.0);
+
+    assert_eq!(cached(&canvas, 3), vec![false; 3]);
+
+    let first = canvas.stroke_shapes(0, &mut tessellator);
+    let second = canvas.stroke_shapes(0, &mut tessellator);
+    assert_eq!(first.len(), 3);
+    assert_eq!(cached(&canvas, 3), vec![true; 3]);
+
+    // The same mesh is handed out again rather than a fresh copy
+    for (a, b) in first.iter().zip(&second) {
+        let (Shape::Mesh(a), Shape::Mesh(b)) = (a, b) else {
+            panic!("strokes should be painted as meshes");
+        };
+        assert!(std::sync::Arc::ptr_eq(a, b), "stroke was tessellated again");
+        assert!(!a.is_empty(), "stroke mesh is empty");
+    }
+}
+
+#[test]
+fn modified_and_undone_strokes_are_rebuilt() {
+    let mut history = History::default();
+    let mut canvas = canvas_with_strokes(&mut history, 3);
+    let mut tessellator = tessellator(1.0);
+    canvas.stroke_shapes(0, &mut tessellator);
+
+    history.execute(
+        Action::ModifyStroke {
+            before: Some(stroke(30.0)),
+            after: Some(stroke(100.0)),
+            index: 1,
+            layer: 0,
+        },
+        &mut canvas,
+    );
+    assert_eq!(cached(&canvas, 3), vec![true, false, true]);
+
+    canvas.stroke_shapes(0, &mut tessellator);
+    history.undo(&mut canvas);
+    assert_eq!(cached(&canvas, 3), vec![true, false, true]);
+}
+
+#[test]
+fn inserting_keeps_the_other_meshes() {
+    let mut history = History::default();
+    let mut canvas = canvas_with_strokes(&mut history, 2);
+    let mut tessellator = tessellator(1.0);
+    canvas.stroke_shapes(0, &mut tessellator);
+
+    history.execute(
+        Action::AddStroke {
+            stroke: stroke(150.0),
+            index: 0,
+            layer: 0,
+        },
+        &mut canvas,
+    );
+    assert_eq!(cached(&canvas, 3), vec![false, true, true]);
+
+    history.undo(&mut canvas);
+    assert_eq!(cached(&canvas, 2), vec![true, true]);
+}
+
+#[test]
+fn zoom_and_opacity_changes_rebuild_meshes() {
+    let mut history = History::default();
+    let mut canvas = canvas_with_strokes(&mut history, 1);
+    let first = canvas.stroke_shapes(0, &mut tessellator(1.0));
+
+    let zoomed = canvas.stroke_shapes(0, &mut tessellator(2.0));
+    assert_ne!(first, zoomed);
+
+    if let Some(layer) = canvas.layers.get_mut(0) {
+        layer.opacity = 0.5;
+    }
+    let faded = canvas.stroke_shapes(0, &mut tessellator(2.0));
+    let Some(Shape::Mesh(mesh)) = faded.first() else {
+        panic!("stroke should be painted as a mesh");
+    };
+    assert!(
+        mesh.vertices
+            .iter()
+            .all(|vertex| vertex.color.a() <= Color32::BLACK.gamma_multiply(0.5).a()),
+        "layer opacity was not applied"
+    );
+}
+
+#[test]
+fn small_zoom_changes_keep_meshes() {
+    let mut history = History::default();
+    let mut canvas = canvas_with_strokes(&mut history, 2);
+    let first = canvas.stroke_shapes(0, &mut tessellator(1.0));
+
+    for pixels_per_unit in [1.05, 1.2, 0.9, 1.3] {
+        let zoomed = canvas.stroke_shapes(0, &mut tessellator(pixels_per_unit));
+        for (a, b) in first.iter().zip(&zoomed) {
+            let (Shape::Mesh(a), Shape::Mesh(b)) = (a, b) else {
+                panic!("strokes should be painted as meshes");
+            };
+            assert!(
+                std::sync::Arc::ptr_eq(a, b),
+                "re-tessellated at {pixels_per_unit}"
+            );
+        }
+    }
+}
+
+#[test]
+fn round_strokes_keep_their_width_around_corners() {
+    let mut tessellator = tessellator(1.0);
+    let corner = SingleStroke::new(
+        Stroke::new(10.0, Color32::BLACK),
+        vec![
+            Pos2::new(10.0, 10.0),
+            Pos2::new(50.0, 10.0),
+            Pos2::new(50.0, 50.0),
+        ],
+    );
+    let mesh = tessellator.tessellate(&corner, Color32::BLACK);
+
+    // Within half the width of the corner, on its outer side
+    assert!(
+        coverage(&mesh, Pos2::new(54.0, 8.0)) > 0.9,
+        "corner pinched"
+    );
+    // Round ends
+    assert!(
+        coverage(&mesh, Pos2::new(6.0, 10.0)) > 0.9,
+        "start not round"
+    );
+    assert!(
+        coverage(&mesh, Pos2::new(50.0, 54.0)) > 0.9,
+        "end not round"
+    );
+    assert!(
+        coverage(&mesh, Pos2::new(6.0, 6.0)) < 0.1,
+        "start drawn square"
+    );
+}
+
+#[test]
+fn round_strokes_keep_their_width_through_hairpins() {
+    let mut tessellator = tessellator(1.0);
+    let hairpin = SingleStroke::new(
+        Stroke::new(10.0, Color32::BLACK),
+        vec![
+            Pos2::new(10.0, 30.0),
+            Pos2::new(50.0, 30.0),
+            Pos2::new(10.0, 30.0),
+        ],
+    );
+    let mesh = tessellator.tessellate(&hairpin, Color32::BLACK);
+
+    for point in [
+        Pos2::new(30.0, 34.0),
+        Pos2::new(45.0, 26.0),
+        Pos2::new(48.0, 33.0),
+    ] {
+        assert!(coverage(&mesh, point) > 0.9, "{point:?} not covered");
+    }
+}
@@ -0,0 +1,162 @@
+use eframe_template::document::file::{self, DocumentError};
+use eframe_template::document::palette::{
+    self, Palette, PaletteError, PaletteFormat, Palettes, RECENT_LIMIT, Swatch,
+};
+use eframe_template::draw::canvas::{Canvas, SingleStroke};
+use eframe_template::draw::fill::Fill;
+use eframe_template::draw::text::{TextAlign, TextBox};
+use egui::{Color32, Pos2, Stroke, Vec2};
+
+fn sample() -> Palette {
+    let mut palette = Palette::new("Sunset");
+    palette.swatches.push(Swatch {
+        color: Color32::from_rgb(250, 90, 30),
+        name: "Deep orange".to_owned(),
+    });
+    palette.add(Color32::from_rgb(20, 0, 60));
+    palette
+}
+
+#[test]
+fn gpl_round_trips() {
+    let gpl = palette::to_gpl(&sample());
+    assert!(gpl.starts_with("GIMP Palette\nName: Sunset\n"), "{gpl}");
+
+    let read = palette::from_gpl(&gpl, "fallback").expect("written palette reads back");
+    assert_eq!(read.name, "Sunset");
+    assert_eq!(
+        read.colors().collect::<Vec<_>>(),
+        sample().colors().collect::<Vec<_>>()
+    );
+    assert_eq!(
+        read.swatches.first().map(|swatch| swatch.name.as_str()),
+        Some("Deep orange")
+    );
+}
+
+#[test]
+fn gpl_skips_comments_and_headers() {
+    let gpl = "GIMP Palette\nColumns: 4\n# a comment\n\n  0 128 255\tSky\n255 255 255\n";
+    let read = palette::from_gpl(gpl, "Imported").expect("palette parses");
+
+    assert_eq!(read.name, "Imported");
+    assert_eq!(
+        read.swatches,
+        vec![
+            Swatch {
+                color: Color32::from_rgb(0, 128, 255),
+                name: "Sky".to_owned(),
+            },
+            Swatch::new(Color32::WHITE),
+        ]
+    );
+}
+
+#[test]
+fn malformed_gpl_is_rejected() {
+    assert_eq!(
+        palette::from_gpl("0 0 0\n", "x"),
+        Err(PaletteError::MissingGplHeader)
+    );
+    assert_eq!(
+        palette::from_gpl("GIMP Palette\n12 300 4 Too bright\n", "x"),
+        Err(PaletteError::InvalidGplLine {
+            line: 2,
+            text: "12 300 4 Too bright".to_owned(),
+        })
+    );
+}
+
+#[test]
+fn ase_round_trips_and_rejects_bad_data() {
+    let ase = palette::to_ase(&sample());
+    assert!(ase.starts_with(b"ASEF"), "missing signature");
+
+    let read = palette::from_ase(&ase, "fallback").expect("written palette reads back");
+    assert_eq!(read.name, "Sunset");
+    assert_eq!(
+        read.colors().collect::<Vec<_>>(),
+        sample().colors().collect::<Vec<_>>()
+    );
+    // Unnamed swatches are written with their hex label
+    assert_eq!(
+        read.swatches.get(1).map(|swatch| swatch.name.as_str()),
+        Some("#14003cff")
+    );
+
+    let cut = ase.get(..ase.len() - 6).unwrap_or_default();
+    assert_eq!(palette::from_ase(cut, "x"), Err(PaletteError::TruncatedAse));
+    assert_eq!(
+        palette::from_ase(b"GIMP Palette", "x"),
+        Err(PaletteError::MissingAseSignature)
+    );
+}
+
+#[test]
+fn recent_colors_are_unique_newest_first_and_limited() {
+    let mut palettes = Palettes::default();
+    for gray in 0..40 {
+        palettes.use_color(Color32::from_gray(gray));
+    }
+    palettes.use_color(Color32::from_gray(30));
+
+    assert_eq!(palettes.recent.len(), RECENT_LIMIT);
+    assert_eq!(palettes.recent.first(), Some(&Color32::from_gray(30)));
+    assert_eq!(palettes.recent.get(1), Some(&Color32::from_gray(39)));
+    assert_eq!(
+        palettes
+            .recent
+            .iter()
+            .filter(|color| **color == Color32::from_gray(30))
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn document_colors_are_collected_once_in_order() {
+    let mut canvas = Canvas::new(Vec2::new(100.0, 100.0));
+    if let Some(layer) = canvas.layers.first_mut() {
+        for color in [Color32::RED, Color32::BLUE, Color32::RED] {
+            layer.strokes.push(SingleStroke::new(
+                Stroke::new(2.0, color),
+                vec![Pos2::ZERO, Pos2::new(10.0, 10.0)],
+            ));
+        }
+        layer.fills.push(Fill::new(Color32::GREEN, Vec::new()));
+        layer.texts.push(TextBox::new(
+            "Hi",
+            Pos2::ZERO,
+            12.0,
+            Color32::BLUE,
+            TextAlign::Left,
+        ));
+    }
+
+    let colors: Vec<Color32> = palette::document_colors(&canvas).colors().collect();
+    assert_eq!(colors, vec![Color32::RED, Color32::BLUE, Color32::GREEN]);
+}
+
+#[test]
+fn palette_files_go_through_the_document_module() {
+    let path =
+        std::env::temp_dir().join(format!("simple_paint_{}_palette.gpl", std::process::id()));
+    assert!(
+        file::export_palette(&path, &sample(), PaletteFormat::Gpl).is_ok(),
+        "export failed"
+    );
+    let read = file::import_palette(&path).expect("exported palette imports");
+    assert_eq!(
+        read.colors().collect::<Vec<_>>(),
+        sample().colors().collect::<Vec<_>>()
+    );
+
+    let unknown = file::import_palette(&path.with_extension("txt"));
+    assert!(
+        matches!(
+            unknown,
+            Err(DocumentError::Palette(PaletteError::UnknownFormat(_)))
+        ),
+        "{unknown:?}"
+    );
+}
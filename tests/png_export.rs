@@ -0,0 +1,67 @@
+use eframe_template::draw::canvas::{Action, Canvas, SingleStroke};
+use eframe_template::export::{png, raster};
+use egui::{Color32, Pos2, Stroke, Vec2};
+
+fn canvas_with_line() -> Canvas {
+    let mut canvas = Canvas::new(Vec2::new(100.0, 50.0));
+    let stroke = SingleStroke::new(
+        Stroke::new(4.0, Color32::BLACK),
+        vec![Pos2::new(10.0, 25.0), Pos2::new(90.0, 25.0)],
+    );
+    Action::AddStroke {
+        stroke,
+        index: 0,
+        layer: 0,
+    }
+    .execute(&mut canvas);
+    canvas
+}
+
+#[test]
+fn rasterizes_strokes_over_white_background() {
+    let pixmap = raster::rasterize(&canvas_with_line(), 1.0);
+
+    assert_eq!((pixmap.width, pixmap.height), (100, 50));
+    assert_eq!(pixmap.pixel(5, 5), Some(Color32::WHITE));
+    assert_eq!(pixmap.pixel(50, 24), Some(Color32::BLACK));
+    assert_eq!(pixmap.pixel(95, 25), Some(Color32::WHITE));
+}
+
+#[test]
+fn antialiases_stroke_edges() {
+    let mut canvas = Canvas::new(Vec2::new(20.0, 20.0));
+    let stroke = SingleStroke::new(
+        Stroke::new(1.0, Color32::BLACK),
+        vec![Pos2::new(0.0, 10.0), Pos2::new(20.0, 10.0)],
+    );
+    Action::AddStroke {
+        stroke,
+        index: 0,
+        layer: 0,
+    }
+    .execute(&mut canvas);
+
+    let pixmap = raster::rasterize(&canvas, 1.0);
+    let edge = pixmap.pixel(10, 10).map(|color| color.r());
+
+    // The line straddles two pixel rows, so each one is only partially covered
+    assert!(edge.is_some_and(|r| r > 0 && r < 255), "{edge:?}");
+}
+
+#[test]
+fn scales_output() {
+    let canvas = canvas_with_line();
+
+    for scale in png::SCALES {
+        let pixmap = raster::rasterize(&canvas, scale);
+        assert_eq!(pixmap.width, (100.0 * scale) as u32);
+        assert_eq!(pixmap.height, (50.0 * scale) as u32);
+    }
+}
+
+#[test]
+fn encodes_png() {
+    let bytes = png::canvas_to_png(&canvas_with_line(), 2.0).unwrap_or_default();
+
+    assert!(bytes.starts_with(b"\x89PNG\r\n\x1a\n"), "not a PNG");
+}
@@ -0,0 +1,149 @@
+use eframe_template::document::format::Document;
+use eframe_template::draw::brush::{BrushKind, StrokeCap};
+use eframe_template::draw::canvas::{Action, Canvas, SingleStroke};
+use eframe_template::draw::pressure::{
+    outline, stroke_mesh, width_from_force, width_from_velocity,
+};
+use eframe_template::export::{raster, svg};
+use egui::{Color32, Pos2, Stroke, Vec2};
+
+// Thin at the start, thick at the end
+fn tapered_stroke() -> SingleStroke {
+    SingleStroke {
+        stroke: Stroke::new(10.0, Color32::BLACK),
+        points: vec![
+            Pos2::new(10.0, 25.0),
+            Pos2::new(40.0, 25.0),
+            Pos2::new(60.0, 25.0),
+        ],
+        widths: vec![2.0, 6.0, 10.0],
+        cap: StrokeCap::Round,
+        kind: BrushKind::Pen,
+    }
+}
+
+fn canvas_with(stroke: SingleStroke) -> Canvas {
+    let mut canvas = Canvas::new(Vec2::new(100.0, 50.0));
+    Action::AddStroke {
+        stroke,
+        index: 0,
+        layer: 0,
+    }
+    .execute(&mut canvas);
+    canvas
+}
+
+#[test]
+fn force_maps_to_a_fraction_of_the_width() {
+    assert!((width_from_force(10.0, 1.0) - 10.0).abs() < 1e-4);
+    assert!(width_from_force(10.0, 0.0) > 0.0);
+    assert!(width_from_force(10.0, 0.0) < width_from_force(10.0, 0.5));
+    assert!((width_from_force(10.0, 5.0) - 10.0).abs() < 1e-4);
+}
+
+#[test]
+fn faster_movement_draws_thinner() {
+    let slow = width_from_velocity(10.0, 100.0, None);
+    let fast = width_from_velocity(10.0, 2500.0, None);
+    assert!(fast < slow);
+
+    // Smoothing moves only part of the way towards the new width
+    let smoothed = width_from_velocity(10.0, 2500.0, Some(slow));
+    assert!(fast < smoothed && smoothed < slow);
+}
+
+#[test]
+fn widths_stay_one_per_point() {
+    let mut stroke = SingleStroke::new(Stroke::new(4.0, Color32::BLACK), Vec::new());
+    stroke.push(Pos2::new(0.0, 0.0), None);
+    stroke.push(Pos2::new(1.0, 0.0), Some(3.0));
+    stroke.push(Pos2::new(2.0, 0.0), None);
+
+    // Points before the first reading take its width, later ones keep the last one
+    assert_eq!(stroke.widths, vec![3.0, 3.0, 3.0]);
+    assert_eq!(stroke.width_at(1), 3.0);
+    assert_eq!(
+        SingleStroke::new(Stroke::new(4.0, Color32::BLACK), Vec::new()).width_at(0),
+        4.0
+    );
+}
+
+#[test]
+fn outline_follows_the_widths() {
+    let outline = outline(&tapered_stroke());
+    let thickness_at = |x: f32| {
+        let ys: Vec<f32> = outline
+            .iter()
+            .filter(|point| (point.x - x).abs() < 1e-3)
+            .map(|point| point.y)
+            .collect();
+        ys.iter().copied().fold(f32::MIN, f32::max) - ys.iter().copied().fold(f32::MAX, f32::min)
+    };
+
+    assert!((thickness_at(40.0) - 6.0).abs() < 1e-3);
+    assert!(thickness_at(10.0) < thickness_at(60.0));
+}
+
+#[test]
+fn mesh_indices_stay_in_bounds() {
+    let mesh = stroke_mesh(&tapered_stroke(), Color32::BLACK, 1.0);
+
+    assert!(!mesh.indices.is_empty());
+    assert_eq!(mesh.indices.len() % 3, 0);
+    assert!(
+        mesh.indices
+            .iter()
+            .all(|index| (*index as usize) < mesh.vertices.len())
+    );
+}
+
+#[test]
+fn uniform_strokes_are_not_tapered() {
+    let stroke = SingleStroke {
+        stroke: Stroke::new(2.0, Color32::BLACK),
+        points: vec![Pos2::ZERO, Pos2::new(1.0, 1.0)],
+        widths: Vec::new(),
+        cap: StrokeCap::Round,
+        kind: BrushKind::Pen,
+    };
+
+    assert!(!stroke.is_tapered());
+    assert!(tapered_stroke().is_tapered());
+    // Older documents without widths still read the same
+    assert!(
+        !serde_json::to_string(&stroke)
+            .unwrap_or_default()
+            .contains("widths")
+    );
+}
+
+#[test]
+fn documents_keep_per_point_widths() {
+    let canvas = canvas_with(tapered_stroke());
+    let json = serde_json::to_string(&Document::from_canvas(&canvas)).unwrap_or_default();
+    let document: Option<Document> = serde_json::from_str(&json).ok();
+    let restored = document.map(Document::into_canvas);
+
+    assert_eq!(
+        restored.as_ref().and_then(|canvas| canvas.strokes(0)),
+        Some(&vec![tapered_stroke()])
+    );
+}
+
+#[test]
+fn svg_fills_the_tapered_outline() {
+    let svg = svg::canvas_to_svg(&canvas_with(tapered_stroke()));
+
+    assert!(svg.contains(r##"fill="#000000""##), "{svg}");
+    assert!(svg.contains(r#"stroke="none""#), "{svg}");
+    assert_eq!(svg.matches(" Z").count(), 1, "{svg}");
+}
+
+#[test]
+fn png_export_tapers_strokes() {
+    let pixmap = raster::rasterize(&canvas_with(tapered_stroke()), 1.0);
+
+    // 2 units wide at the start, 10 at the end
+    assert_eq!(pixmap.pixel(12, 21), Some(Color32::WHITE));
+    assert_eq!(pixmap.pixel(58, 21), Some(Color32::BLACK));
+}
@@ -0,0 +1,259 @@
+use eframe_template::SimplePaintApp;
+use eframe_template::draw::canvas::{Action, Canvas, SingleStroke};
+use eframe_template::draw::history::History;
+use eframe_template::draw::selection::{
+    Gesture, Handle, Selection, Transform, handle_at, pick_in_lasso, pick_in_rect,
+    point_in_polygon, scale_between,
+};
+use eframe_template::keymap::Command;
+use egui::{Color32, Pos2, Rect, Stroke, Vec2};
+
+fn stroke_at(from: Pos2, to: Pos2) -> SingleStroke {
+    SingleStroke::new(Stroke::new(2.0, Color32::BLACK), vec![from, to])
+}
+
+fn strokes() -> Vec<SingleStroke> {
+    vec![
+        stroke_at(Pos2::new(10.0, 10.0), Pos2::new(20.0, 20.0)),
+        stroke_at(Pos2::new(100.0, 100.0), Pos2::new(120.0, 100.0)),
+        stroke_at(Pos2::new(15.0, 90.0), Pos2::new(25.0, 95.0)),
+    ]
+}
+
+fn assert_close(a: Pos2, b: Pos2) {
+    assert!(a.distance(b) < 1e-3, "{a:?} != {b:?}");
+}
+
+#[test]
+fn rectangle_picks_strokes_touching_it() {
+    let rect = Rect::from_min_max(Pos2::new(0.0, 0.0), Pos2::new(50.0, 92.0));
+
+    assert_eq!(pick_in_rect(&strokes(), rect), vec![0, 2]);
+}
+
+#[test]
+fn lasso_picks_strokes_inside_polygon() {
+    let triangle = [
+        Pos2::new(0.0, 0.0),
+        Pos2::new(200.0, 0.0),
+        Pos2::new(0.0, 200.0),
+    ];
+
+    assert_eq!(pick_in_lasso(&strokes(), &triangle), vec![0, 2]);
+    assert!(pick_in_lasso(&strokes(), &triangle[..2]).is_empty());
+}
+
+#[test]
+fn point_in_polygon_handles_concave_shapes() {
+    // A "C" shape open to the right
+    let polygon = [
+        Pos2::new(0.0, 0.0),
+        Pos2::new(30.0, 0.0),
+        Pos2::new(30.0, 10.0),
+        Pos2::new(10.0, 10.0),
+        Pos2::new(10.0, 20.0),
+        Pos2::new(30.0, 20.0),
+        Pos2::new(30.0, 30.0),
+        Pos2::new(0.0, 30.0),
+    ];
+
+    assert!(point_in_polygon(Pos2::new(5.0, 15.0), &polygon));
+    assert!(!point_in_polygon(Pos2::new(20.0, 15.0), &polygon));
+    assert!(!point_in_polygon(Pos2::new(40.0, 5.0), &polygon));
+}
+
+#[test]
+fn selection_bounds_cover_selected_strokes() {
+    let selection = Selection {
+        layer: 0,
+        strokes: vec![0, 2],
+    };
+
+    assert_eq!(
+        selection.bounds(&strokes()),
+        Some(Rect::from_min_max(
+            Pos2::new(10.0, 10.0),
+            Pos2::new(25.0, 95.0)
+        ))
+    );
+    assert_eq!(Selection::default().bounds(&strokes()), None);
+}
+
+#[test]
+fn handles_are_found_around_bounds() {
+    let bounds = Rect::from_min_max(Pos2::new(0.0, 100.0), Pos2::new(100.0, 200.0));
+
+    assert_eq!(
+        handle_at(bounds, Pos2::new(1.0, 101.0)),
+        Some(Handle::Corner {
+            anchor: bounds.right_bottom()
+        })
+    );
+    assert_eq!(
+        handle_at(bounds, Pos2::new(50.0, 70.0)),
+        Some(Handle::Rotate)
+    );
+    assert_eq!(
+        handle_at(bounds, Pos2::new(50.0, 150.0)),
+        Some(Handle::Inside)
+    );
+    assert_eq!(handle_at(bounds, Pos2::new(300.0, 150.0)), None);
+}
+
+#[test]
+fn transforms_move_scale_and_rotate_points() {
+    let point = Pos2::new(20.0, 10.0);
+
+    assert_close(
+        Transform::translate(Vec2::new(5.0, -5.0)).apply(point),
+        Pos2::new(25.0, 5.0),
+    );
+    assert_close(
+        Transform::scale_around(Pos2::new(10.0, 10.0), Vec2::splat(2.0)).apply(point),
+        Pos2::new(30.0, 10.0),
+    );
+    assert_close(
+        Transform::rotate_around(Pos2::new(10.0, 10.0), std::f32::consts::FRAC_PI_2).apply(point),
+        Pos2::new(10.0, 20.0),
+    );
+}
+
+#[test]
+fn scaling_a_stroke_scales_its_width() {
+    let stroke = stroke_at(Pos2::new(0.0, 0.0), Pos2::new(10.0, 0.0));
+    let scaled = Transform::scale_around(Pos2::ZERO, Vec2::splat(3.0)).apply_to_stroke(&stroke);
+
+    assert!((scaled.stroke.width - 6.0).abs() < 1e-4);
+    assert_eq!(scaled.points.last().copied(), Some(Pos2::new(30.0, 0.0)));
+}
+
+#[test]
+fn uniform_scale_keeps_aspect_ratio() {
+    let anchor = Pos2::ZERO;
+    let start = Pos2::new(10.0, 10.0);
+
+    assert_eq!(
+        scale_between(anchor, start, Pos2::new(20.0, 15.0), false),
+        Vec2::new(2.0, 1.5)
+    );
+    assert_eq!(
+        scale_between(anchor, start, Pos2::new(20.0, 15.0), true),
+        Vec2::splat(2.0)
+    );
+}
+
+#[test]
+fn modifying_several_strokes_is_one_undo_step() {
+    let mut canvas = Canvas::new(Vec2::new(200.0, 200.0));
+    let mut history = History::default();
+    for (index, stroke) in strokes().into_iter().enumerate() {
+        history.execute(
+            Action::AddStroke {
+                stroke,
+                index,
+                layer: 0,
+            },
+            &mut canvas,
+        );
+    }
+
+    let original = strokes();
+    let indices = vec![0, 2];
+    let before: Vec<SingleStroke> = indices
+        .iter()
+        .filter_map(|index| original.get(*index).cloned())
+        .collect();
+    let after = before
+        .iter()
+        .map(|stroke| Transform::translate(Vec2::splat(10.0)).apply_to_stroke(stroke))
+        .collect();
+    history.execute(
+        Action::ModifyStrokes {
+            before,
+            after,
+            indices,
+            layer: 0,
+        },
+        &mut canvas,
+    );
+
+    assert_ne!(canvas.strokes(0), Some(&original));
+    history.undo(&mut canvas);
+    assert_eq!(canvas.strokes(0), Some(&original));
+}
+
+#[test]
+fn selections_on_locked_layers_are_left_alone() {
+    let mut app = SimplePaintApp::default();
+    for (index, stroke) in strokes().into_iter().enumerate() {
+        app.run(Action::AddStroke {
+            stroke,
+            index,
+            layer: 0,
+        });
+    }
+    app.selection = Selection {
+        layer: 0,
+        strokes: vec![0, 1],
+    };
+    if let Some(layer) = app.canvas.layers.first_mut() {
+        layer.locked = true;
+    }
+
+    app.run_command(Command::DeleteSelection);
+    app.run_command(Command::DuplicateSelection);
+    assert_eq!(app.canvas.strokes(0), Some(&strokes()));
+}
+
+#[test]
+fn undo_waits_for_a_selection_drag_to_finish() {
+    let mut app = SimplePaintApp::default();
+    app.run(Action::AddStroke {
+        stroke: stroke_at(Pos2::new(10.0, 10.0), Pos2::new(20.0, 20.0)),
+        index: 0,
+        layer: 0,
+    });
+    app.select_gesture = Some(Gesture::Marquee {
+        start: Pos2::ZERO,
+        current: Pos2::new(5.0, 5.0),
+    });
+
+    assert!(!app.can_undo(), "undo offered mid-drag");
+    app.run_command(Command::Undo);
+    assert_eq!(app.canvas.strokes(0).map(Vec::len), Some(1));
+
+    app.select_gesture = None;
+    app.run_command(Command::Undo);
+    assert_eq!(app.canvas.strokes(0).map(Vec::len), Some(0));
+}
+
+#[test]
+fn new_and_opened_documents_start_without_a_selection() {
+    for open in [false, true] {
+        let mut app = SimplePaintApp::default();
+        for (index, stroke) in strokes().into_iter().enumerate() {
+            app.run(Action::AddStroke {
+                stroke,
+                index,
+                layer: 0,
+            });
+        }
+        app.selection = Selection {
+            layer: 0,
+            strokes: vec![0, 1],
+        };
+        app.select_gesture = Some(Gesture::Marquee {
+            start: Pos2::ZERO,
+            current: Pos2::new(5.0, 5.0),
+        });
+
+        if open {
+            app.replace_canvas(Canvas::new(Vec2::new(100.0, 100.0)));
+        } else {
+            app.start_fresh();
+        }
+        assert!(app.selection.is_empty(), "selection kept");
+        assert!(app.select_gesture.is_none(), "gesture kept");
+        assert!(!app.history.can_undo(), "history kept");
+    }
+}
@@ -0,0 +1,96 @@
+use eframe::App as _;
+use eframe_template::SimplePaintApp;
+use eframe_template::draw::canvas::{Action, SingleStroke};
+use egui::{Color32, Pos2, Stroke};
+use std::collections::HashMap;
+
+#[derive(Default)]
+struct MemoryStorage(HashMap<String, String>);
+
+impl eframe::Storage for MemoryStorage {
+    fn get_string(&self, key: &str) -> Option<String> {
+        self.0.get(key).cloned()
+    }
+
+    fn set_string(&mut self, key: &str, value: String) {
+        self.0.insert(key.to_owned(), value);
+    }
+
+    fn flush(&mut self) {}
+}
+
+fn app_with_stroke() -> SimplePaintApp {
+    let mut app = SimplePaintApp::default();
+    app.initial_modal.active = false;
+    app.stroke_type = Stroke::new(12.0, Color32::RED);
+    app.run(Action::AddStroke {
+        stroke: SingleStroke::new(
+            app.stroke_type,
+            vec![Pos2::new(1.0, 1.0), Pos2::new(5.0, 5.0)],
+        ),
+        index: 0,
+        layer: 0,
+    });
+    app
+}
+
+fn stroke_count(app: &SimplePaintApp) -> usize {
+    app.canvas.strokes(0).map_or(0, Vec::len)
+}
+
+#[test]
+fn restores_previous_session() {
+    let mut storage = MemoryStorage::default();
+    app_with_stroke().save(&mut storage);
+
+    let restored = SimplePaintApp::restore(&storage);
+
+    assert_eq!(stroke_count(&restored), 1);
+    assert_eq!(restored.stroke_type, Stroke::new(12.0, Color32::RED));
+    assert!(!restored.initial_modal.active, "initial modal shown again");
+    assert!(restored.history.can_undo(), "history was not restored");
+}
+
+#[test]
+fn skips_history_when_disabled() {
+    let mut storage = MemoryStorage::default();
+    let mut app = app_with_stroke();
+    app.session.restore_history = false;
+    app.save(&mut storage);
+
+    let restored = SimplePaintApp::restore(&storage);
+
+    assert_eq!(stroke_count(&restored), 1);
+    assert!(!restored.history.can_undo(), "history was restored");
+}
+
+#[test]
+fn starts_fresh_when_restore_is_disabled() {
+    let mut storage = MemoryStorage::default();
+    let mut app = app_with_stroke();
+    app.session.restore_session = false;
+    app.save(&mut storage);
+
+    let restored = SimplePaintApp::restore(&storage);
+
+    assert_eq!(stroke_count(&restored), 0);
+    assert!(restored.initial_modal.active, "initial modal not shown");
+    assert!(!restored.session.restore_session, "preference was lost");
+}
+
+#[test]
+fn corrupt_session_falls_back_to_defaults_and_is_kept() {
+    let mut storage = MemoryStorage::default();
+    eframe::Storage::set_string(&mut storage, eframe::APP_KEY, "(((not ron".to_owned());
+
+    let mut restored = SimplePaintApp::restore(&storage);
+    assert_eq!(stroke_count(&restored), 0);
+
+    restored.save(&mut storage);
+    let kept = storage
+        .0
+        .iter()
+        .any(|(key, value)| key != eframe::APP_KEY && value == "(((not ron");
+
+    assert!(kept, "corrupt session was discarded");
+}
@@ -0,0 +1,99 @@
+use eframe_template::draw::shapes::{self, Shape, ShapeKind};
+use egui::{Color32, Pos2, Stroke};
+
+fn close(a: Pos2, b: Pos2) -> bool {
+    a.distance(b) < 1e-3
+}
+
+#[test]
+fn plain_drags_span_from_the_anchor() {
+    let anchor = Pos2::new(10.0, 20.0);
+    let cursor = Pos2::new(40.0, 30.0);
+    for kind in [ShapeKind::Rectangle, ShapeKind::Ellipse, ShapeKind::Line] {
+        assert_eq!(
+            shapes::drag_points(kind, anchor, cursor, false, false),
+            (anchor, cursor)
+        );
+    }
+}
+
+#[test]
+fn shift_keeps_boxes_square_in_the_drag_direction() {
+    let anchor = Pos2::new(50.0, 50.0);
+    for kind in [ShapeKind::Rectangle, ShapeKind::Ellipse] {
+        let (from, to) = shapes::drag_points(kind, anchor, Pos2::new(80.0, 40.0), true, false);
+        assert_eq!(from, anchor);
+        assert_eq!(to, Pos2::new(80.0, 20.0));
+
+        let (_, to) = shapes::drag_points(kind, anchor, Pos2::new(45.0, 70.0), true, false);
+        assert_eq!(to, Pos2::new(30.0, 70.0));
+    }
+}
+
+#[test]
+fn shift_snaps_lines_to_45_degrees() {
+    let anchor = Pos2::new(0.0, 0.0);
+    let (_, to) = shapes::drag_points(ShapeKind::Line, anchor, Pos2::new(100.0, 10.0), true, false);
+    assert!(close(to, Pos2::new(100.0_f32.hypot(10.0), 0.0)), "{to:?}");
+
+    let (_, to) = shapes::drag_points(ShapeKind::Line, anchor, Pos2::new(30.0, 28.0), true, false);
+    assert!((to.x - to.y).abs() < 1e-3, "not diagonal: {to:?}");
+    assert!((to.to_vec2().length() - 30.0_f32.hypot(28.0)).abs() < 1e-3);
+}
+
+#[test]
+fn alt_grows_the_shape_around_the_anchor() {
+    let anchor = Pos2::new(50.0, 50.0);
+    let (from, to) = shapes::drag_points(
+        ShapeKind::Rectangle,
+        anchor,
+        Pos2::new(60.0, 70.0),
+        false,
+        true,
+    );
+    assert_eq!((from, to), (Pos2::new(40.0, 30.0), Pos2::new(60.0, 70.0)));
+
+    // Both together: a square centered on the anchor
+    let (from, to) = shapes::drag_points(
+        ShapeKind::Ellipse,
+        anchor,
+        Pos2::new(60.0, 70.0),
+        true,
+        true,
+    );
+    assert_eq!((from, to), (Pos2::new(30.0, 30.0), Pos2::new(70.0, 70.0)));
+}
+
+#[test]
+fn outlines_follow_the_shape() {
+    let stroke = Stroke::new(2.0, Color32::BLACK);
+    let from = Pos2::new(40.0, 30.0);
+    let to = Pos2::new(10.0, 10.0);
+
+    let rectangle = Shape::new(ShapeKind::Rectangle, stroke, from, to).outline();
+    assert_eq!(
+        rectangle,
+        vec![
+            Pos2::new(10.0, 10.0),
+            Pos2::new(40.0, 10.0),
+            Pos2::new(40.0, 30.0),
+            Pos2::new(10.0, 30.0),
+            Pos2::new(10.0, 10.0),
+        ]
+    );
+
+    let line = Shape::new(ShapeKind::Line, stroke, from, to).outline();
+    assert_eq!(line, vec![from, to]);
+
+    let ellipse = Shape::new(ShapeKind::Ellipse, stroke, from, to).outline();
+    assert!(
+        matches!((ellipse.first(), ellipse.last()), (Some(a), Some(b)) if close(*a, *b)),
+        "ellipse not closed"
+    );
+    for point in &ellipse {
+        // Normalized to the unit circle, every point lies on it
+        let x = (point.x - 25.0) / 15.0;
+        let y = (point.y - 20.0) / 10.0;
+        assert!((x.hypot(y) - 1.0).abs() < 1e-3, "{point:?} off the ellipse");
+    }
+}
@@ -0,0 +1,109 @@
+use eframe_template::SimplePaintApp;
+use eframe_template::draw::canvas::{Action, SingleStroke};
+use eframe_template::draw::simplify::{Reduction, simplify, tolerance};
+use egui::{Color32, Pos2, Stroke};
+
+// A straight run with tiny wobbles, then a sharp corner
+fn wobbly_corner() -> SingleStroke {
+    let mut points: Vec<Pos2> = (0..=10)
+        .map(|i| {
+            let wobble = if i % 2 == 0 { 0.1 } else { -0.1 };
+            Pos2::new(i as f32 * 10.0, wobble)
+        })
+        .collect();
+    points.push(Pos2::new(100.0, 100.0));
+
+    SingleStroke::new(Stroke::new(2.0, Color32::BLACK), points)
+}
+
+#[test]
+fn drops_points_within_tolerance() {
+    let (simplified, reduction) = simplify(&wobbly_corner(), 0.5);
+
+    assert_eq!(
+        simplified.points,
+        vec![
+            Pos2::new(0.0, 0.1),
+            Pos2::new(100.0, 0.1),
+            Pos2::new(100.0, 100.0)
+        ]
+    );
+    assert_eq!(
+        reduction,
+        Reduction {
+            before: 12,
+            after: 3
+        }
+    );
+    assert_eq!(reduction.removed(), 9);
+    assert_eq!(reduction.to_string(), "12 → 3 points (75% fewer)");
+}
+
+#[test]
+fn tight_tolerance_keeps_everything() {
+    let (simplified, reduction) = simplify(&wobbly_corner(), 0.01);
+
+    assert_eq!(simplified, wobbly_corner());
+    assert_eq!(reduction.removed(), 0);
+}
+
+#[test]
+fn tolerance_shrinks_when_zoomed_in() {
+    assert!(tolerance(4.0) < tolerance(1.0));
+    assert!(tolerance(0.25) > tolerance(1.0));
+}
+
+#[test]
+fn pressure_changes_are_kept() {
+    let mut stroke = SingleStroke::new(
+        Stroke::new(2.0, Color32::BLACK),
+        vec![
+            Pos2::new(0.0, 0.0),
+            Pos2::new(10.0, 0.0),
+            Pos2::new(20.0, 0.0),
+            Pos2::new(30.0, 0.0),
+        ],
+    );
+    stroke.widths = vec![2.0, 2.0, 8.0, 2.0];
+
+    let (simplified, _) = simplify(&stroke, 0.5);
+
+    assert_eq!(simplified.widths, vec![2.0, 2.0, 8.0, 2.0]);
+
+    stroke.widths = vec![2.0, 4.0, 6.0, 8.0];
+    let (simplified, _) = simplify(&stroke, 0.5);
+    assert_eq!(simplified.widths, vec![2.0, 8.0]);
+    assert_eq!(simplified.points.len(), 2);
+}
+
+#[test]
+fn simplify_all_is_one_undo_step() {
+    let mut app = SimplePaintApp::default();
+    for index in 0..2 {
+        app.run(Action::AddStroke {
+            stroke: wobbly_corner(),
+            index,
+            layer: 0,
+        });
+    }
+    app.canvas.set_zoom(1.0);
+
+    app.simplify_all();
+
+    assert!(
+        app.canvas
+            .strokes(0)
+            .is_some_and(|strokes| strokes.iter().all(|stroke| stroke.points.len() == 3))
+    );
+    assert!(
+        app.status
+            .as_ref()
+            .is_some_and(|status| status.contains("24 → 6"))
+    );
+
+    app.undo();
+    assert_eq!(
+        app.canvas.strokes(0),
+        Some(&vec![wobbly_corner(), wobbly_corner()])
+    );
+}
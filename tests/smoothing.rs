@@ -0,0 +1,72 @@
+use eframe_template::draw::canvas::SingleStroke;
+use eframe_template::draw::smoothing::{lazy_follow, smooth};
+use egui::{Color32, Pos2, Stroke};
+
+fn zigzag() -> SingleStroke {
+    SingleStroke::new(
+        Stroke::new(2.0, Color32::BLACK),
+        vec![
+            Pos2::new(0.0, 0.0),
+            Pos2::new(10.0, 10.0),
+            Pos2::new(20.0, 0.0),
+            Pos2::new(30.0, 10.0),
+        ],
+    )
+}
+
+#[test]
+fn lazy_pen_waits_inside_the_radius() {
+    let pen = Pos2::new(10.0, 10.0);
+
+    assert_eq!(lazy_follow(pen, Pos2::new(15.0, 10.0), 8.0), pen);
+    assert_eq!(
+        lazy_follow(pen, Pos2::new(30.0, 10.0), 8.0),
+        Pos2::new(22.0, 10.0)
+    );
+    assert_eq!(
+        lazy_follow(pen, Pos2::new(30.0, 10.0), 0.0),
+        Pos2::new(30.0, 10.0)
+    );
+}
+
+#[test]
+fn smoothing_keeps_the_end_points() {
+    let smoothed = smooth(&zigzag(), 2);
+
+    assert_eq!(smoothed.points.first(), Some(&Pos2::new(0.0, 0.0)));
+    assert_eq!(smoothed.points.last(), Some(&Pos2::new(30.0, 10.0)));
+    assert_eq!(smooth(&zigzag(), 1).points.len(), 8);
+    // The peak at (10, 10) is cut off
+    assert!(!smoothed.points.contains(&Pos2::new(10.0, 10.0)));
+    assert!(
+        smoothed
+            .points
+            .iter()
+            .filter(|point| point.x < 20.0)
+            .all(|point| point.y < 10.0)
+    );
+}
+
+#[test]
+fn smoothing_interpolates_widths() {
+    let mut stroke = zigzag();
+    stroke.widths = vec![1.0, 3.0, 5.0, 7.0];
+    let smoothed = smooth(&stroke, 1);
+
+    assert_eq!(smoothed.widths.len(), smoothed.points.len());
+    assert_eq!(
+        smoothed.widths,
+        vec![1.0, 1.5, 2.5, 3.5, 4.5, 5.5, 6.5, 7.0]
+    );
+}
+
+#[test]
+fn zero_passes_or_short_strokes_are_unchanged() {
+    assert_eq!(smooth(&zigzag(), 0), zigzag());
+
+    let line = SingleStroke::new(
+        Stroke::new(2.0, Color32::BLACK),
+        vec![Pos2::new(0.0, 0.0), Pos2::new(5.0, 5.0)],
+    );
+    assert_eq!(smooth(&line, 3), line);
+}
@@ -0,0 +1,210 @@
+use eframe_template::document::format::Document;
+use eframe_template::draw::canvas::{Action, Canvas, Layer, SingleStroke};
+use eframe_template::draw::eraser::{self, EraserMode};
+use eframe_template::draw::history::History;
+use eframe_template::draw::selection;
+use egui::{Color32, Pos2, Rect, Stroke, Vec2};
+use proptest::prelude::*;
+
+fn stroke(x: f32, y: f32) -> SingleStroke {
+    SingleStroke::new(
+        Stroke::new(2.0, Color32::BLACK),
+        vec![
+            Pos2::new(x, y),
+            Pos2::new(x + 30.0, y + 5.0),
+            Pos2::new(x + 40.0, y + 50.0),
+        ],
+    )
+}
+
+fn add(history: &mut History, canvas: &mut Canvas, stroke: SingleStroke, index: usize) {
+    history.execute(
+        Action::AddStroke {
+            stroke,
+            index,
+            layer: 0,
+        },
+        canvas,
+    );
+}
+
+// Eraser actions found through the index, compared with a scan over every stroke
+fn assert_eraser_agrees(canvas: &Canvas, pos: Pos2, radius: f32) {
+    let strokes = canvas.strokes(0).cloned().unwrap_or_default();
+    let reach = Rect::from_center_size(pos, Vec2::splat(radius * 2.0));
+    let candidates = canvas.strokes_in(0, reach);
+
+    let indexed =
+        eraser::erase_actions_among(EraserMode::Partial, &strokes, &candidates, 0, pos, radius);
+    let linear = eraser::erase_actions(EraserMode::Partial, &strokes, 0, pos, radius);
+
+    assert_eq!(
+        format!("{indexed:?}"),
+        format!("{linear:?}"),
+        "index and linear scan disagree at {pos:?}"
+    );
+}
+
+#[derive(Clone, Debug)]
+enum Op {
+    Add { position: usize, x: u8, y: u8 },
+    Remove { position: usize },
+    Modify { position: usize, x: u8, y: u8 },
+    Undo,
+    Redo,
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        (any::<usize>(), any::<u8>(), any::<u8>()).prop_map(|(position, x, y)| Op::Add {
+            position,
+            x,
+            y
+        }),
+        any::<usize>().prop_map(|position| Op::Remove { position }),
+        (any::<usize>(), any::<u8>(), any::<u8>()).prop_map(|(position, x, y)| Op::Modify {
+            position,
+            x,
+            y
+        }),
+        Just(Op::Undo),
+        Just(Op::Redo),
+    ]
+}
+
+proptest! {
+    // The index follows every edit, undo and redo
+    #[test]
+    fn index_matches_linear_scan(
+        ops in prop::collection::vec(op(), 0..48),
+        probes in prop::collection::vec((0.0_f32..300.0, 0.0_f32..300.0), 1..8),
+    ) {
+        let mut canvas = Canvas::new(Vec2::new(300.0, 300.0));
+        let mut history = History::default();
+
+        for op in &ops {
+            let len = canvas.strokes(0).map_or(0, Vec::len);
+            match *op {
+                Op::Add { position, x, y } => {
+                    let stroke = stroke(f32::from(x), f32::from(y));
+                    add(&mut history, &mut canvas, stroke, position % (len + 1));
+                }
+                Op::Remove { position } if len > 0 => {
+                    let index = position % len;
+                    let stroke = canvas.strokes(0).and_then(|s| s.get(index)).cloned();
+                    if let Some(stroke) = stroke {
+                        history.execute(
+                            Action::RemoveStroke {
+                                stroke,
+                                index,
+                                layer: 0,
+                            },
+                            &mut canvas,
+                        );
+                    }
+                }
+                Op::Modify { position, x, y } if len > 0 => {
+                    let index = position % len;
+                    let before = canvas.strokes(0).and_then(|s| s.get(index)).cloned();
+                    history.execute(
+                        Action::ModifyStroke {
+                            before,
+                            after: Some(stroke(f32::from(x), f32::from(y))),
+                            index,
+                            layer: 0,
+                        },
+                        &mut canvas,
+                    );
+                }
+                Op::Undo => history.undo(&mut canvas),
+                Op::Redo => history.redo(&mut canvas),
+                _ => {}
+            }
+
+            for (x, y) in &probes {
+                assert_eraser_agrees(&canvas, Pos2::new(*x, *y), 12.0);
+            }
+        }
+    }
+}
+
+#[test]
+fn finds_only_nearby_strokes() {
+    let mut canvas = Canvas::new(Vec2::new(1000.0, 1000.0));
+    let mut history = History::default();
+    add(&mut history, &mut canvas, stroke(10.0, 10.0), 0);
+    add(&mut history, &mut canvas, stroke(800.0, 800.0), 1);
+
+    let near_first = Rect::from_center_size(Pos2::new(20.0, 20.0), Vec2::splat(10.0));
+    assert_eq!(canvas.strokes_in(0, near_first), vec![0]);
+
+    let everywhere = Rect::from_min_max(Pos2::new(-1e6, -1e6), Pos2::new(1e6, 1e6));
+    assert_eq!(canvas.strokes_in(0, everywhere), vec![0, 1]);
+}
+
+#[test]
+fn marquee_through_index_matches_linear_pick() {
+    let mut canvas = Canvas::new(Vec2::new(1000.0, 1000.0));
+    let mut history = History::default();
+    for index in 0..20 {
+        let offset = index as f32 * 45.0;
+        add(
+            &mut history,
+            &mut canvas,
+            stroke(offset, offset / 2.0),
+            index,
+        );
+    }
+    let strokes = canvas.strokes(0).cloned().unwrap_or_default();
+    let rect = Rect::from_min_max(Pos2::new(100.0, 50.0), Pos2::new(400.0, 300.0));
+
+    assert_eq!(
+        selection::pick_in_rect_among(&strokes, canvas.strokes_in(0, rect), rect),
+        selection::pick_in_rect(&strokes, rect)
+    );
+}
+
+#[test]
+fn layer_changes_rebuild_the_index() {
+    let mut canvas = Canvas::new(Vec2::new(1000.0, 1000.0));
+    let mut history = History::default();
+    add(&mut history, &mut canvas, stroke(10.0, 10.0), 0);
+
+    let mut layer = Layer::new("Layer 2");
+    layer.strokes.push(stroke(500.0, 500.0));
+    history.execute(Action::AddLayer { layer, index: 0 }, &mut canvas);
+
+    let area = Rect::from_center_size(Pos2::new(510.0, 510.0), Vec2::splat(20.0));
+    assert_eq!(canvas.strokes_in(0, area), vec![0]);
+    assert!(canvas.strokes_in(1, area).is_empty());
+
+    history.undo(&mut canvas);
+    assert!(canvas.strokes_in(0, area).is_empty());
+}
+
+#[test]
+fn loaded_documents_are_indexed() {
+    let mut layer = Layer::new("Layer 1");
+    layer.strokes.push(stroke(500.0, 500.0));
+    let mut source = Canvas::new(Vec2::new(1000.0, 1000.0));
+    source.layers = vec![layer];
+
+    let canvas = Document::from_canvas(&source).into_canvas();
+    let far = Rect::from_center_size(Pos2::new(10.0, 10.0), Vec2::splat(20.0));
+
+    assert!(canvas.strokes_in(0, far).is_empty());
+}
+
+#[test]
+fn stale_index_falls_back_to_every_stroke() {
+    let mut canvas = Canvas::new(Vec2::new(1000.0, 1000.0));
+    if let Some(layer) = canvas.active_layer_mut() {
+        layer.strokes.push(stroke(500.0, 500.0));
+    }
+    let far = Rect::from_center_size(Pos2::new(10.0, 10.0), Vec2::splat(20.0));
+
+    assert_eq!(canvas.strokes_in(0, far), vec![0]);
+
+    canvas.rebuild_caches();
+    assert!(canvas.strokes_in(0, far).is_empty());
+}
@@ -0,0 +1,111 @@
+use eframe_template::draw::canvas::{Action, Canvas, Layer, SingleStroke};
+use eframe_template::draw::shapes::{Shape, ShapeKind};
+use eframe_template::export::svg::canvas_to_svg;
+use egui::{Color32, Pos2, Stroke, Vec2};
+
+#[test]
+fn exports_strokes_as_paths() {
+    let mut canvas = Canvas::new(Vec2::new(200.0, 100.0));
+    let stroke = SingleStroke::new(
+        Stroke::new(4.0, Color32::from_rgb(255, 0, 0)),
+        vec![
+            Pos2::new(10.0, 10.0),
+            Pos2::new(20.0, 20.0),
+            Pos2::new(30.0, 10.0),
+        ],
+    );
+    Action::AddStroke {
+        stroke,
+        index: 0,
+        layer: 0,
+    }
+    .execute(&mut canvas);
+
+    let svg = canvas_to_svg(&canvas);
+
+    assert!(svg.contains(r#"viewBox="0 0 200 100""#), "{svg}");
+    assert!(svg.contains(r##"fill="#ffffff""##), "{svg}");
+    assert!(svg.contains(r#"d="M10 10 L20 20 L30 10""#), "{svg}");
+    assert!(svg.contains(r##"stroke="#ff0000""##), "{svg}");
+    assert!(svg.contains(r#"stroke-width="4""#), "{svg}");
+}
+
+#[test]
+fn skips_hidden_layers() {
+    let mut canvas = Canvas::new(Vec2::new(200.0, 100.0));
+    let stroke = SingleStroke::new(
+        Stroke::new(1.0, Color32::BLACK),
+        vec![Pos2::new(0.0, 0.0), Pos2::new(5.0, 5.0)],
+    );
+    Action::AddStroke {
+        stroke,
+        index: 0,
+        layer: 0,
+    }
+    .execute(&mut canvas);
+    if let Some(layer) = canvas.active_layer_mut() {
+        layer.visible = false;
+    }
+
+    assert!(!canvas_to_svg(&canvas).contains("<path"));
+}
+
+#[test]
+fn layer_groups_get_unique_ids() {
+    let mut canvas = Canvas::new(Vec2::new(200.0, 100.0));
+    canvas.layers.push(Layer::new("Layer 1"));
+    canvas.layers.push(Layer::new("2 <inked>"));
+
+    let svg = canvas_to_svg(&canvas);
+
+    assert!(
+        svg.contains(r#"<g id="layer-1" data-name="Layer 1""#),
+        "{svg}"
+    );
+    assert!(
+        svg.contains(r#"<g id="layer-2" data-name="Layer 1""#),
+        "{svg}"
+    );
+    assert!(
+        svg.contains(r#"<g id="layer-3" data-name="2 &lt;inked&gt;""#),
+        "{svg}"
+    );
+}
+
+#[test]
+fn layer_opacity_fades_each_object() {
+    let mut canvas = Canvas::new(Vec2::new(200.0, 100.0));
+    let stroke = SingleStroke::new(
+        Stroke::new(4.0, Color32::BLACK),
+        vec![Pos2::new(10.0, 10.0), Pos2::new(90.0, 10.0)],
+    );
+    Action::AddStroke {
+        stroke,
+        index: 0,
+        layer: 0,
+    }
+    .execute(&mut canvas);
+    Action::AddShape {
+        shape: Shape::new(
+            ShapeKind::Line,
+            Stroke::new(4.0, Color32::BLACK),
+            Pos2::new(10.0, 50.0),
+            Pos2::new(90.0, 50.0),
+        ),
+        index: 0,
+        layer: 0,
+    }
+    .execute(&mut canvas);
+    if let Some(layer) = canvas.active_layer_mut() {
+        layer.opacity = 0.4;
+    }
+
+    let svg = canvas_to_svg(&canvas);
+
+    // Overlapping objects show through each other, as on the canvas, rather than the
+    // whole group fading at once
+    assert!(!svg.contains(" opacity="), "{svg}");
+    assert_eq!(svg.matches(r#"stroke-opacity="0.4""#).count(), 2, "{svg}");
+    // Lines end flat, as they are painted
+    assert!(svg.contains(r#"stroke-linecap="butt""#), "{svg}");
+}
@@ -0,0 +1,170 @@
+use eframe_template::document::file;
+use eframe_template::draw::canvas::{Action, Canvas, Layer};
+use eframe_template::draw::history::History;
+use eframe_template::draw::text::{Editing, TextAlign, TextBox};
+use eframe_template::export::{raster, svg};
+use egui::{Color32, Pos2, Vec2};
+
+fn label(text: &str) -> TextBox {
+    TextBox::new(
+        text,
+        Pos2::new(40.0, 30.0),
+        40.0,
+        Color32::BLACK,
+        TextAlign::Left,
+    )
+}
+
+fn editing(index: Option<usize>, text: TextBox) -> Editing {
+    Editing {
+        layer: 0,
+        index,
+        text,
+    }
+}
+
+fn texts(canvas: &Canvas) -> Vec<String> {
+    canvas
+        .texts(0)
+        .map(|texts| texts.iter().map(|text| text.text.clone()).collect())
+        .unwrap_or_default()
+}
+
+#[test]
+fn editing_records_add_modify_and_remove() {
+    let mut canvas = Canvas::new(Vec2::new(400.0, 200.0));
+    let mut history = History::default();
+
+    let add = editing(None, label("Hello")).action(&[]);
+    history.execute(add.expect("new text is added"), &mut canvas);
+    assert_eq!(texts(&canvas), vec!["Hello"]);
+
+    let mut changed = label("Hello\nworld");
+    changed.align = TextAlign::Center;
+    let modify = editing(Some(0), changed).action(canvas.texts(0).expect("layer exists"));
+    history.execute(modify.expect("edit is recorded"), &mut canvas);
+    assert_eq!(texts(&canvas), vec!["Hello\nworld"]);
+
+    let remove = editing(Some(0), label("  ")).action(canvas.texts(0).expect("layer exists"));
+    history.execute(remove.expect("emptied text is removed"), &mut canvas);
+    assert!(texts(&canvas).is_empty());
+
+    history.undo(&mut canvas);
+    assert_eq!(
+        canvas
+            .texts(0)
+            .and_then(|texts| texts.first())
+            .map(|text| text.align),
+        Some(TextAlign::Center)
+    );
+    history.undo(&mut canvas);
+    assert_eq!(texts(&canvas), vec!["Hello"]);
+    history.undo(&mut canvas);
+    assert!(texts(&canvas).is_empty());
+}
+
+#[test]
+fn blank_or_unchanged_edits_record_nothing() {
+    assert!(editing(None, label("")).action(&[]).is_none());
+    assert!(editing(None, label(" \n ")).action(&[]).is_none());
+    assert!(
+        editing(Some(0), label("Same"))
+            .action(&[label("Same")])
+            .is_none()
+    );
+    // The text went away in the meantime
+    assert!(editing(Some(3), label("Gone")).action(&[]).is_none());
+}
+
+#[test]
+fn texts_survive_save_and_merge_down() {
+    let mut canvas = Canvas::new(Vec2::new(400.0, 200.0));
+    let mut history = History::default();
+    let mut upper = Layer::new("Labels");
+    upper.opacity = 0.5;
+    upper.texts.push(label("Note"));
+    history.execute(
+        Action::AddLayer {
+            layer: upper,
+            index: 1,
+        },
+        &mut canvas,
+    );
+
+    let path = std::env::temp_dir().join(format!("simple_paint_{}_text.json", std::process::id()));
+    assert!(file::save(&path, &canvas).is_ok(), "save failed");
+    let opened = file::open(&path).expect("document opens");
+    assert_eq!(opened.texts(1), canvas.texts(1));
+
+    let upper = canvas.layers.get(1).cloned().expect("layer was added");
+    let lower = canvas.layers.first().cloned().expect("layer exists");
+    history.execute(
+        Action::MergeDown {
+            upper,
+            lower,
+            index: 1,
+        },
+        &mut canvas,
+    );
+    assert_eq!(texts(&canvas), vec!["Note"]);
+    assert_eq!(
+        canvas
+            .texts(0)
+            .and_then(|texts| texts.first())
+            .map(|text| text.color),
+        Some(Color32::BLACK.gamma_multiply(0.5))
+    );
+
+    history.undo(&mut canvas);
+    assert!(texts(&canvas).is_empty());
+    assert_eq!(canvas.texts(1).map(Vec::len), Some(1));
+}
+
+#[test]
+fn svg_export_writes_one_line_per_tspan() {
+    let mut canvas = Canvas::new(Vec2::new(400.0, 200.0));
+    let mut text = label("a < b\nc & d");
+    text.align = TextAlign::Right;
+    if let Some(layer) = canvas.layers.first_mut() {
+        layer.texts.push(text);
+    }
+
+    let svg = svg::canvas_to_svg(&canvas);
+    assert!(svg.contains(r#"text-anchor="end""#), "{svg}");
+    assert!(svg.contains(r#"font-size="40""#), "{svg}");
+    assert!(svg.contains(">a &lt; b</tspan>"), "{svg}");
+    assert!(svg.contains(">c &amp; d</tspan>"), "{svg}");
+    assert_eq!(svg.matches("<tspan").count(), 2);
+    // Lines sit one line height apart
+    assert!(svg.contains(r#"y="67.2""#), "{svg}");
+    assert!(svg.contains(r#"y="117.2""#), "{svg}");
+}
+
+#[test]
+fn png_export_draws_glyphs_where_the_text_is() {
+    let mut canvas = Canvas::new(Vec2::new(400.0, 200.0));
+    let mut text = label("HHHH");
+    text.pos = Pos2::new(200.0, 30.0);
+    text.align = TextAlign::Center;
+    if let Some(layer) = canvas.layers.first_mut() {
+        layer.texts.push(text);
+    }
+
+    let pixmap = raster::rasterize(&canvas, 2.0);
+    let dark = |x: u32, y: u32| pixmap.pixel(x, y).is_some_and(|pixel| pixel.r() < 128);
+    let inked: Vec<(u32, u32)> = (0..pixmap.height)
+        .flat_map(|y| (0..pixmap.width).map(move |x| (x, y)))
+        .filter(|(x, y)| dark(*x, *y))
+        .collect();
+
+    assert!(!inked.is_empty(), "no text was drawn");
+    // Centered on x = 200, within the first line below y = 30, at twice the size
+    assert!(inked.iter().any(|(x, _)| *x < 400), "text not centered");
+    assert!(inked.iter().any(|(x, _)| *x > 400), "text not centered");
+    assert!(
+        inked
+            .iter()
+            .all(|(x, y)| (200..600).contains(x) && (60..160).contains(y)),
+        "ink outside of the text"
+    );
+}